@@ -1,5 +1,8 @@
-use embedded_hal::i2c::I2c;
-use std::{thread, time::Duration};
+use crate::i2c::lock::{BusLock, LockError, LockedI2c};
+use crate::sensor::{Measurement, Quality, Quantity, Sensor};
+use crate::units::Unit;
+use embedded_hal::i2c::{ErrorType, I2c};
+use std::{io, thread, time::Duration};
 
 /// I2C addresses
 pub const ADS1115_ADDR_A: u8 = 0x48;
@@ -64,21 +67,69 @@ pub fn pga_to_voltage(pga: Pga) -> f32 {
     }
 }
 
+/// PGA whose full-scale range is exactly `volts`, e.g. 4.096
+pub fn pga_from_voltage(volts: f32) -> Option<Pga> {
+    [
+        Pga::Gain0_256V,
+        Pga::Gain0_512V,
+        Pga::Gain1_024V,
+        Pga::Gain2_048V,
+        Pga::Gain4_096V,
+        Pga::Gain6_144V,
+    ]
+    .into_iter()
+    .find(|&pga| (pga_to_voltage(pga) - volts).abs() < 1e-4)
+}
+
+/// Single-ended input for channel 0-3 (AINx against GND)
+pub fn mux_for_channel(channel: u8) -> Option<Mux> {
+    match channel {
+        0 => Some(Mux::Ain0Gnd),
+        1 => Some(Mux::Ain1Gnd),
+        2 => Some(Mux::Ain2Gnd),
+        3 => Some(Mux::Ain3Gnd),
+        _ => None,
+    }
+}
+
+/// Smallest PGA range that still covers `volts` (6.144V if none does)
+pub fn pga_for_voltage(volts: f32) -> Pga {
+    [
+        Pga::Gain0_256V,
+        Pga::Gain0_512V,
+        Pga::Gain1_024V,
+        Pga::Gain2_048V,
+        Pga::Gain4_096V,
+    ]
+    .into_iter()
+    .find(|&pga| pga_to_voltage(pga) >= volts)
+    .unwrap_or(Pga::Gain6_144V)
+}
+
 /// Converts raw ADC value to voltage using the given gain voltage range
 pub fn adc_to_voltage(raw: i16, gain_volts: f32) -> f32 {
     (raw as f32) * gain_volts / 32768.0
 }
 
+/// Whether `volts` sits at the highest or lowest code of `full_scale`
+pub fn is_clipped(volts: f32, full_scale: f32) -> bool {
+    volts.abs() >= full_scale * 32767.0 / 32768.0
+}
+
+/// Bus lock held across conversions, and how its failures are reported
+type ConversionLock<E> = (BusLock, fn(io::Error) -> E);
+
 /// ADS1115 driver using embedded-hal I2C
-pub struct AdsSensor<I2C> {
+pub struct AdsSensor<I2C: ErrorType> {
     i2c: I2C,
     addr: u8,
     mux: Mux,
     pga: Pga,
     mode: Mode,
     dr: DataRate,
-    pub name: &'static str,  // sensor friendly name
-    pub units: &'static str, // units of measurement, e.g. "Celsius"
+    bus_lock: Option<ConversionLock<I2C::Error>>,
+    pub name: String, // sensor friendly name
+    pub units: Unit,  // unit of the converted reading, e.g. Celsius
 }
 
 impl<I2C, E> AdsSensor<I2C>
@@ -88,14 +139,7 @@ where
     /// Create new AdsSensor instance with fixed data rate 128 SPS
     /// Create new AdsSensor instance with fixed data rate 128 SPS,
     /// including sensor name and units
-    pub fn new(
-        i2c: I2C,
-        addr: u8,
-        mux: Mux,
-        pga: Pga,
-        name: &'static str,
-        units: &'static str,
-    ) -> Result<Self, E> {
+    pub fn new(i2c: I2C, addr: u8, mux: Mux, pga: Pga, name: &str, units: Unit) -> Result<Self, E> {
         Ok(Self {
             i2c,
             addr,
@@ -103,11 +147,17 @@ where
             pga,
             mode: Mode::SingleShot,
             dr: DataRate::Sps128,
-            name,
+            bus_lock: None,
+            name: name.to_string(),
             units,
         })
     }
 
+    /// Full-scale voltage of the configured PGA
+    pub fn full_scale(&self) -> f32 {
+        pga_to_voltage(self.pga)
+    }
+
     /// Build configuration bytes to write to ADS1115 config register
     fn build_config_bytes(&self) -> [u8; 3] {
         const OS_SINGLE_CONVERSION: u8 = 0b1000_0000; // bit 15 (MSB bit 7)
@@ -118,6 +168,11 @@ where
 
     /// Perform a single-shot conversion and return voltage reading in volts
     pub fn get_voltage(&mut self) -> Result<f32, E> {
+        let _guard = match &self.bus_lock {
+            Some((lock, lock_error)) => Some(lock.hold().map_err(lock_error)?),
+            None => None,
+        };
+
         let config = self.build_config_bytes();
         self.i2c.write(self.addr, &config)?;
 
@@ -141,3 +196,38 @@ where
         self.i2c
     }
 }
+
+impl<I2C: I2c> AdsSensor<LockedI2c<I2C>> {
+    /// Hold the bus lock for the whole write-config / wait / read sequence
+    /// of `get_voltage`, so other processes cannot interleave with it
+    pub fn with_bus_lock(mut self) -> Self {
+        self.bus_lock = Some((self.i2c.lock(), LockError::Lock));
+        self
+    }
+}
+
+impl<I2C, E> Sensor for AdsSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    fn id(&self) -> &str {
+        &self.name
+    }
+
+    fn quantity(&self) -> Quantity {
+        Quantity::Voltage
+    }
+
+    /// Raw channel voltage, flagged when clipped by the PGA
+    fn measure(&mut self) -> Result<Measurement, E> {
+        let voltage = self.get_voltage()?;
+        let quality = if is_clipped(voltage, self.full_scale()) {
+            Quality::CLIPPED
+        } else {
+            Quality::GOOD
+        };
+        Ok(Measurement::new(&self.name, Quantity::Voltage, voltage).with_quality(quality))
+    }
+}
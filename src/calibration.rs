@@ -0,0 +1,377 @@
+use crate::ec::EcMeter;
+use crate::ph::{CalibrationPoint, PhCalibration, REFERENCE_TEMPERATURE};
+use crate::units::{Conductivity, Ph, Temperature, Voltage};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Where calibrations are kept unless the config says otherwise
+pub const DEFAULT_STORE_FILE: &str = "hydro-sense-calibration.json";
+
+/// Calibrations older than this are flagged for redoing (30 days)
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Calibrations kept per probe, including the current one
+pub const DEFAULT_HISTORY_LIMIT: usize = 10;
+
+/// Why the calibration store could not be read or written
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    /// The file is not a valid calibration store
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "calibration store I/O failed: {}", e),
+            StoreError::Parse(e) => write!(f, "invalid calibration store: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+/// One buffer or standard solution and the voltage read in it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReferencePoint {
+    /// Buffer pH or standard conductivity (mS/cm at 25 °C)
+    pub reference: f32,
+    /// Probe voltage in the solution (volts)
+    pub voltage: f32,
+}
+
+/// Fitted coefficients, enough to rebuild the probe calibration
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Coefficients {
+    /// `PhCalibration`: anchor buffer and the slopes either side (V/pH)
+    Ph {
+        anchor_ph: f32,
+        anchor_voltage: f32,
+        acid_slope: f32,
+        base_slope: f32,
+    },
+    /// `EcMeter` fit: cell constant K (1/cm) and zero offset (mS/cm)
+    Ec { k: f32, zero_offset: f32 },
+}
+
+/// One completed calibration of one probe
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRecord {
+    /// When the calibration was made, stored as Unix seconds
+    #[serde(with = "unix_seconds")]
+    pub timestamp: SystemTime,
+    /// Solution temperature during calibration (°C)
+    pub temperature: f32,
+    pub points: Vec<ReferencePoint>,
+    pub coefficients: Coefficients,
+}
+
+impl CalibrationRecord {
+    /// Record of a pH calibration made from `points`
+    pub fn ph(calibration: &PhCalibration, points: &[CalibrationPoint]) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            temperature: calibration.temperature.celsius(),
+            points: points
+                .iter()
+                .map(|p| ReferencePoint {
+                    reference: p.ph.value(),
+                    voltage: p.voltage.volts(),
+                })
+                .collect(),
+            coefficients: Coefficients::Ph {
+                anchor_ph: calibration.anchor.ph.value(),
+                anchor_voltage: calibration.anchor.voltage.volts(),
+                acid_slope: calibration.acid_slope,
+                base_slope: calibration.base_slope,
+            },
+        }
+    }
+
+    /// Record of the fit of `meter`, made from (standard, voltage) pairs
+    /// with the solutions at `temperature`
+    pub fn ec(
+        meter: &EcMeter,
+        points: &[(Conductivity, Voltage)],
+        temperature: Temperature,
+    ) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            temperature: temperature.celsius(),
+            points: points
+                .iter()
+                .map(|(standard, voltage)| ReferencePoint {
+                    reference: standard.ms_per_cm(),
+                    voltage: voltage.volts(),
+                })
+                .collect(),
+            coefficients: Coefficients::Ec {
+                k: meter.k,
+                zero_offset: meter.zero_offset,
+            },
+        }
+    }
+
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// The stored pH calibration, if this is one
+    pub fn ph_calibration(&self) -> Option<PhCalibration> {
+        match self.coefficients {
+            Coefficients::Ph {
+                anchor_ph,
+                anchor_voltage,
+                acid_slope,
+                base_slope,
+            } => Some(PhCalibration {
+                anchor: CalibrationPoint {
+                    ph: Ph::new(anchor_ph),
+                    voltage: Voltage::from_volts(anchor_voltage),
+                },
+                acid_slope,
+                base_slope,
+                temperature: Temperature::from_celsius(self.temperature),
+            }),
+            Coefficients::Ec { .. } => None,
+        }
+    }
+
+    /// `meter` with the stored EC fit, if this is one
+    pub fn apply_to_ec(&self, meter: EcMeter) -> Option<EcMeter> {
+        match self.coefficients {
+            Coefficients::Ec { k, zero_offset } => Some(EcMeter {
+                k,
+                zero_offset,
+                ..meter
+            }),
+            Coefficients::Ph { .. } => None,
+        }
+    }
+
+    /// Resulting slope: V/pH at 25 °C for pH, cell constant K for EC
+    pub fn slope(&self) -> f32 {
+        match self.coefficients {
+            Coefficients::Ph { .. } => self.ph_calibration().map_or(f32::NAN, |c| c.slope_25c()),
+            Coefficients::Ec { k, .. } => k,
+        }
+    }
+
+    /// Resulting offset: pH 7 voltage at 25 °C for pH, zero offset
+    /// (mS/cm) for EC
+    pub fn offset(&self) -> f32 {
+        match self.coefficients {
+            Coefficients::Ph { .. } => self.ph_calibration().map_or(f32::NAN, |c| {
+                c.neutral_voltage(Temperature::from_celsius(REFERENCE_TEMPERATURE))
+                    .volts()
+            }),
+            Coefficients::Ec { zero_offset, .. } => zero_offset,
+        }
+    }
+
+    /// Time since the calibration; zero if the clock went backwards
+    pub fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.timestamp).unwrap_or_default()
+    }
+}
+
+/// Whether a probe's calibration can still be trusted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalibrationStatus {
+    /// The probe has never been calibrated
+    Missing,
+    Current {
+        age: Duration,
+    },
+    /// Older than the store's maximum age; recalibrate
+    Expired {
+        age: Duration,
+    },
+}
+
+impl CalibrationStatus {
+    pub fn needs_calibration(&self) -> bool {
+        !matches!(self, CalibrationStatus::Current { .. })
+    }
+}
+
+impl fmt::Display for CalibrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = |age: &Duration| age.as_secs() / (24 * 60 * 60);
+        match self {
+            CalibrationStatus::Missing => write!(f, "never calibrated"),
+            CalibrationStatus::Current { age } => write!(f, "calibrated {} days ago", days(age)),
+            CalibrationStatus::Expired { age } => {
+                write!(f, "calibration expired ({} days old)", days(age))
+            }
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    /// History per probe key, oldest first
+    probes: BTreeMap<String, Vec<CalibrationRecord>>,
+}
+
+/// Calibration history of every probe, kept in a JSON file.
+///
+/// Probes are keyed by the `calibration` name in the config (e.g. "ph").
+/// Each new calibration is appended; the newest is the current one and
+/// the older ones stay available for `rollback`.
+pub struct CalibrationStore {
+    path: PathBuf,
+    probes: BTreeMap<String, Vec<CalibrationRecord>>,
+    max_age: Duration,
+    history_limit: usize,
+}
+
+impl CalibrationStore {
+    /// Load the store at `path`; a missing file gives an empty store
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        let path = path.as_ref().to_path_buf();
+        let file = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(StoreError::Parse)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => StoreFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            probes: file.probes,
+            max_age: DEFAULT_MAX_AGE,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        })
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Calibrations kept per probe (at least one)
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Keys of all calibrated probes
+    pub fn probes(&self) -> impl Iterator<Item = &str> {
+        self.probes.keys().map(String::as_str)
+    }
+
+    /// Calibration in use for `probe`
+    pub fn current(&self, probe: &str) -> Option<&CalibrationRecord> {
+        self.history(probe).last()
+    }
+
+    /// All kept calibrations of `probe`, oldest first
+    pub fn history(&self, probe: &str) -> &[CalibrationRecord] {
+        self.probes.get(probe).map_or(&[], Vec::as_slice)
+    }
+
+    /// Make `record` the current calibration of `probe`, dropping the
+    /// oldest ones beyond the history limit
+    pub fn record(&mut self, probe: &str, record: CalibrationRecord) {
+        let history = self.probes.entry(probe.to_string()).or_default();
+        history.push(record);
+        let excess = history.len().saturating_sub(self.history_limit);
+        history.drain(..excess);
+    }
+
+    /// Discard the current calibration of `probe` and go back to the
+    /// previous one, which is returned. The last remaining calibration
+    /// is never discarded.
+    pub fn rollback(&mut self, probe: &str) -> Option<&CalibrationRecord> {
+        let history = self.probes.get_mut(probe)?;
+        if history.len() < 2 {
+            return None;
+        }
+        history.pop();
+        history.last()
+    }
+
+    /// Age check of `probe` against the maximum age
+    pub fn status(&self, probe: &str, now: SystemTime) -> CalibrationStatus {
+        match self.current(probe) {
+            None => CalibrationStatus::Missing,
+            Some(record) => {
+                let age = record.age(now);
+                if age > self.max_age {
+                    CalibrationStatus::Expired { age }
+                } else {
+                    CalibrationStatus::Current { age }
+                }
+            }
+        }
+    }
+
+    /// Write the store back to its file. The new contents are synced to
+    /// disk before they replace the file in one step, so a crash mid-write
+    /// cannot lose the history.
+    pub fn save(&self) -> Result<(), StoreError> {
+        let file = StoreFile {
+            probes: self.probes.clone(),
+        };
+        let text = serde_json::to_string_pretty(&file).map_err(StoreError::Parse)?;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut out = File::create(&tmp)?;
+        out.write_all(text.as_bytes())?;
+        out.write_all(b"\n")?;
+        out.sync_all()?;
+        drop(out);
+        fs::rename(&tmp, &self.path)?;
+
+        // Make the rename itself durable
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// `SystemTime` as whole seconds since the Unix epoch
+mod unix_seconds {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        seconds.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let seconds = u64::deserialize(deserializer)?;
+        Ok(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+}
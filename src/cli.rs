@@ -0,0 +1,501 @@
+use crate::config::{AdapterConfig, Config, ConfigError, DeviceConfig, DeviceKind};
+use crate::df0991::GeneralRGBColor;
+use crate::i2c::{AdapterInfo, SCAN_ADDRESSES};
+use crate::ph::{CalibrationPoint, Ph4502c};
+use crate::sensor::{Measurement, Quality, Quantity};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::{
+    fmt,
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
+};
+
+/// Command line of the `hydro-sense` binary
+#[derive(Clone, Debug, Parser)]
+#[command(name = "hydro-sense", version, about = "Hydroponics sensor monitor")]
+pub struct Cli {
+    /// Configuration file [default: hydro-sense.toml if present, else the
+    /// stock setup]
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Subcommand to run; `monitor` when none is given
+    pub fn command(&self) -> Command {
+        self.command.clone().unwrap_or(Command::Monitor {
+            bus: BusArgs::default(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Subcommand)]
+pub enum Command {
+    /// List the I2C adapters of this machine
+    Adapters,
+    /// Probe a bus for devices (only `--address`, if given)
+    Scan {
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+    /// Take one reading from configured sensors; the bus options move
+    /// their ADS1115
+    Read {
+        /// Sensor names [default: all sensors]
+        sensors: Vec<String>,
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+    /// Read the sensors continuously; a long button press starts the pH
+    /// calibration. The bus options move the ADS1115, the adapter option
+    /// also the button.
+    Monitor {
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+    /// Run the guided pH calibration on the RGB button
+    Calibrate {
+        /// pH sensor [default: the first one configured]
+        sensor: Option<String>,
+        /// Buffers in the order they are asked for
+        #[arg(long, value_delimiter = ',', default_values_t = [7.0, 4.0])]
+        buffers: Vec<f32>,
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+    /// Set the RGB button colour
+    Led {
+        /// red, orange, yellow, green, cyan, blue, purple, white, or
+        /// black/off
+        #[arg(value_parser = parse_color)]
+        color: GeneralRGBColor,
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+    /// Test the OLED: light every pixel, then show a line of text
+    Display {
+        /// Seconds each test pattern stays up
+        #[arg(long, default_value = "2", value_parser = parse_seconds)]
+        hold: Duration,
+        #[command(flatten)]
+        bus: BusArgs,
+    },
+}
+
+/// Output of the commands
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON document per line
+    Json,
+}
+
+impl Format {
+    pub fn render<T: Serialize + fmt::Display>(&self, report: &T) -> String {
+        match self {
+            Format::Text => report.to_string(),
+            Format::Json => serde_json::to_string(report).expect("serialisable report"),
+        }
+    }
+}
+
+/// Adapter and address options shared by the commands
+#[derive(Clone, Debug, Default, PartialEq, Args)]
+pub struct BusArgs {
+    /// Configured adapter name, or part of the kernel's adapter name
+    /// (e.g. "MCP2221")
+    #[arg(short, long)]
+    pub adapter: Option<String>,
+
+    /// I2C address, e.g. 0x2A
+    #[arg(short = 'A', long, value_parser = parse_address)]
+    pub address: Option<u8>,
+}
+
+/// Adapter and address a command talks to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub adapter: AdapterConfig,
+    pub address: u8,
+    /// Configured device at the address, if any
+    pub device: Option<String>,
+}
+
+impl BusArgs {
+    /// Where to find a `kind` device: the options override the first
+    /// configured device of that kind, `default_address` is used without
+    /// either. `None` if no adapter is given or configured.
+    pub fn target(&self, config: &Config, kind: DeviceKind, default_address: u8) -> Option<Target> {
+        let device = config.first_device(kind);
+        let adapter = self.adapter(config, device)?;
+        let address = self
+            .address
+            .or(device.map(|d| d.address))
+            .unwrap_or(default_address);
+        let device = config
+            .devices
+            .iter()
+            .find(|d| d.adapter == adapter.name && d.address == address)
+            .map(|d| d.name.clone());
+        Some(Target {
+            adapter,
+            address,
+            device,
+        })
+    }
+
+    /// Adapter from the options, else the one of `device`, else the first
+    /// configured one
+    pub fn adapter(&self, config: &Config, device: Option<&DeviceConfig>) -> Option<AdapterConfig> {
+        match &self.adapter {
+            Some(name) => Some(named_adapter(config, name)),
+            None => device
+                .and_then(|d| config.device_adapter(d))
+                .or(config.adapters.first())
+                .cloned(),
+        }
+    }
+
+    /// Point the configured `device` at the adapter and address from the
+    /// options, e.g. before building the sensors on it. The changed
+    /// config is validated again, so an override cannot move a device
+    /// onto an address it cannot have or that another device uses.
+    pub fn apply(&self, config: &mut Config, device: &str) -> Result<(), ConfigError> {
+        let adapter = self
+            .adapter
+            .as_ref()
+            .map(|name| named_adapter(config, name));
+        if let Some(adapter) = &adapter {
+            if config.adapter(&adapter.name).is_none() {
+                config.adapters.push(adapter.clone());
+            }
+        }
+        if let Some(device) = config.devices.iter_mut().find(|d| d.name == device) {
+            if let Some(adapter) = adapter {
+                device.adapter = adapter.name;
+            }
+            if let Some(address) = self.address {
+                device.address = address;
+            }
+        }
+        config.validate()
+    }
+}
+
+/// Configured adapter `name`, or an unconfigured one found by its sysfs
+/// name
+fn named_adapter(config: &Config, name: &str) -> AdapterConfig {
+    config.adapter(name).cloned().unwrap_or(AdapterConfig {
+        name: name.to_string(),
+        sysfs_name: name.to_string(),
+        lock_file: None,
+    })
+}
+
+/// I2C address in hex (`0x48`) or decimal (`72`), within the 7-bit range
+/// devices can use
+pub fn parse_address(text: &str) -> Result<u8, String> {
+    let parsed = match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    match parsed {
+        Ok(address) if SCAN_ADDRESSES.contains(&address) => Ok(address),
+        _ => Err(format!(
+            "expected an address from 0x{:02X} to 0x{:02X}",
+            SCAN_ADDRESSES.start(),
+            SCAN_ADDRESSES.end()
+        )),
+    }
+}
+
+/// Non-negative, finite number of seconds, e.g. `0.5`
+pub fn parse_seconds(text: &str) -> Result<Duration, String> {
+    text.parse::<f32>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f32(secs).ok())
+        .ok_or_else(|| format!("expected a number of seconds, got '{}'", text))
+}
+
+/// Button colour by name; "off" is black
+pub fn parse_color(text: &str) -> Result<GeneralRGBColor, String> {
+    let color = match text.to_ascii_lowercase().as_str() {
+        "red" => GeneralRGBColor::Red,
+        "orange" => GeneralRGBColor::Orange,
+        "yellow" => GeneralRGBColor::Yellow,
+        "green" => GeneralRGBColor::Green,
+        "cyan" => GeneralRGBColor::Cyan,
+        "blue" => GeneralRGBColor::Blue,
+        "purple" => GeneralRGBColor::Purple,
+        "white" => GeneralRGBColor::White,
+        "black" | "off" => GeneralRGBColor::Black,
+        _ => return Err(format!("unknown colour '{}'", text)),
+    };
+    Ok(color)
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                         Reports                              │
+// │                                                              │
+// │ What the commands print. Each report is one line (or a few   │
+// │ aligned lines) of text, or one JSON document with the same   │
+// │ content.                                                     │
+// └──────────────────────────────────────────────────────────────┘
+
+/// `adapters`: the machine's adapters, with the configured name if any
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct AdapterList(pub Vec<AdapterEntry>);
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AdapterEntry {
+    pub device: String,
+    pub name: String,
+    pub configured: Option<String>,
+}
+
+impl AdapterList {
+    pub fn new(adapters: Vec<AdapterInfo>, config: &Config) -> Self {
+        let entries = adapters
+            .into_iter()
+            .map(|info| AdapterEntry {
+                configured: config
+                    .adapters
+                    .iter()
+                    .find(|a| info.name.contains(&a.sysfs_name))
+                    .map(|a| a.name.clone()),
+                device: info.device,
+                name: info.name,
+            })
+            .collect();
+        Self(entries)
+    }
+}
+
+impl fmt::Display for AdapterList {
+    /// e.g. `/dev/i2c-7   MCP2221 usb-i2c bridge (mcp2221)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "No I2C adapters found");
+        }
+        for (i, entry) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:<12} {}", entry.device, entry.name)?;
+            if let Some(name) = &entry.configured {
+                write!(f, " ({})", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `scan`: the addresses that answered on one adapter
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScanReport {
+    pub adapter: String,
+    pub location: String,
+    pub devices: Vec<ScanEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScanEntry {
+    pub address: u8,
+    /// Configured device at the address
+    pub device: Option<String>,
+}
+
+impl ScanReport {
+    /// Name the `found` addresses after the devices configured on `adapter`
+    pub fn new(adapter: &str, location: &str, found: &[u8], config: &Config) -> Self {
+        let devices = found
+            .iter()
+            .map(|&address| ScanEntry {
+                address,
+                device: config
+                    .devices
+                    .iter()
+                    .find(|d| d.adapter == adapter && d.address == address)
+                    .map(|d| d.name.clone()),
+            })
+            .collect();
+        Self {
+            adapter: adapter.to_string(),
+            location: location.to_string(),
+            devices,
+        }
+    }
+}
+
+impl fmt::Display for ScanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} device(s)",
+            self.adapter,
+            self.location,
+            self.devices.len()
+        )?;
+        for entry in &self.devices {
+            write!(f, "\n  0x{:02X}", entry.address)?;
+            if let Some(device) = &entry.device {
+                write!(f, "  {}", device)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `read` and `monitor`: one measurement and whether it is in alarm
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MeasurementReport {
+    pub sensor: String,
+    pub quantity: String,
+    /// `null` in JSON for a failed conversion
+    pub value: f32,
+    pub unit: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: f64,
+    pub quality: Vec<&'static str>,
+    pub alarm: bool,
+    #[serde(skip)]
+    precision: usize,
+}
+
+impl MeasurementReport {
+    /// Report `measurement`, checked against the sensor's alarm limits;
+    /// flagged readings still alarm unless the probe is faulty
+    pub fn new(measurement: &Measurement, config: &Config) -> Self {
+        let has_value =
+            !measurement.quality.contains(Quality::PROBE_FAULT) && !measurement.value.is_nan();
+        let alarm = config
+            .sensor(&measurement.sensor_id)
+            .and_then(|s| s.alarm)
+            .is_some_and(|alarm| has_value && alarm.is_triggered(measurement.value));
+        Self {
+            sensor: measurement.sensor_id.clone(),
+            quantity: measurement.quantity.to_string(),
+            value: measurement.value,
+            unit: measurement.unit.to_string(),
+            timestamp: measurement
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            quality: measurement.quality.names(),
+            alarm,
+            precision: match measurement.quantity {
+                Quantity::Voltage | Quantity::Conductivity => 3,
+                Quantity::Temperature | Quantity::Ph => 2,
+            },
+        }
+    }
+}
+
+impl fmt::Display for MeasurementReport {
+    /// e.g. `pH: 6.80 pH (alarm)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.*} {}",
+            self.sensor, self.precision, self.value, self.unit
+        )?;
+        if !self.quality.is_empty() {
+            write!(f, " ({})", self.quality.join(", "))?;
+        }
+        if self.alarm {
+            write!(f, " (alarm)")?;
+        }
+        Ok(())
+    }
+}
+
+/// `calibrate`: the new calibration of a pH probe
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CalibrationReport {
+    pub sensor: String,
+    pub buffers: Vec<BufferReading>,
+    /// Slope in % of the ideal electrode
+    pub slope_efficiency: f32,
+    pub offset_mv: f32,
+    pub health: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BufferReading {
+    pub ph: f32,
+    pub volts: f32,
+}
+
+impl CalibrationReport {
+    /// Report the calibrated `probe` of `sensor` and the captured `points`
+    pub fn new(sensor: &str, probe: &Ph4502c, points: &[CalibrationPoint]) -> Self {
+        Self {
+            sensor: sensor.to_string(),
+            buffers: points
+                .iter()
+                .map(|point| BufferReading {
+                    ph: point.ph.value(),
+                    volts: point.voltage.volts(),
+                })
+                .collect(),
+            slope_efficiency: probe.slope_efficiency(),
+            offset_mv: probe.offset_mv(),
+            health: probe.health().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CalibrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} calibrated: slope {:.1}% of ideal, offset {:+.1} mV, {}",
+            self.sensor, self.slope_efficiency, self.offset_mv, self.health
+        )?;
+        for buffer in &self.buffers {
+            write!(f, "\n  pH {:.2} at {:.3} V", buffer.ph, buffer.volts)?;
+        }
+        Ok(())
+    }
+}
+
+/// `led` and `display`: what was done on which device
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DeviceReport {
+    pub adapter: String,
+    pub address: u8,
+    pub result: String,
+}
+
+impl DeviceReport {
+    pub fn new(target: &Target, result: impl Into<String>) -> Self {
+        Self {
+            adapter: target.adapter.name.clone(),
+            address: target.address,
+            result: result.into(),
+        }
+    }
+}
+
+impl fmt::Display for DeviceReport {
+    /// e.g. `mcp2221 0x2A: LED set to Green`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} 0x{:02X}: {}",
+            self.adapter, self.address, self.result
+        )
+    }
+}
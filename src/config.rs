@@ -0,0 +1,785 @@
+use crate::ads1115::{mux_for_channel, pga_from_voltage, AdsSensor, Mux, Pga};
+use crate::calibration::{
+    CalibrationRecord, CalibrationStore, StoreError, DEFAULT_HISTORY_LIMIT, DEFAULT_MAX_AGE,
+    DEFAULT_STORE_FILE,
+};
+use crate::ec::{EcMeter, EcSensor, TdsScale};
+use crate::i2c::lock::{LockError, LockedI2c};
+use crate::lm35::{Lm35, Lm35Sensor};
+use crate::ph::{Ph4502c, PhSensor};
+use crate::sensor::Sensor;
+use crate::temperature::{NtcSensor, RtTable, SteinhartHart, ThermistorParams, Topology};
+use crate::units::{Unit, Voltage};
+use embedded_hal::i2c::I2c;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Where the binary looks for its configuration by default
+pub const DEFAULT_CONFIG_FILE: &str = "hydro-sense.toml";
+
+/// The stock setup: MCP2221 adapter, ADS1115 at 0x48 with NTC, pH, LM35
+/// and EC on AIN0-3, RGB button at 0x2A and OLED at 0x3C
+pub const DEFAULT_CONFIG: &str = include_str!("../hydro-sense.toml");
+
+/// Sensor driver built from the configuration on a locked bus
+pub type BuiltSensor<E> = Box<dyn Sensor<Error = LockError<E>>>;
+
+/// Why a configuration could not be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// Two entries of one section share a name
+    DuplicateName {
+        section: &'static str,
+        name: String,
+    },
+    UnknownAdapter {
+        device: String,
+        adapter: String,
+    },
+    UnknownDevice {
+        sensor: String,
+        device: String,
+    },
+    /// A sensor refers to a device that is not an ADS1115
+    NotAnAdc {
+        sensor: String,
+        device: String,
+    },
+    /// `compensate_with` does not name a temperature sensor
+    NotATemperatureSensor {
+        sensor: String,
+        compensate_with: String,
+    },
+    /// Address outside the range the device can be strapped to
+    InvalidAddress {
+        device: String,
+        address: u8,
+    },
+    /// Two devices on one adapter at the same address
+    AddressConflict {
+        adapter: String,
+        address: u8,
+        first: String,
+        second: String,
+    },
+    InvalidChannel {
+        sensor: String,
+        channel: u8,
+    },
+    InvalidPga {
+        sensor: String,
+        pga: f32,
+    },
+    InvalidModel {
+        sensor: String,
+        reason: String,
+    },
+    /// Alarm low threshold not below the high one
+    InvalidAlarm {
+        sensor: String,
+    },
+    InvalidPollInterval {
+        name: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "invalid config: {}", e),
+            ConfigError::DuplicateName { section, name } => {
+                write!(f, "duplicate {} name '{}'", section, name)
+            }
+            ConfigError::UnknownAdapter { device, adapter } => {
+                write!(f, "device '{}' uses unknown adapter '{}'", device, adapter)
+            }
+            ConfigError::UnknownDevice { sensor, device } => {
+                write!(f, "sensor '{}' uses unknown device '{}'", sensor, device)
+            }
+            ConfigError::NotAnAdc { sensor, device } => {
+                write!(
+                    f,
+                    "sensor '{}': device '{}' is not an ADS1115",
+                    sensor, device
+                )
+            }
+            ConfigError::NotATemperatureSensor {
+                sensor,
+                compensate_with,
+            } => write!(
+                f,
+                "sensor '{}': '{}' is not a temperature sensor",
+                sensor, compensate_with
+            ),
+            ConfigError::InvalidAddress { device, address } => {
+                write!(f, "device '{}': invalid address 0x{:02X}", device, address)
+            }
+            ConfigError::AddressConflict {
+                adapter,
+                address,
+                first,
+                second,
+            } => write!(
+                f,
+                "'{}' and '{}' both use address 0x{:02X} on adapter '{}'",
+                first, second, address, adapter
+            ),
+            ConfigError::InvalidChannel { sensor, channel } => {
+                write!(f, "sensor '{}': invalid channel {} (0-3)", sensor, channel)
+            }
+            ConfigError::InvalidPga { sensor, pga } => write!(
+                f,
+                "sensor '{}': invalid PGA {} V (6.144, 4.096, 2.048, 1.024, 0.512 or 0.256)",
+                sensor, pga
+            ),
+            ConfigError::InvalidModel { sensor, reason } => {
+                write!(f, "sensor '{}': {}", sensor, reason)
+            }
+            ConfigError::InvalidAlarm { sensor } => {
+                write!(f, "sensor '{}': alarm low must be below high", sensor)
+            }
+            ConfigError::InvalidPollInterval { name } => {
+                write!(f, "'{}': poll interval must be above zero", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+/// Whole sensor setup, usually read from `hydro-sense.toml`
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    #[serde(default, rename = "adapter")]
+    pub adapters: Vec<AdapterConfig>,
+    #[serde(default, rename = "device")]
+    pub devices: Vec<DeviceConfig>,
+    #[serde(default, rename = "sensor")]
+    pub sensors: Vec<SensorConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Default time between readings (ms)
+    pub poll_interval_ms: u64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+/// Where calibrations are stored and when they expire
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalibrationConfig {
+    /// Calibration store, see `calibration::CalibrationStore`
+    pub file: PathBuf,
+    /// Calibrations older than this are flagged (days)
+    pub max_age_days: u64,
+    /// Calibrations kept per probe for rollback
+    pub history: usize,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from(DEFAULT_STORE_FILE),
+            max_age_days: DEFAULT_MAX_AGE.as_secs() / (24 * 60 * 60),
+            history: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+}
+
+impl CalibrationConfig {
+    /// Open the store with the configured age limit and history length
+    pub fn open_store(&self) -> Result<CalibrationStore, StoreError> {
+        Ok(CalibrationStore::open(&self.file)?
+            .with_max_age(Duration::from_secs(self.max_age_days * 24 * 60 * 60))
+            .with_history_limit(self.history))
+    }
+}
+
+/// I2C adapter selected by its sysfs name, like `find_adapter`
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdapterConfig {
+    pub name: String,
+    /// Substring of the adapter's sysfs name, e.g. "MCP2221"
+    pub sysfs_name: String,
+    /// Cross-process bus lock file, see `i2c::lock`
+    pub lock_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Ads1115,
+    Df0991,
+    Ssd1306,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: DeviceKind,
+    pub adapter: String,
+    pub address: u8,
+}
+
+/// One ADS1115 channel and how its voltage is converted
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorConfig {
+    pub name: String,
+    /// ADS1115 device name
+    pub device: String,
+    /// Single-ended input 0-3
+    pub channel: u8,
+    pub model: ModelConfig,
+    /// Key of the probe's entry in the calibration store
+    pub calibration: Option<String>,
+    /// Temperature sensor whose latest reading compensates pH and EC
+    pub compensate_with: Option<String>,
+    /// Overrides `monitor.poll_interval_ms`
+    pub poll_interval_ms: Option<u64>,
+    pub alarm: Option<AlarmConfig>,
+}
+
+/// Conversion model; unset parameters keep the driver defaults
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ModelConfig {
+    /// Raw channel voltage
+    Voltage { pga: f32 },
+    Ntc {
+        supply: f32,
+        pga: f32,
+        r0: Option<f32>,
+        t0: Option<f32>,
+        beta: Option<f32>,
+        r_fixed: Option<f32>,
+        /// "high_side" (default) or "low_side"
+        topology: Option<String>,
+        /// Steinhart–Hart A, B and C
+        steinhart_hart: Option<[f64; 3]>,
+        /// R–T table CSV, see `RtTable::load`
+        table: Option<PathBuf>,
+    },
+    Lm35 {
+        /// Ground lift of the offset circuit (volts)
+        offset_circuit: Option<f32>,
+        gain: Option<f32>,
+        offset: Option<f32>,
+        max_temperature: Option<f32>,
+    },
+    Ph4502c {
+        gain: Option<f32>,
+        zero_voltage: Option<f32>,
+    },
+    Ec {
+        cell_constant: Option<f32>,
+        temp_coefficient: Option<f32>,
+        /// 500 or 700 ppm per mS/cm
+        tds_scale: Option<u16>,
+    },
+}
+
+/// Readings outside `low..=high` raise an alarm
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlarmConfig {
+    pub low: Option<f32>,
+    pub high: Option<f32>,
+}
+
+impl ModelConfig {
+    /// The PH4502C module of a `ph4502c` model, uncalibrated
+    pub fn ph4502c(&self) -> Option<Ph4502c> {
+        match self {
+            ModelConfig::Ph4502c { gain, zero_voltage } => {
+                let stock = Ph4502c::default();
+                Some(Ph4502c::new(
+                    gain.unwrap_or(stock.gain),
+                    zero_voltage.unwrap_or(stock.zero_voltage),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the model reads a temperature
+    pub fn is_temperature(&self) -> bool {
+        matches!(self, ModelConfig::Ntc { .. } | ModelConfig::Lm35 { .. })
+    }
+
+    /// Whether the model takes a sample temperature for compensation
+    pub fn is_compensated(&self) -> bool {
+        matches!(self, ModelConfig::Ph4502c { .. } | ModelConfig::Ec { .. })
+    }
+}
+
+impl AlarmConfig {
+    pub fn is_triggered(&self, value: f32) -> bool {
+        self.low.is_some_and(|low| value < low) || self.high.is_some_and(|high| value > high)
+    }
+}
+
+impl Default for Config {
+    /// The stock setup from `DEFAULT_CONFIG`
+    fn default() -> Self {
+        Self::parse(DEFAULT_CONFIG).expect("built-in config is valid")
+    }
+}
+
+impl Config {
+    /// Read and validate a TOML config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse and validate TOML text
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str(text).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check names, references, addresses, channels and model parameters,
+    /// loading NTC R–T tables to make sure they parse
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        unique_names("adapter", self.adapters.iter().map(|a| &a.name))?;
+        unique_names("device", self.devices.iter().map(|d| &d.name))?;
+        unique_names("sensor", self.sensors.iter().map(|s| &s.name))?;
+
+        if self.monitor.poll_interval_ms == 0 {
+            return Err(ConfigError::InvalidPollInterval {
+                name: "monitor".to_string(),
+            });
+        }
+
+        let mut addresses: HashMap<(&str, u8), &str> = HashMap::new();
+        for device in &self.devices {
+            if self.adapter(&device.adapter).is_none() {
+                return Err(ConfigError::UnknownAdapter {
+                    device: device.name.clone(),
+                    adapter: device.adapter.clone(),
+                });
+            }
+
+            let valid_addresses = match device.kind {
+                DeviceKind::Ads1115 => 0x48..=0x4B,
+                DeviceKind::Ssd1306 => 0x3C..=0x3D,
+                DeviceKind::Df0991 => 0x08..=0x77,
+            };
+            if !valid_addresses.contains(&device.address) {
+                return Err(ConfigError::InvalidAddress {
+                    device: device.name.clone(),
+                    address: device.address,
+                });
+            }
+
+            let key = (device.adapter.as_str(), device.address);
+            if let Some(first) = addresses.insert(key, &device.name) {
+                return Err(ConfigError::AddressConflict {
+                    adapter: device.adapter.clone(),
+                    address: device.address,
+                    first: first.to_string(),
+                    second: device.name.clone(),
+                });
+            }
+        }
+
+        for sensor in &self.sensors {
+            self.validate_sensor(sensor)?;
+        }
+        Ok(())
+    }
+
+    fn validate_sensor(&self, sensor: &SensorConfig) -> Result<(), ConfigError> {
+        let name = || sensor.name.clone();
+        let device = self
+            .device(&sensor.device)
+            .ok_or_else(|| ConfigError::UnknownDevice {
+                sensor: name(),
+                device: sensor.device.clone(),
+            })?;
+        if device.kind != DeviceKind::Ads1115 {
+            return Err(ConfigError::NotAnAdc {
+                sensor: name(),
+                device: sensor.device.clone(),
+            });
+        }
+        if mux_for_channel(sensor.channel).is_none() {
+            return Err(ConfigError::InvalidChannel {
+                sensor: name(),
+                channel: sensor.channel,
+            });
+        }
+        if sensor.poll_interval_ms == Some(0) {
+            return Err(ConfigError::InvalidPollInterval { name: name() });
+        }
+        if let Some(AlarmConfig {
+            low: Some(low),
+            high: Some(high),
+        }) = sensor.alarm
+        {
+            if low >= high {
+                return Err(ConfigError::InvalidAlarm { sensor: name() });
+            }
+        }
+
+        let invalid = |reason: &str| ConfigError::InvalidModel {
+            sensor: name(),
+            reason: reason.to_string(),
+        };
+        if let Some(source) = &sensor.compensate_with {
+            if !sensor.model.is_compensated() {
+                return Err(invalid("only ph4502c and ec models take compensate_with"));
+            }
+            if !self
+                .sensor(source)
+                .is_some_and(|s| s.model.is_temperature())
+            {
+                return Err(ConfigError::NotATemperatureSensor {
+                    sensor: name(),
+                    compensate_with: source.clone(),
+                });
+            }
+        }
+
+        // Parameters that scale or divide a reading; NaN fails as well
+        let scales = match &sensor.model {
+            ModelConfig::Ntc {
+                supply,
+                r0,
+                beta,
+                r_fixed,
+                ..
+            } => vec![
+                ("supply", Some(*supply)),
+                ("r0", *r0),
+                ("beta", *beta),
+                ("r_fixed", *r_fixed),
+            ],
+            ModelConfig::Lm35 { gain, .. } | ModelConfig::Ph4502c { gain, .. } => {
+                vec![("gain", *gain)]
+            }
+            ModelConfig::Ec { cell_constant, .. } => vec![("cell_constant", *cell_constant)],
+            ModelConfig::Voltage { .. } => Vec::new(),
+        };
+        for (parameter, value) in scales {
+            if value.is_some_and(|v| v.is_nan() || v <= 0.0) {
+                return Err(invalid(&format!("{} must be above zero", parameter)));
+            }
+        }
+
+        match &sensor.model {
+            ModelConfig::Voltage { pga } | ModelConfig::Ntc { pga, .. }
+                if pga_from_voltage(*pga).is_none() =>
+            {
+                Err(ConfigError::InvalidPga {
+                    sensor: name(),
+                    pga: *pga,
+                })
+            }
+            ModelConfig::Ntc {
+                topology: Some(topology),
+                ..
+            } if parse_topology(topology).is_none() => {
+                Err(invalid("topology must be 'high_side' or 'low_side'"))
+            }
+            ModelConfig::Ntc {
+                steinhart_hart: Some(_),
+                table: Some(_),
+                ..
+            } => Err(invalid("choose either steinhart_hart or table")),
+            ModelConfig::Ntc {
+                table: Some(path), ..
+            } => match RtTable::load(path) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(invalid(&format!("table {}: {}", path.display(), e))),
+            },
+            ModelConfig::Ec {
+                tds_scale: Some(scale),
+                ..
+            } if parse_tds_scale(*scale).is_none() => Err(invalid("tds_scale must be 500 or 700")),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn adapter(&self, name: &str) -> Option<&AdapterConfig> {
+        self.adapters.iter().find(|a| a.name == name)
+    }
+
+    pub fn device(&self, name: &str) -> Option<&DeviceConfig> {
+        self.devices.iter().find(|d| d.name == name)
+    }
+
+    pub fn sensor(&self, name: &str) -> Option<&SensorConfig> {
+        self.sensors.iter().find(|s| s.name == name)
+    }
+
+    /// First device of `kind`, e.g. the RGB button
+    pub fn first_device(&self, kind: DeviceKind) -> Option<&DeviceConfig> {
+        self.devices.iter().find(|d| d.kind == kind)
+    }
+
+    /// Adapter a device sits on
+    pub fn device_adapter(&self, device: &DeviceConfig) -> Option<&AdapterConfig> {
+        self.adapter(&device.adapter)
+    }
+
+    /// Time between readings of `sensor`
+    pub fn poll_interval(&self, sensor: &SensorConfig) -> Duration {
+        Duration::from_millis(
+            sensor
+                .poll_interval_ms
+                .unwrap_or(self.monitor.poll_interval_ms),
+        )
+    }
+
+    /// Build the driver of `sensor` on a locked bus from `open`; every
+    /// conversion holds the bus lock from start to finish
+    pub fn build_sensor<B, F>(
+        &self,
+        sensor: &SensorConfig,
+        open: F,
+    ) -> io::Result<BuiltSensor<B::Error>>
+    where
+        B: I2c + 'static,
+        F: FnOnce(&AdapterConfig) -> io::Result<LockedI2c<B>>,
+    {
+        self.build(sensor, None, open)
+    }
+
+    /// Same as `build_sensor`, with the probe's current calibration from
+    /// `store` applied to pH and EC sensors
+    pub fn build_calibrated_sensor<B, F>(
+        &self,
+        sensor: &SensorConfig,
+        store: &CalibrationStore,
+        open: F,
+    ) -> io::Result<BuiltSensor<B::Error>>
+    where
+        B: I2c + 'static,
+        F: FnOnce(&AdapterConfig) -> io::Result<LockedI2c<B>>,
+    {
+        let record = sensor.calibration.as_deref().and_then(|p| store.current(p));
+        self.build(sensor, record, open)
+    }
+
+    fn build<B, F>(
+        &self,
+        sensor: &SensorConfig,
+        record: Option<&CalibrationRecord>,
+        open: F,
+    ) -> io::Result<BuiltSensor<B::Error>>
+    where
+        B: I2c + 'static,
+        F: FnOnce(&AdapterConfig) -> io::Result<LockedI2c<B>>,
+    {
+        let invalid = |e: ConfigError| io::Error::new(io::ErrorKind::InvalidInput, e);
+        self.validate_sensor(sensor).map_err(invalid)?;
+
+        // Validation guarantees the device, adapter and channel exist
+        let device = self.device(&sensor.device).expect("validated device");
+        let adapter = self.device_adapter(device).expect("validated adapter");
+        let mux = mux_for_channel(sensor.channel).expect("validated channel");
+        let (address, name) = (device.address, sensor.name.as_str());
+        let i2c = open(adapter)?;
+        let bus_error = |e: LockError<B::Error>| io::Error::other(format!("{}: {:?}", name, e));
+
+        let built: BuiltSensor<B::Error> = match &sensor.model {
+            ModelConfig::Voltage { pga } => Box::new(
+                AdsSensor::new(i2c, address, mux, checked_pga(*pga), name, Unit::Volts)
+                    .map_err(bus_error)?
+                    .with_bus_lock(),
+            ),
+            ModelConfig::Ntc { supply, pga, .. } => Box::new(
+                NtcSensor::new(
+                    i2c,
+                    address,
+                    mux,
+                    checked_pga(*pga),
+                    Voltage::from_volts(*supply),
+                    thermistor_params(&sensor.model)?,
+                )
+                .map_err(bus_error)?
+                .with_name(name)
+                .with_bus_lock(),
+            ),
+            ModelConfig::Lm35 {
+                offset_circuit,
+                gain,
+                offset,
+                max_temperature,
+            } => {
+                let mut lm35 = Lm35::default();
+                if let Some(lift) = offset_circuit {
+                    lm35 = lm35.with_offset_circuit(*lift);
+                }
+                lm35 =
+                    lm35.with_calibration(gain.unwrap_or(lm35.gain), offset.unwrap_or(lm35.offset));
+                if let Some(max) = max_temperature {
+                    lm35 = lm35.with_max_temperature(*max);
+                }
+                Box::new(
+                    Lm35Sensor::new(i2c, address, mux, lm35)
+                        .map_err(bus_error)?
+                        .with_name(name)
+                        .with_bus_lock(),
+                )
+            }
+            ModelConfig::Ph4502c { .. } => {
+                let mut probe = sensor.model.ph4502c().expect("pH model");
+                if let Some(calibration) = record.and_then(|r| r.ph_calibration()) {
+                    probe = probe.with_calibration(calibration);
+                }
+                Box::new(
+                    PhSensor::new(i2c, address, mux, probe)
+                        .map_err(bus_error)?
+                        .with_name(name)
+                        .with_bus_lock(),
+                )
+            }
+            ModelConfig::Ec {
+                cell_constant,
+                temp_coefficient,
+                tds_scale,
+            } => {
+                let mut meter = EcMeter::default();
+                if let Some(k) = cell_constant {
+                    meter = meter.with_cell_constant(*k);
+                }
+                if let Some(coefficient) = temp_coefficient {
+                    meter = meter.with_temp_coefficient(*coefficient);
+                }
+                if let Some(scale) = tds_scale.and_then(parse_tds_scale) {
+                    meter = meter.with_tds_scale(scale);
+                }
+                if let Some(calibrated) = record.and_then(|r| r.apply_to_ec(meter)) {
+                    meter = calibrated;
+                }
+                Box::new(
+                    EcSensor::new(i2c, address, mux, meter)
+                        .map_err(bus_error)?
+                        .with_name(name)
+                        .with_bus_lock(),
+                )
+            }
+        };
+        Ok(built)
+    }
+
+    /// Build every configured sensor, opening a bus per sensor with `open`
+    pub fn build_sensors<B, F>(&self, mut open: F) -> io::Result<Vec<BuiltSensor<B::Error>>>
+    where
+        B: I2c + 'static,
+        F: FnMut(&AdapterConfig) -> io::Result<LockedI2c<B>>,
+    {
+        self.sensors
+            .iter()
+            .map(|sensor| self.build_sensor(sensor, &mut open))
+            .collect()
+    }
+}
+
+fn unique_names<'a>(
+    section: &'static str,
+    names: impl Iterator<Item = &'a String>,
+) -> Result<(), ConfigError> {
+    let mut seen = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            return Err(ConfigError::DuplicateName {
+                section,
+                name: name.clone(),
+            });
+        }
+        seen.push(name);
+    }
+    Ok(())
+}
+
+fn checked_pga(volts: f32) -> Pga {
+    pga_from_voltage(volts).expect("validated PGA")
+}
+
+fn parse_topology(topology: &str) -> Option<Topology> {
+    match topology {
+        "high_side" => Some(Topology::HighSide),
+        "low_side" => Some(Topology::LowSide),
+        _ => None,
+    }
+}
+
+fn parse_tds_scale(scale: u16) -> Option<TdsScale> {
+    match scale {
+        500 => Some(TdsScale::Ppm500),
+        700 => Some(TdsScale::Ppm700),
+        _ => None,
+    }
+}
+
+/// Thermistor parameters of an `ntc` model, loading its R–T table if any
+fn thermistor_params(model: &ModelConfig) -> io::Result<ThermistorParams> {
+    let ModelConfig::Ntc {
+        r0,
+        t0,
+        beta,
+        r_fixed,
+        topology,
+        steinhart_hart,
+        table,
+        ..
+    } = model
+    else {
+        return Ok(ThermistorParams::default());
+    };
+
+    let stock = ThermistorParams::default();
+    let mut params = ThermistorParams {
+        r0: r0.unwrap_or(stock.r0),
+        t0: t0.unwrap_or(stock.t0),
+        beta: beta.unwrap_or(stock.beta),
+        r_fixed: r_fixed.unwrap_or(stock.r_fixed),
+        topology: topology
+            .as_deref()
+            .and_then(parse_topology)
+            .unwrap_or(stock.topology),
+        ..stock
+    };
+    if let Some([a, b, c]) = *steinhart_hart {
+        params = params.with_steinhart_hart(SteinhartHart { a, b, c });
+    }
+    if let Some(path) = table {
+        params = params.with_table(RtTable::load(path)?);
+    }
+    Ok(params)
+}
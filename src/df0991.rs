@@ -1,5 +1,6 @@
 use core::convert::Infallible;
 use embedded_hal::i2c::I2c;
+use std::time::{Duration, Instant};
 
 /// Default I2C address for the RGB button.
 pub const RGBBUTTON_DEFAULT_I2C_ADDR: u8 = 0x2A;
@@ -14,8 +15,14 @@ const RGBBUTTON_BUTTON_SIGNAL_REG: u8 = 0x04;
 const RGBBUTTON_PID_MSB_REG: u8 = 0x09;
 const RGBBUTTON_PID_LSB_REG: u8 = 0x0A;
 
+/// Minimum time between two accepted button changes
+pub const DEBOUNCE_TIME: Duration = Duration::from_millis(50);
+
+/// Hold time after which a press counts as a long press
+pub const LONG_PRESS_TIME: Duration = Duration::from_secs(2);
+
 /// Predefined RGB colors
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum GeneralRGBColor {
     Red = 0xFF0000,
     Orange = 0xFF7F00,
@@ -96,3 +103,104 @@ where
         Ok(u16::from_be_bytes(buf))
     }
 }
+
+/// Debounced button state.
+///
+/// A change of the raw signal is accepted only if the last accepted change
+/// is at least `debounce` ago; anything quicker is contact bounce.
+#[derive(Clone, Copy, Debug)]
+pub struct ButtonDebouncer {
+    pressed: bool,
+    last_change: Instant,
+    debounce: Duration,
+}
+
+impl ButtonDebouncer {
+    pub fn new(pressed: bool, now: Instant) -> Self {
+        Self {
+            pressed,
+            last_change: now,
+            debounce: DEBOUNCE_TIME,
+        }
+    }
+
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Feed one raw reading; returns the new state if it was accepted
+    pub fn update(&mut self, raw: bool, now: Instant) -> Option<bool> {
+        if raw == self.pressed || now.duration_since(self.last_change) < self.debounce {
+            return None;
+        }
+        self.pressed = raw;
+        self.last_change = now;
+        Some(raw)
+    }
+}
+
+/// A completed button gesture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// Pressed and released before the long press time
+    Short,
+    /// Held for the long press time; reported while still held
+    Long,
+}
+
+/// Turns debounced button states into short and long presses.
+///
+/// A long press is reported as soon as the hold time is reached, so the
+/// user gets feedback without letting go; its release is swallowed.
+#[derive(Clone, Copy, Debug)]
+pub struct PressDetector {
+    pressed_at: Option<Instant>,
+    long_reported: bool,
+    long_press: Duration,
+}
+
+impl Default for PressDetector {
+    fn default() -> Self {
+        Self {
+            pressed_at: None,
+            long_reported: false,
+            long_press: LONG_PRESS_TIME,
+        }
+    }
+}
+
+impl PressDetector {
+    pub fn with_long_press(mut self, long_press: Duration) -> Self {
+        self.long_press = long_press;
+        self
+    }
+
+    /// Feed the debounced state on every poll
+    pub fn update(&mut self, pressed: bool, now: Instant) -> Option<ButtonEvent> {
+        match (pressed, self.pressed_at) {
+            (true, None) => {
+                self.pressed_at = Some(now);
+                self.long_reported = false;
+                None
+            }
+            (true, Some(since)) => {
+                if !self.long_reported && now.duration_since(since) >= self.long_press {
+                    self.long_reported = true;
+                    Some(ButtonEvent::Long)
+                } else {
+                    None
+                }
+            }
+            (false, Some(_)) => {
+                self.pressed_at = None;
+                (!self.long_reported).then_some(ButtonEvent::Short)
+            }
+            (false, None) => None,
+        }
+    }
+}
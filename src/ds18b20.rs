@@ -0,0 +1,172 @@
+use crate::units::Temperature;
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where the Linux w1 bus driver lists its slave devices
+pub const SYSFS_W1_DEVICES: &str = "/sys/bus/w1/devices";
+
+/// 1-Wire family code of the DS18B20; its device directories are `28-<serial>`
+pub const DS18B20_FAMILY: &str = "28";
+
+/// Value the scratchpad holds after power-on, before any conversion (°C)
+pub const POWER_ON_TEMPERATURE: f32 = 85.0;
+
+/// Scratchpad byte 6 after power-on; a real 85 °C conversion leaves 0x10
+pub const POWER_ON_COUNT_REMAIN: u8 = 0x0C;
+
+/// Why a DS18B20 reading failed
+#[derive(Debug)]
+pub enum Ds18b20Error {
+    /// `w1_slave` could not be read, e.g. the probe was unplugged
+    Io(io::Error),
+    /// The scratchpad CRC did not match (noise on a long cable)
+    Crc { line: String },
+    /// `w1_slave` is not in the expected two-line format
+    Malformed { contents: String },
+    /// The probe still holds its power-on value (brown-out or no conversion)
+    PowerOnReset,
+}
+
+impl fmt::Display for Ds18b20Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ds18b20Error::Io(e) => write!(f, "could not read DS18B20: {}", e),
+            Ds18b20Error::Crc { line } => write!(f, "DS18B20 CRC check failed ({})", line),
+            Ds18b20Error::Malformed { contents } => {
+                write!(f, "unexpected w1_slave contents: '{}'", contents.trim())
+            }
+            Ds18b20Error::PowerOnReset => write!(f, "DS18B20 reports its 85 °C power-on value"),
+        }
+    }
+}
+
+impl std::error::Error for Ds18b20Error {}
+
+impl From<io::Error> for Ds18b20Error {
+    fn from(e: io::Error) -> Self {
+        Ds18b20Error::Io(e)
+    }
+}
+
+/// Parse the `w1_slave` file of a DS18B20:
+///
+/// ```text
+/// 72 01 4b 46 7f ff 0e 10 57 : crc=57 YES
+/// 72 01 4b 46 7f ff 0e 10 57 t=23125
+/// ```
+///
+/// The first line ends in `YES` when the scratchpad CRC is good; the second
+/// carries the temperature in millidegrees Celsius. 85 °C counts as the
+/// power-on value only when scratchpad byte 6 still holds its reset value,
+/// so a genuine 85 °C reading is kept.
+pub fn parse_w1_slave(contents: &str) -> Result<Temperature, Ds18b20Error> {
+    let malformed = || Ds18b20Error::Malformed {
+        contents: contents.to_string(),
+    };
+
+    let mut lines = contents.lines();
+    let (crc_line, data_line) = match (lines.next(), lines.next()) {
+        (Some(crc), Some(data)) => (crc.trim(), data.trim()),
+        _ => return Err(malformed()),
+    };
+
+    match crc_line.rsplit(' ').next() {
+        Some("YES") => {}
+        Some("NO") => {
+            return Err(Ds18b20Error::Crc {
+                line: crc_line.to_string(),
+            })
+        }
+        _ => return Err(malformed()),
+    }
+
+    let millidegrees: i32 = data_line
+        .rsplit_once("t=")
+        .and_then(|(_, t)| t.trim().parse().ok())
+        .ok_or_else(malformed)?;
+
+    let celsius = millidegrees as f32 / 1000.0;
+    let count_remain = crc_line
+        .split_whitespace()
+        .nth(6)
+        .and_then(|byte| u8::from_str_radix(byte, 16).ok());
+    if celsius == POWER_ON_TEMPERATURE
+        && count_remain.is_none_or(|byte| byte == POWER_ON_COUNT_REMAIN)
+    {
+        return Err(Ds18b20Error::PowerOnReset);
+    }
+    Ok(Temperature::from_celsius(celsius))
+}
+
+/// DS18B20 probe exposed by the Linux `w1-therm` driver
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ds18b20 {
+    id: String,
+    dir: PathBuf,
+}
+
+impl Ds18b20 {
+    /// Probe with device id `id` (e.g. `28-0316a2795bff`) below `/sys/bus/w1/devices`
+    pub fn new(id: &str) -> Self {
+        Self::with_root(id, SYSFS_W1_DEVICES)
+    }
+
+    /// Probe below `sysfs_root` instead of `/sys/bus/w1/devices`
+    pub fn with_root<P: AsRef<Path>>(id: &str, sysfs_root: P) -> Self {
+        Self {
+            id: id.to_string(),
+            dir: sysfs_root.as_ref().join(id),
+        }
+    }
+
+    /// Device id, family code and serial number, e.g. `28-0316a2795bff`
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Serial number part of the id
+    pub fn serial(&self) -> &str {
+        self.id
+            .split_once('-')
+            .map_or(&self.id, |(_, serial)| serial)
+    }
+
+    /// Read `w1_slave`; the driver runs a conversion first, which takes
+    /// up to 750 ms at 12-bit resolution
+    pub fn read_temperature(&self) -> Result<Temperature, Ds18b20Error> {
+        let contents = fs::read_to_string(self.dir.join("w1_slave"))?;
+        parse_w1_slave(&contents)
+    }
+}
+
+/// Finds all DS18B20 probes on the w1 bus, sorted by id.
+///
+/// Scans `/sys/bus/w1/devices` for `28-*` directories; other 1-Wire
+/// devices and the `w1_bus_master*` links are skipped. A missing w1
+/// directory (driver not loaded) gives an empty list.
+pub fn find_probes() -> io::Result<Vec<Ds18b20>> {
+    find_probes_in(SYSFS_W1_DEVICES)
+}
+
+/// Same as `find_probes`, scanning `sysfs_root` instead of
+/// `/sys/bus/w1/devices` (used by tests with a fake sysfs tree).
+pub fn find_probes_in<P: AsRef<Path>>(sysfs_root: P) -> io::Result<Vec<Ds18b20>> {
+    let devices = match fs::read_dir(&sysfs_root) {
+        Ok(devices) => devices,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut probes = Vec::new();
+    for entry in devices {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.split_once('-').map(|(family, _)| family) == Some(DS18B20_FAMILY) {
+            probes.push(Ds18b20::with_root(&name, &sysfs_root));
+        }
+    }
+
+    probes.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(probes)
+}
@@ -0,0 +1,319 @@
+use crate::ads1115::{is_clipped, AdsSensor, Mux, Pga};
+use crate::i2c::lock::LockedI2c;
+use crate::sensor::{Measurement, Quality, Quantity, Sensor};
+use crate::units::{Conductivity, Temperature, Unit, Voltage};
+use embedded_hal::i2c::{ErrorType, I2c};
+use std::fmt;
+
+/// Standard KCl calibration solutions at 25 °C
+pub const EC_STANDARD_LOW: Conductivity = Conductivity::from_ms_per_cm(1.413);
+pub const EC_STANDARD_HIGH: Conductivity = Conductivity::from_ms_per_cm(12.88);
+
+/// Temperature conductivity is compensated to (°C)
+pub const REFERENCE_TEMPERATURE: f32 = 25.0;
+
+/// Typical compensation coefficient of nutrient solutions (per °C)
+pub const DEFAULT_TEMP_COEFFICIENT: f32 = 0.02;
+
+/// Conversion from conductivity to total dissolved solids
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TdsScale {
+    /// NaCl scale, 500 ppm per mS/cm (US meters)
+    Ppm500,
+    /// KCl scale, 700 ppm per mS/cm (EU/Australian meters)
+    Ppm700,
+}
+
+impl TdsScale {
+    /// ppm per mS/cm
+    pub fn factor(&self) -> f32 {
+        match self {
+            TdsScale::Ppm500 => 500.0,
+            TdsScale::Ppm700 => 700.0,
+        }
+    }
+}
+
+/// Calibration errors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EcError {
+    /// The probe reads no conductance at all (dry or disconnected)
+    NoSignal { voltage: f32 },
+    /// Both standards gave the same reading
+    SameStandard,
+    /// The fitted cell constant is not positive
+    InvalidCellConstant { k: f32 },
+}
+
+impl fmt::Display for EcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EcError::NoSignal { voltage } => {
+                write!(f, "no EC probe signal ({:.3} V)", voltage)
+            }
+            EcError::SameStandard => write!(f, "calibration standards gave the same reading"),
+            EcError::InvalidCellConstant { k } => write!(f, "invalid cell constant K={:.3}", k),
+        }
+    }
+}
+
+impl std::error::Error for EcError {}
+
+/// Analog EC meter board with conductivity cell.
+///
+/// The board output is proportional to the conductance between the probe
+/// electrodes; the cell constant K turns that into conductivity:
+/// EC = K * G + zero_offset, compensated to 25 °C as
+/// EC25 = EC / (1 + coefficient * (T - 25)).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EcMeter {
+    /// Board transfer (mS of conductance per volt of output)
+    pub conductance_per_volt: f32,
+    /// Cell constant K (1/cm)
+    pub k: f32,
+    /// Conductivity with no conductance (mS/cm), from a two-point calibration
+    pub zero_offset: f32,
+    /// Linear temperature coefficient (per °C)
+    pub temp_coefficient: f32,
+    pub tds_scale: TdsScale,
+}
+
+impl Default for EcMeter {
+    /// Generic Arduino EC board (820 Ω, 200 mV reference) with a K=1 probe
+    fn default() -> Self {
+        Self {
+            conductance_per_volt: 1000.0 / 164.0,
+            k: 1.0,
+            zero_offset: 0.0,
+            temp_coefficient: DEFAULT_TEMP_COEFFICIENT,
+            tds_scale: TdsScale::Ppm500,
+        }
+    }
+}
+
+impl EcMeter {
+    pub fn with_cell_constant(mut self, k: f32) -> Self {
+        self.k = k;
+        self.zero_offset = 0.0;
+        self
+    }
+
+    pub fn with_temp_coefficient(mut self, temp_coefficient: f32) -> Self {
+        self.temp_coefficient = temp_coefficient;
+        self
+    }
+
+    pub fn with_tds_scale(mut self, tds_scale: TdsScale) -> Self {
+        self.tds_scale = tds_scale;
+        self
+    }
+
+    /// Conductance between the electrodes (mS) from the board voltage
+    pub fn conductance(&self, voltage: Voltage) -> f32 {
+        voltage.volts() * self.conductance_per_volt
+    }
+
+    /// Conductivity at the sample temperature, without compensation
+    pub fn voltage_to_raw_ec(&self, voltage: Voltage) -> Conductivity {
+        Conductivity::from_ms_per_cm(self.k * self.conductance(voltage) + self.zero_offset)
+    }
+
+    /// Conductivity compensated to 25 °C for a sample at `temperature`
+    pub fn voltage_to_ec(&self, voltage: Voltage, temperature: Temperature) -> Conductivity {
+        let raw = self.voltage_to_raw_ec(voltage).ms_per_cm();
+        Conductivity::from_ms_per_cm(raw / self.compensation(temperature))
+    }
+
+    /// Board voltage for a solution of `ec` (at 25 °C) measured at
+    /// `temperature`, the inverse of `voltage_to_ec`
+    pub fn ec_to_voltage(&self, ec: Conductivity, temperature: Temperature) -> Voltage {
+        let raw = ec.ms_per_cm() * self.compensation(temperature);
+        let conductance = (raw - self.zero_offset) / self.k;
+        Voltage::from_volts(conductance / self.conductance_per_volt)
+    }
+
+    /// Total dissolved solids (ppm) from conductivity at 25 °C
+    pub fn ec_to_tds(&self, ec: Conductivity) -> f32 {
+        ec.ms_per_cm() * self.tds_scale.factor()
+    }
+
+    /// Fit K from one standard solution (at 25 °C) read at `voltage` with
+    /// the solution at `temperature`
+    pub fn calibrate(
+        &mut self,
+        standard: Conductivity,
+        voltage: Voltage,
+        temperature: Temperature,
+    ) -> Result<f32, EcError> {
+        let conductance = self.conductance(voltage);
+        if conductance <= 0.0 {
+            return Err(EcError::NoSignal {
+                voltage: voltage.volts(),
+            });
+        }
+
+        let k = standard.ms_per_cm() * self.compensation(temperature) / conductance;
+        self.set_fit(k, 0.0)
+    }
+
+    /// Fit K and the zero offset from the 1.413 and 12.88 mS/cm standards
+    /// (or any two), each as (standard, voltage); both at `temperature`
+    pub fn calibrate_two_point(
+        &mut self,
+        low: (Conductivity, Voltage),
+        high: (Conductivity, Voltage),
+        temperature: Temperature,
+    ) -> Result<f32, EcError> {
+        let compensation = self.compensation(temperature);
+        let ec_low = low.0.ms_per_cm() * compensation;
+        let ec_high = high.0.ms_per_cm() * compensation;
+        let (g_low, g_high) = (self.conductance(low.1), self.conductance(high.1));
+        if g_low <= 0.0 || g_high <= 0.0 {
+            return Err(EcError::NoSignal {
+                voltage: low.1.volts().min(high.1.volts()),
+            });
+        }
+        if (g_high - g_low).abs() < f32::EPSILON {
+            return Err(EcError::SameStandard);
+        }
+
+        let k = (ec_high - ec_low) / (g_high - g_low);
+        self.set_fit(k, ec_low - k * g_low)
+    }
+
+    fn set_fit(&mut self, k: f32, zero_offset: f32) -> Result<f32, EcError> {
+        if !(k.is_finite() && k > 0.0) {
+            return Err(EcError::InvalidCellConstant { k });
+        }
+        self.k = k;
+        self.zero_offset = zero_offset;
+        Ok(k)
+    }
+
+    fn compensation(&self, temperature: Temperature) -> f32 {
+        1.0 + self.temp_coefficient * (temperature.celsius() - REFERENCE_TEMPERATURE)
+    }
+}
+
+/// EC meter read through an ADS1115 channel
+pub struct EcSensor<I2C: ErrorType> {
+    ads: AdsSensor<I2C>,
+    meter: EcMeter,
+    sample_temperature: Option<Temperature>,
+}
+
+impl<I2C, E> EcSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// ADS1115 channel `mux` at `addr`; 4.096V covers the 12.88 mS/cm standard
+    pub fn new(i2c: I2C, addr: u8, mux: Mux, meter: EcMeter) -> Result<Self, E> {
+        let ads = AdsSensor::new(
+            i2c,
+            addr,
+            mux,
+            Pga::Gain4_096V,
+            "EC Sensor",
+            Unit::MilliSiemensPerCm,
+        )?;
+        Ok(Self {
+            ads,
+            meter,
+            sample_temperature: None,
+        })
+    }
+
+    /// Solution temperature used by `measure`, e.g. from a temperature probe
+    pub fn set_sample_temperature(&mut self, temperature: Option<Temperature>) {
+        self.sample_temperature = temperature;
+    }
+
+    /// Name reported in logs and measurements instead of the default
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.ads.name = name.to_string();
+        self
+    }
+
+    pub fn meter(&self) -> &EcMeter {
+        &self.meter
+    }
+
+    pub fn meter_mut(&mut self) -> &mut EcMeter {
+        &mut self.meter
+    }
+
+    /// Raw board voltage, e.g. for a calibration point
+    pub fn get_voltage(&mut self) -> Result<Voltage, E> {
+        self.ads.get_voltage().map(Voltage::from_volts)
+    }
+
+    /// Read the channel and return conductivity at 25 °C for a sample at `temperature`
+    pub fn get_ec(&mut self, temperature: Temperature) -> Result<Conductivity, E> {
+        let voltage = self.get_voltage()?;
+        Ok(self.meter.voltage_to_ec(voltage, temperature))
+    }
+
+    /// Read the channel and return TDS (ppm) on the meter's scale
+    pub fn get_tds(&mut self, temperature: Temperature) -> Result<f32, E> {
+        let ec = self.get_ec(temperature)?;
+        Ok(self.meter.ec_to_tds(ec))
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.ads.release()
+    }
+}
+
+impl<I2C: I2c> EcSensor<LockedI2c<I2C>> {
+    /// Hold the bus lock across each whole conversion
+    pub fn with_bus_lock(mut self) -> Self {
+        self.ads = self.ads.with_bus_lock();
+        self
+    }
+}
+
+impl<I2C, E> Sensor for EcSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    fn id(&self) -> &str {
+        &self.ads.name
+    }
+
+    fn quantity(&self) -> Quantity {
+        Quantity::Conductivity
+    }
+
+    /// Conductivity at 25 °C, uncompensated without a sample temperature
+    fn measure(&mut self) -> Result<Measurement, E> {
+        let voltage = self.ads.get_voltage()?;
+        let reference = Temperature::from_celsius(REFERENCE_TEMPERATURE);
+        let temperature = self.sample_temperature.unwrap_or(reference);
+        let ec = self
+            .meter
+            .voltage_to_ec(Voltage::from_volts(voltage), temperature);
+
+        let mut quality = Quality::GOOD;
+        if is_clipped(voltage, self.ads.full_scale()) {
+            quality.insert(Quality::CLIPPED);
+        }
+        if ec.ms_per_cm() < 0.0 {
+            quality.insert(Quality::OUT_OF_RANGE);
+        }
+        if self.sample_temperature.is_none() {
+            quality.insert(Quality::UNCOMPENSATED);
+        }
+
+        Ok(
+            Measurement::new(&self.ads.name, Quantity::Conductivity, ec.ms_per_cm())
+                .with_quality(quality),
+        )
+    }
+
+    fn set_sample_temperature(&mut self, temperature: Option<Temperature>) {
+        self.sample_temperature = temperature;
+    }
+}
@@ -1,4 +1,21 @@
-use std::{fs, io::Read};
+pub mod lock;
+pub mod record;
+pub mod retry;
+pub mod shared;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use linux_embedded_hal::I2cdev;
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Where Linux lists its I2C adapters
+pub const SYSFS_I2C_ADAPTERS: &str = "/sys/class/i2c-adapter";
 
 /// Finds the Linux I2C adapter device path by matching a substring in its
 /// "name" file.
@@ -27,7 +44,16 @@ use std::{fs, io::Read};
 ///   is found.
 ///
 pub fn find_adapter(device_name: &str) -> std::io::Result<String> {
-    let adapters = fs::read_dir("/sys/class/i2c-adapter")?;
+    find_adapter_in(SYSFS_I2C_ADAPTERS, device_name)
+}
+
+/// Same as `find_adapter`, scanning `sysfs_root` instead of
+/// `/sys/class/i2c-adapter` (used by tests with a fake sysfs tree).
+pub fn find_adapter_in<P: AsRef<Path>>(
+    sysfs_root: P,
+    device_name: &str,
+) -> std::io::Result<String> {
+    let adapters = fs::read_dir(sysfs_root)?;
 
     for entry in adapters {
         let entry = entry?;
@@ -49,3 +75,311 @@ pub fn find_adapter(device_name: &str) -> std::io::Result<String> {
         format!("I2C adapter '{}' not found", device_name),
     ))
 }
+
+/// An I2C adapter listed in sysfs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// Device file, e.g. `/dev/i2c-7`
+    pub device: String,
+    /// Name the kernel gives the adapter, e.g. "MCP2221 usb-i2c bridge"
+    pub name: String,
+}
+
+/// Lists the I2C adapters in `/sys/class/i2c-adapter`, by bus number
+pub fn list_adapters() -> std::io::Result<Vec<AdapterInfo>> {
+    list_adapters_in(SYSFS_I2C_ADAPTERS)
+}
+
+/// Same as `list_adapters`, scanning `sysfs_root` instead
+pub fn list_adapters_in<P: AsRef<Path>>(sysfs_root: P) -> std::io::Result<Vec<AdapterInfo>> {
+    let mut adapters = Vec::new();
+    for entry in fs::read_dir(sysfs_root)? {
+        let path = entry?.path();
+        let name = fs::read_to_string(path.join("name"))?;
+        let devname = path.file_name().unwrap().to_string_lossy();
+        adapters.push(AdapterInfo {
+            device: format!("/dev/{}", devname),
+            name: name.trim().to_string(),
+        });
+    }
+    adapters.sort_by_key(|a| bus_number(&a.device));
+    Ok(adapters)
+}
+
+fn bus_number(device: &str) -> Option<u32> {
+    device.rsplit('-').next()?.parse().ok()
+}
+
+/// Addresses probed by `scan`; the others are reserved by the I2C spec
+pub const SCAN_ADDRESSES: std::ops::RangeInclusive<u8> = 0x08..=0x77;
+
+/// Finds the devices on a bus by reading one byte from each address in
+/// `addresses` (like `i2cdetect -r`); an address that fails to answer
+/// counts as empty.
+///
+/// A read is harmless for the devices used here, but may have side
+/// effects on others (e.g. clearing an interrupt flag).
+pub fn scan<I: I2c>(i2c: &mut I, addresses: std::ops::RangeInclusive<u8>) -> Vec<u8> {
+    let mut buf = [0u8; 1];
+    addresses
+        .filter(|&address| match i2c.read(address, &mut buf) {
+            Ok(()) => true,
+            Err(e) => {
+                log::trace!("0x{:02X}: {:?}", address, e);
+                false
+            }
+        })
+        .collect()
+}
+
+/// Something that can find and open an I2C adapter again after it went away
+pub trait AdapterSource {
+    type Bus: I2c;
+
+    /// Current location of the adapter, e.g. `/dev/i2c-7`. Fails while the
+    /// adapter is unplugged.
+    fn locate(&mut self) -> io::Result<String>;
+
+    /// Identity of the adapter at `location`. It changes when the adapter
+    /// is unplugged and plugged back in, even at the same location.
+    fn identify(&mut self, location: &str) -> io::Result<u64>;
+
+    /// Open the adapter found at `location`
+    fn open(&mut self, location: &str) -> io::Result<Self::Bus>;
+}
+
+/// Adapter selected by name through sysfs, like `find_adapter`
+pub struct SysfsAdapter {
+    name: String,
+    sysfs_root: PathBuf,
+}
+
+impl SysfsAdapter {
+    pub fn new(name: &str) -> Self {
+        Self::with_root(name, SYSFS_I2C_ADAPTERS)
+    }
+
+    /// Look the adapter up below `sysfs_root` instead of `/sys/class/i2c-adapter`
+    pub fn with_root<P: AsRef<Path>>(name: &str, sysfs_root: P) -> Self {
+        Self {
+            name: name.to_string(),
+            sysfs_root: sysfs_root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl AdapterSource for SysfsAdapter {
+    type Bus = I2cdev;
+
+    fn locate(&mut self) -> io::Result<String> {
+        find_adapter_in(&self.sysfs_root, &self.name)
+    }
+
+    /// The device node is created anew on every plug, with a new inode
+    fn identify(&mut self, location: &str) -> io::Result<u64> {
+        Ok(fs::metadata(location)?.ino())
+    }
+
+    fn open(&mut self, location: &str) -> io::Result<I2cdev> {
+        I2cdev::new(location).map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+/// Errors of `ReconnectingI2c`
+#[derive(Debug)]
+pub enum ReconnectError<E> {
+    /// The underlying bus failed while the adapter stayed connected
+    Bus(E),
+    /// The adapter did not come back before the wait timeout
+    Disconnected(io::Error),
+}
+
+impl<E: fmt::Debug> fmt::Display for ReconnectError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconnectError::Bus(e) => write!(f, "I2C bus error: {:?}", e),
+            ReconnectError::Disconnected(e) => write!(f, "I2C adapter disconnected: {}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for ReconnectError<E> {}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for ReconnectError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ReconnectError::Bus(e) => e.kind(),
+            ReconnectError::Disconnected(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Hook run on a freshly reopened bus to bring attached devices back up
+type ReconnectHook<B> = Box<dyn FnMut(&mut B) -> Result<(), <B as ErrorType>::Error>>;
+
+/// I2C bus that survives the adapter being unplugged and plugged back in.
+///
+/// When a transaction fails and the adapter is no longer the one that was
+/// opened (it is gone, came back under a new `/dev/i2c-N`, or a quick
+/// replug reused the same `/dev/i2c-N` with a new identity), the bus waits
+/// for the adapter to return, reopens it, runs the `on_reconnect` hooks to
+/// re-initialise the attached drivers and retries the transaction once.
+/// Other failures, like a missing target, are returned as they are.
+pub struct ReconnectingI2c<S: AdapterSource> {
+    source: S,
+    location: String,
+    identity: u64,
+    bus: Option<S::Bus>,
+    poll_interval: Duration,
+    wait_timeout: Option<Duration>,
+    hooks: Vec<ReconnectHook<S::Bus>>,
+    reconnects: usize,
+}
+
+impl<S: AdapterSource> ReconnectingI2c<S> {
+    /// Open the adapter now; waits forever for it after a disconnect
+    pub fn new(mut source: S) -> io::Result<Self> {
+        let location = source.locate()?;
+        let identity = source.identify(&location)?;
+        let bus = source.open(&location)?;
+
+        Ok(Self {
+            source,
+            location,
+            identity,
+            bus: Some(bus),
+            poll_interval: Duration::from_millis(500),
+            wait_timeout: None,
+            hooks: Vec::new(),
+            reconnects: 0,
+        })
+    }
+
+    /// How often to look for the adapter while it is gone
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Give up waiting for the adapter after `timeout` (default: never)
+    pub fn with_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = Some(timeout);
+        self
+    }
+
+    /// Run `hook` on the bus after every reconnect, e.g. to call
+    /// `DFRobotRGBButton::begin` or restore an LED colour
+    pub fn on_reconnect<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&mut S::Bus) -> Result<(), <S::Bus as ErrorType>::Error> + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Location of the adapter currently in use
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Number of successful reconnects so far
+    pub fn reconnects(&self) -> usize {
+        self.reconnects
+    }
+
+    /// Whether the bus must be reopened after a failed transaction: the
+    /// adapter is gone, or is not the one that was opened. The error kinds
+    /// can not tell, as drivers report a vanished adapter differently.
+    fn needs_reopen(&mut self) -> bool {
+        match self.source.locate() {
+            Ok(location) if location == self.location => self
+                .source
+                .identify(&location)
+                .map_or(true, |identity| identity != self.identity),
+            _ => true,
+        }
+    }
+
+    /// Wait for the adapter to return, reopen it and run the hooks
+    fn reconnect(&mut self) -> Result<(), io::Error> {
+        let started = Instant::now();
+
+        loop {
+            if let Ok(location) = self.source.locate() {
+                match self.open_and_init(&location) {
+                    Ok((identity, bus)) => {
+                        log::info!("I2C adapter reconnected at {}", location);
+                        self.location = location;
+                        self.identity = identity;
+                        self.bus = Some(bus);
+                        self.reconnects += 1;
+                        return Ok(());
+                    }
+                    Err(e) => log::warn!("Reopening I2C adapter at {} failed: {}", location, e),
+                }
+            }
+
+            if let Some(timeout) = self.wait_timeout {
+                if started.elapsed() >= timeout {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("I2C adapter did not return within {:?}", timeout),
+                    ));
+                }
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+
+    fn open_and_init(&mut self, location: &str) -> io::Result<(u64, S::Bus)> {
+        let identity = self.source.identify(location)?;
+        let mut bus = self.source.open(location)?;
+        for hook in self.hooks.iter_mut() {
+            hook(&mut bus).map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        }
+        Ok((identity, bus))
+    }
+
+    /// Release the bus currently in use
+    pub fn release(self) -> Option<S::Bus> {
+        self.bus
+    }
+}
+
+impl<S: AdapterSource> ErrorType for ReconnectingI2c<S> {
+    type Error = ReconnectError<<S::Bus as ErrorType>::Error>;
+}
+
+impl<S: AdapterSource> I2c for ReconnectingI2c<S> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.bus.is_none() {
+            self.reconnect().map_err(ReconnectError::Disconnected)?;
+        }
+
+        let bus = self.bus.as_mut().expect("bus reopened above");
+        let error = match bus.transaction(address, operations) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        if !self.needs_reopen() {
+            return Err(ReconnectError::Bus(error));
+        }
+
+        log::warn!(
+            "I2C adapter at {} disconnected ({:?}), waiting for it to return",
+            self.location,
+            error
+        );
+        self.bus = None;
+        self.reconnect().map_err(ReconnectError::Disconnected)?;
+
+        let bus = self.bus.as_mut().expect("bus reopened above");
+        bus.transaction(address, operations)
+            .map_err(ReconnectError::Bus)
+    }
+}
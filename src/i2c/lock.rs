@@ -0,0 +1,262 @@
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+/// Lock file shared by every hydro-sense process on the host
+pub const DEFAULT_LOCK_FILE: &str = "/tmp/hydro-sense-i2c.lock";
+
+struct LockState {
+    file: File,
+    depth: u32,
+    owner: Option<ThreadId>,
+}
+
+struct SharedLock {
+    state: Mutex<LockState>,
+    released: Condvar,
+}
+
+/// Advisory cross-process lock on an I2C bus, using `flock(2)`.
+///
+/// The lock is taken on a lock file or directly on the `/dev/i2c-N` node,
+/// so every cooperating process must use the same path. Handles are cheap
+/// to clone and share one lock; holding it again on the thread that holds
+/// it (nested `hold` calls, possibly through a clone) does not block, while
+/// other threads wait for it like other processes do.
+#[derive(Clone)]
+pub struct BusLock {
+    path: PathBuf,
+    shared: Arc<SharedLock>,
+    timeout: Option<Duration>,
+}
+
+impl BusLock {
+    /// Lock on `path`, created if missing; waits up to 5 s for the lock
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .or_else(|_| File::open(&path))?;
+
+        let state = LockState {
+            file,
+            depth: 0,
+            owner: None,
+        };
+        Ok(Self {
+            path,
+            shared: Arc::new(SharedLock {
+                state: Mutex::new(state),
+                released: Condvar::new(),
+            }),
+            timeout: Some(Duration::from_secs(5)),
+        })
+    }
+
+    /// How long `hold` waits for another process or thread; `None` waits
+    /// forever
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this handle (or a clone) currently holds the lock, on any
+    /// thread
+    pub fn is_held(&self) -> bool {
+        self.state().depth > 0
+    }
+
+    /// Take the lock until the returned guard is dropped
+    pub fn hold(&self) -> io::Result<BusLockGuard> {
+        let me = thread::current().id();
+        let mut state = self.state();
+        if state.depth > 0 && state.owner != Some(me) {
+            state = self.wait_for_thread(state)?;
+        }
+        if state.depth == 0 {
+            self.acquire(&state.file)?;
+            state.owner = Some(me);
+        }
+        state.depth += 1;
+
+        Ok(BusLockGuard { lock: self.clone() })
+    }
+
+    /// Wait until the thread holding the lock through a clone releases it
+    fn wait_for_thread<'a>(
+        &self,
+        mut state: MutexGuard<'a, LockState>,
+    ) -> io::Result<MutexGuard<'a, LockState>> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let released = &self.shared.released;
+
+        while state.depth > 0 {
+            state = match deadline {
+                None => released.wait(state).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!(
+                                "I2C bus lock {} is held by another thread",
+                                self.path.display()
+                            ),
+                        ));
+                    }
+                    let (state, _) = released
+                        .wait_timeout(state, left)
+                        .unwrap_or_else(|e| e.into_inner());
+                    state
+                }
+            };
+        }
+        Ok(state)
+    }
+
+    fn acquire(&self, file: &File) -> io::Result<()> {
+        let fd = file.as_raw_fd();
+        let Some(timeout) = self.timeout else {
+            return flock(fd, libc::LOCK_EX);
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match flock(fd, libc::LOCK_EX | libc::LOCK_NB) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                other => return other,
+            }
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("I2C bus lock {} is held elsewhere", self.path.display()),
+                ));
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, LockState> {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn flock(fd: i32, operation: i32) -> io::Result<()> {
+    loop {
+        // SAFETY: `fd` belongs to a `File` that outlives this call
+        if unsafe { libc::flock(fd, operation) } == 0 {
+            return Ok(());
+        }
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+}
+
+/// Keeps a `BusLock` held; the lock is released when the outermost guard drops
+pub struct BusLockGuard {
+    lock: BusLock,
+}
+
+impl Drop for BusLockGuard {
+    fn drop(&mut self) {
+        let mut state = self.lock.state();
+        state.depth -= 1;
+        if state.depth == 0 {
+            state.owner = None;
+            if let Err(e) = flock(state.file.as_raw_fd(), libc::LOCK_UN) {
+                log::warn!("Could not release I2C bus lock: {}", e);
+            }
+            self.lock.shared.released.notify_all();
+        }
+    }
+}
+
+/// Errors of `LockedI2c`
+#[derive(Debug)]
+pub enum LockError<E> {
+    /// The underlying bus failed
+    Bus(E),
+    /// The bus lock could not be taken
+    Lock(io::Error),
+}
+
+impl<E: fmt::Debug> fmt::Display for LockError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Bus(e) => write!(f, "I2C bus error: {:?}", e),
+            LockError::Lock(e) => write!(f, "I2C bus lock failed: {}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for LockError<E> {}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for LockError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            LockError::Bus(e) => e.kind(),
+            LockError::Lock(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// I2C bus wrapper that holds a `BusLock` during every transaction.
+///
+/// Sequences of several transactions (such as `AdsSensor::get_voltage`)
+/// stay atomic across processes by holding the lock around them with
+/// `BusLock::hold`; the wrapper then joins the held lock.
+pub struct LockedI2c<I2C> {
+    i2c: I2C,
+    lock: BusLock,
+}
+
+impl<I2C: I2c> LockedI2c<I2C> {
+    pub fn new(i2c: I2C, lock: BusLock) -> Self {
+        Self { i2c, lock }
+    }
+
+    /// Handle to the lock, for holding it across several transactions
+    pub fn lock(&self) -> BusLock {
+        self.lock.clone()
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: I2c> ErrorType for LockedI2c<I2C> {
+    type Error = LockError<I2C::Error>;
+}
+
+impl<I2C: I2c> I2c for LockedI2c<I2C> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let _guard = self.lock.hold().map_err(LockError::Lock)?;
+        self.i2c
+            .transaction(address, operations)
+            .map_err(LockError::Bus)
+    }
+}
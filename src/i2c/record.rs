@@ -0,0 +1,375 @@
+//! Recording and replay of I2C traffic.
+//!
+//! Recordings are plain text, one transaction per line:
+//!
+//! ```text
+//! # hydro-sense i2c recording v1
+//! 1750000000.000000 48 w:01c183
+//! 1750000000.010215 48 w:00 r:3a98
+//! 1750000000.021030 2a w:04 ! nack-address
+//! ```
+//!
+//! * Lines starting with `#` and blank lines are ignored.
+//! * The first field is the UNIX time of the transaction in seconds with
+//!   microsecond resolution.
+//! * The second field is the 7-bit target address in hex.
+//! * Then one field per operation: `w:<hex>` for bytes written and
+//!   `r:<hex>` for bytes read back. An empty write is `w:`.
+//! * A failed transaction ends with `!` and the error kind: `nack-address`,
+//!   `nack-data`, `nack`, `bus`, `arbitration`, `overrun` or `other`.
+
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// First line of every recording
+pub const RECORDING_HEADER: &str = "# hydro-sense i2c recording v1";
+
+/// One operation of a recorded transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordedOp {
+    Write(Vec<u8>),
+    Read(Vec<u8>),
+}
+
+/// One line of a recording
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedTransaction {
+    /// Seconds since the UNIX epoch
+    pub timestamp: f64,
+    pub address: u8,
+    pub ops: Vec<RecordedOp>,
+    pub error: Option<ErrorKind>,
+}
+
+impl fmt::Display for RecordedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.6} {:02x}", self.timestamp, self.address)?;
+        for op in &self.ops {
+            match op {
+                RecordedOp::Write(bytes) => write!(f, " w:{}", to_hex(bytes))?,
+                RecordedOp::Read(bytes) => write!(f, " r:{}", to_hex(bytes))?,
+            }
+        }
+        if let Some(kind) = self.error {
+            write!(f, " ! {}", kind_name(kind))?;
+        }
+        Ok(())
+    }
+}
+
+impl RecordedTransaction {
+    /// Parse one recording line
+    pub fn parse(line: &str) -> io::Result<Self> {
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} in recording line '{}'", what, line),
+            )
+        };
+
+        let mut fields = line.split_whitespace();
+        let timestamp = fields
+            .next()
+            .and_then(|t| t.parse::<f64>().ok())
+            .ok_or_else(|| invalid("bad timestamp"))?;
+        let address = fields
+            .next()
+            .and_then(|a| u8::from_str_radix(a, 16).ok())
+            .ok_or_else(|| invalid("bad address"))?;
+
+        let mut ops = Vec::new();
+        let mut error = None;
+        while let Some(field) = fields.next() {
+            if field == "!" {
+                let name = fields.next().ok_or_else(|| invalid("missing error"))?;
+                error = Some(kind_from_name(name).ok_or_else(|| invalid("unknown error"))?);
+                break;
+            }
+
+            let (kind, hex) = field
+                .split_once(':')
+                .ok_or_else(|| invalid("bad operation"))?;
+            let bytes = from_hex(hex).ok_or_else(|| invalid("bad hex data"))?;
+            match kind {
+                "w" => ops.push(RecordedOp::Write(bytes)),
+                "r" => ops.push(RecordedOp::Read(bytes)),
+                _ => return Err(invalid("bad operation")),
+            }
+        }
+
+        Ok(Self {
+            timestamp,
+            address,
+            ops,
+            error,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => Some((hex_digit(*hi)? << 4) | hex_digit(*lo)?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+fn kind_name(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => "nack-address",
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data) => "nack-data",
+        ErrorKind::NoAcknowledge(_) => "nack",
+        ErrorKind::Bus => "bus",
+        ErrorKind::ArbitrationLoss => "arbitration",
+        ErrorKind::Overrun => "overrun",
+        _ => "other",
+    }
+}
+
+fn kind_from_name(name: &str) -> Option<ErrorKind> {
+    Some(match name {
+        "nack-address" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        "nack-data" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        "nack" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+        "bus" => ErrorKind::Bus,
+        "arbitration" => ErrorKind::ArbitrationLoss,
+        "overrun" => ErrorKind::Overrun,
+        "other" => ErrorKind::Other,
+        _ => return None,
+    })
+}
+
+/// I2C bus wrapper that appends every transaction to a recording
+pub struct RecordingI2c<I2C, W: Write> {
+    i2c: I2C,
+    out: W,
+}
+
+impl<I2C: I2c> RecordingI2c<I2C, BufWriter<File>> {
+    /// Record into a new file at `path`
+    pub fn create<P: AsRef<Path>>(i2c: I2C, path: P) -> io::Result<Self> {
+        Self::new(i2c, BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<I2C: I2c, W: Write> RecordingI2c<I2C, W> {
+    /// Record into any writer; the header line is written right away
+    pub fn new(i2c: I2C, mut out: W) -> io::Result<Self> {
+        writeln!(out, "{}", RECORDING_HEADER)?;
+        out.flush()?;
+        Ok(Self { i2c, out })
+    }
+
+    /// Release the I2C interface and the writer
+    pub fn release(self) -> (I2C, W) {
+        (self.i2c, self.out)
+    }
+}
+
+impl<I2C: I2c, W: Write> ErrorType for RecordingI2c<I2C, W> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c, W: Write> I2c for RecordingI2c<I2C, W> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        let result = self.i2c.transaction(address, operations);
+
+        let record = RecordedTransaction {
+            timestamp,
+            address,
+            ops: operations
+                .iter()
+                .map(|op| match op {
+                    Operation::Write(bytes) => RecordedOp::Write(bytes.to_vec()),
+                    Operation::Read(buf) => RecordedOp::Read(buf.to_vec()),
+                })
+                .collect(),
+            error: result.as_ref().err().map(|e| e.kind()),
+        };
+
+        // A full disk must not take the sensors down with it
+        if let Err(e) = writeln!(self.out, "{}", record).and_then(|_| self.out.flush()) {
+            log::warn!("Could not write I2C recording: {}", e);
+        }
+
+        result
+    }
+}
+
+/// Errors of `ReplayI2c`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The recorded transaction failed with this error
+    Recorded(ErrorKind),
+    /// All recorded transactions were used up
+    EndOfRecording,
+    /// The driver issued a different transaction than the one recorded
+    Mismatch {
+        index: usize,
+        expected: String,
+        got: String,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Recorded(kind) => write!(f, "recorded I2C error: {}", kind),
+            ReplayError::EndOfRecording => write!(f, "no more recorded I2C transactions"),
+            ReplayError::Mismatch {
+                index,
+                expected,
+                got,
+            } => write!(
+                f,
+                "transaction {} differs from recording: expected '{}', got '{}'",
+                index, expected, got
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Error for ReplayError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ReplayError::Recorded(kind) => *kind,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// I2C bus that serves the responses of a recording back in order.
+///
+/// Every transaction must match the recorded address, operation types and
+/// written bytes; reads get the recorded bytes. Timestamps are ignored, so
+/// replays run as fast as the driver asks.
+pub struct ReplayI2c {
+    transactions: Vec<RecordedTransaction>,
+    next: usize,
+}
+
+impl ReplayI2c {
+    /// Load the recording at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse a recording from any reader
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut transactions = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            transactions.push(RecordedTransaction::parse(line)?);
+        }
+        Ok(Self::new(transactions))
+    }
+
+    pub fn new(transactions: Vec<RecordedTransaction>) -> Self {
+        Self {
+            transactions,
+            next: 0,
+        }
+    }
+
+    /// Transactions not replayed yet
+    pub fn remaining(&self) -> usize {
+        self.transactions.len() - self.next
+    }
+
+    /// Whether every recorded transaction was replayed
+    pub fn is_done(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+impl ErrorType for ReplayI2c {
+    type Error = ReplayError;
+}
+
+impl I2c for ReplayI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let index = self.next;
+        let recorded = self
+            .transactions
+            .get(index)
+            .ok_or(ReplayError::EndOfRecording)?;
+
+        let matches = recorded.address == address
+            && recorded.ops.len() == operations.len()
+            && recorded
+                .ops
+                .iter()
+                .zip(operations.iter())
+                .all(|(rec, op)| match (rec, op) {
+                    (RecordedOp::Write(r), Operation::Write(b)) => r.as_slice() == *b,
+                    (RecordedOp::Read(r), Operation::Read(b)) => r.len() == b.len(),
+                    _ => false,
+                });
+
+        if !matches {
+            let got = RecordedTransaction {
+                timestamp: recorded.timestamp,
+                address,
+                ops: operations
+                    .iter()
+                    .map(|op| match op {
+                        Operation::Write(bytes) => RecordedOp::Write(bytes.to_vec()),
+                        Operation::Read(buf) => RecordedOp::Read(vec![0; buf.len()]),
+                    })
+                    .collect(),
+                error: None,
+            };
+            return Err(ReplayError::Mismatch {
+                index,
+                expected: recorded.to_string(),
+                got: got.to_string(),
+            });
+        }
+
+        self.next += 1;
+        if let Some(kind) = recorded.error {
+            return Err(ReplayError::Recorded(kind));
+        }
+
+        for (rec, op) in recorded.ops.iter().zip(operations.iter_mut()) {
+            if let (RecordedOp::Read(bytes), Operation::Read(buf)) = (rec, op) {
+                buf.copy_from_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, I2c, Operation};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Default rule: retry bus level glitches, not programming or unknown errors
+pub fn default_retryable(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::Bus
+            | ErrorKind::ArbitrationLoss
+            | ErrorKind::NoAcknowledge(_)
+            | ErrorKind::Overrun
+    )
+}
+
+/// How often and how patiently to retry a failed transaction
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Total attempts per transaction, including the first one
+    pub attempts: u32,
+    /// Wait before the first retry; doubled for every further retry
+    pub backoff: Duration,
+    /// Upper bound for the doubled wait
+    pub max_backoff: Duration,
+    /// Which errors are worth another attempt
+    pub retryable: fn(ErrorKind) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(100),
+            retryable: default_retryable,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy with `attempts` tries and the default backoff and rule
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn retry_if(mut self, retryable: fn(ErrorKind) -> bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Wait before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Counters for one I2C address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AddressStats {
+    /// Transactions started by drivers
+    pub transactions: u64,
+    /// Failed attempts, whether retried or not
+    pub errors: u64,
+    /// Attempts repeated after an error
+    pub retries: u64,
+    /// Transactions that still failed after the last attempt
+    pub failures: u64,
+}
+
+/// Shared per-address counters of a `RetryI2c`.
+///
+/// The wrapper itself usually lives inside a driver, so it hands out this
+/// clonable handle for the application to query or export.
+#[derive(Clone, Debug, Default)]
+pub struct RetryStats(Arc<Mutex<BTreeMap<u8, AddressStats>>>);
+
+impl RetryStats {
+    /// Counters for `addr` (all zero if it was never used)
+    pub fn get(&self, addr: u8) -> AddressStats {
+        self.lock().get(&addr).copied().unwrap_or_default()
+    }
+
+    /// Counters of every address seen so far, ordered by address
+    pub fn snapshot(&self) -> Vec<(u8, AddressStats)> {
+        self.lock().iter().map(|(a, s)| (*a, *s)).collect()
+    }
+
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    /// Export as CSV with a header line, one row per address
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("address,transactions,errors,retries,failures\n");
+        for (addr, s) in self.snapshot() {
+            let _ = writeln!(
+                out,
+                "0x{:02X},{},{},{},{}",
+                addr, s.transactions, s.errors, s.retries, s.failures
+            );
+        }
+        out
+    }
+
+    fn update(&self, addr: u8, f: impl FnOnce(&mut AddressStats)) {
+        f(self.lock().entry(addr).or_default());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<u8, AddressStats>> {
+        // Counters stay usable even if a thread panicked while holding them
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// I2C bus wrapper that retries failed transactions according to a
+/// `RetryPolicy` and counts errors and retries per address.
+pub struct RetryI2c<I2C> {
+    i2c: I2C,
+    policy: RetryPolicy,
+    stats: RetryStats,
+}
+
+impl<I2C: I2c> RetryI2c<I2C> {
+    pub fn new(i2c: I2C, policy: RetryPolicy) -> Self {
+        Self {
+            i2c,
+            policy,
+            stats: RetryStats::default(),
+        }
+    }
+
+    /// Handle to the counters, still valid after the wrapper moved into a driver
+    pub fn stats(&self) -> RetryStats {
+        self.stats.clone()
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: I2c> ErrorType for RetryI2c<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c> I2c for RetryI2c<I2C> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.stats.update(address, |s| s.transactions += 1);
+
+        let mut attempt = 1;
+        loop {
+            let error = match self.i2c.transaction(address, operations) {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            self.stats.update(address, |s| s.errors += 1);
+
+            let kind = error.kind();
+            if attempt >= self.policy.attempts || !(self.policy.retryable)(kind) {
+                self.stats.update(address, |s| s.failures += 1);
+                return Err(error);
+            }
+
+            log::debug!(
+                "I2C 0x{:02X} attempt {}/{} failed ({:?}), retrying",
+                address,
+                attempt,
+                self.policy.attempts,
+                kind
+            );
+            thread::sleep(self.policy.delay(attempt));
+            self.stats.update(address, |s| s.retries += 1);
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+use std::{cell::RefCell, rc::Rc};
+
+/// Clonable handle to one I2C bus.
+///
+/// Every clone implements `I2c` and talks through the same bus, so the
+/// drivers of the devices on one adapter can share a single connection
+/// (and its reconnect and retry state) instead of opening their own. Each
+/// transaction borrows the bus until it finishes; the handles are not
+/// `Send`, so sharing stays on one thread.
+pub struct SharedI2c<I2C>(Rc<RefCell<I2C>>);
+
+impl<I2C> SharedI2c<I2C> {
+    pub fn new(i2c: I2C) -> Self {
+        Self(Rc::new(RefCell::new(i2c)))
+    }
+
+    /// Borrow the bus for direct access
+    pub fn with<R>(&self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        f(&mut self.0.borrow_mut())
+    }
+
+    /// Number of handles to the bus, this one included
+    pub fn handles(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+}
+
+impl<I2C> Clone for SharedI2c<I2C> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<I2C: I2c> ErrorType for SharedI2c<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c> I2c for SharedI2c<I2C> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().transaction(address, operations)
+    }
+}
@@ -3,6 +3,19 @@
 #![allow(unused_variables)]
 
 pub mod ads1115;
+pub mod calibration;
+pub mod cli;
+pub mod config;
 pub mod df0991;
+pub mod ds18b20;
+pub mod ec;
 pub mod i2c;
+pub mod lm35;
+pub mod mcp2221;
+pub mod ph;
+pub mod ph_calibrator;
+pub mod sensor;
+pub mod sim;
+pub mod stability;
 pub mod temperature;
+pub mod units;
@@ -0,0 +1,176 @@
+use crate::ads1115::{is_clipped, pga_for_voltage, AdsSensor, Mux, Pga};
+use crate::i2c::lock::LockedI2c;
+use crate::sensor::{Measurement, Quality, Quantity, Sensor};
+use crate::units::{Temperature, Unit, Voltage};
+use embedded_hal::i2c::{ErrorType, I2c};
+
+/// LM35 output slope (volts per °C)
+pub const LM35_VOLTS_PER_C: f32 = 0.010;
+
+/// How the LM35 output is wired to the ADC
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lm35Circuit {
+    /// Basic sensor: 0 V at 0 °C, 2 °C to 150 °C
+    Basic,
+    /// LM35 ground lifted by `offset` volts (diodes or a divider) so the
+    /// output stays positive below 0 °C: Vout = offset + 10 mV/°C * T
+    Offset { offset: f32 },
+}
+
+/// LM35/LM35DZ temperature conversion with two-point style calibration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lm35 {
+    pub circuit: Lm35Circuit,
+    /// Multiplier applied to the raw temperature (1.0 = datasheet slope)
+    pub gain: f32,
+    /// Added after the gain (°C)
+    pub offset: f32,
+    /// Highest temperature to measure, used to pick the PGA (°C)
+    pub max_temperature: f32,
+}
+
+impl Default for Lm35 {
+    /// Basic circuit, uncalibrated, up to 100 °C (the LM35DZ range)
+    fn default() -> Self {
+        Self {
+            circuit: Lm35Circuit::Basic,
+            gain: 1.0,
+            offset: 0.0,
+            max_temperature: 100.0,
+        }
+    }
+}
+
+impl Lm35 {
+    /// Sensor with its ground lifted by `offset` volts
+    pub fn with_offset_circuit(mut self, offset: f32) -> Self {
+        self.circuit = Lm35Circuit::Offset { offset };
+        self
+    }
+
+    /// Correct the reading as `gain * T + offset`
+    pub fn with_calibration(mut self, gain: f32, offset: f32) -> Self {
+        self.gain = gain;
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_max_temperature(mut self, max_temperature: f32) -> Self {
+        self.max_temperature = max_temperature;
+        self
+    }
+
+    /// Output voltage of the LM35 at `temperature`, before calibration
+    pub fn output_voltage(&self, temperature: Temperature) -> Voltage {
+        Voltage::from_volts(self.ground_offset() + temperature.celsius() * LM35_VOLTS_PER_C)
+    }
+
+    /// Temperature from the voltage measured at the ADC
+    pub fn voltage_to_temperature(&self, measured_voltage: Voltage) -> Temperature {
+        let raw = (measured_voltage.volts() - self.ground_offset()) / LM35_VOLTS_PER_C;
+        Temperature::from_celsius(raw * self.gain + self.offset)
+    }
+
+    /// Voltage at the ADC for a calibrated reading of `temperature`, the
+    /// inverse of `voltage_to_temperature`
+    pub fn temperature_to_voltage(&self, temperature: Temperature) -> Voltage {
+        let raw = (temperature.celsius() - self.offset) / self.gain;
+        self.output_voltage(Temperature::from_celsius(raw))
+    }
+
+    /// Smallest PGA range that covers `max_temperature`
+    pub fn pga(&self) -> Pga {
+        let max_temperature = Temperature::from_celsius(self.max_temperature);
+        pga_for_voltage(self.output_voltage(max_temperature).volts())
+    }
+
+    fn ground_offset(&self) -> f32 {
+        match self.circuit {
+            Lm35Circuit::Basic => 0.0,
+            Lm35Circuit::Offset { offset } => offset,
+        }
+    }
+}
+
+/// LM35 read through an ADS1115 channel
+pub struct Lm35Sensor<I2C: ErrorType> {
+    ads: AdsSensor<I2C>,
+    lm35: Lm35,
+}
+
+impl<I2C, E> Lm35Sensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// ADS1115 channel `mux` at `addr` with the PGA chosen for `lm35`
+    pub fn new(i2c: I2C, addr: u8, mux: Mux, lm35: Lm35) -> Result<Self, E> {
+        let ads = AdsSensor::new(i2c, addr, mux, lm35.pga(), "LM35DZ Temp", Unit::Celsius)?;
+        Ok(Self { ads, lm35 })
+    }
+
+    /// Name reported in logs and measurements instead of the default
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.ads.name = name.to_string();
+        self
+    }
+
+    pub fn lm35(&self) -> &Lm35 {
+        &self.lm35
+    }
+
+    /// Read the channel and convert to temperature
+    pub fn get_temperature(&mut self) -> Result<Temperature, E> {
+        let voltage = Voltage::from_volts(self.ads.get_voltage()?);
+        Ok(self.lm35.voltage_to_temperature(voltage))
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.ads.release()
+    }
+}
+
+impl<I2C: I2c> Lm35Sensor<LockedI2c<I2C>> {
+    /// Hold the bus lock across each whole conversion
+    pub fn with_bus_lock(mut self) -> Self {
+        self.ads = self.ads.with_bus_lock();
+        self
+    }
+}
+
+impl<I2C, E> Sensor for Lm35Sensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    fn id(&self) -> &str {
+        &self.ads.name
+    }
+
+    fn quantity(&self) -> Quantity {
+        Quantity::Temperature
+    }
+
+    /// Temperature in °C; a basic circuit pinned at 0 V is below its range
+    fn measure(&mut self) -> Result<Measurement, E> {
+        let voltage = self.ads.get_voltage()?;
+        let temperature = self
+            .lm35
+            .voltage_to_temperature(Voltage::from_volts(voltage));
+
+        let mut quality = Quality::GOOD;
+        if is_clipped(voltage, self.ads.full_scale()) {
+            quality.insert(Quality::CLIPPED);
+        }
+        let below_zero = self.lm35.circuit == Lm35Circuit::Basic && voltage <= 0.0;
+        if below_zero || temperature.celsius() > self.lm35.max_temperature {
+            quality.insert(Quality::OUT_OF_RANGE);
+        }
+
+        Ok(
+            Measurement::new(&self.ads.name, Quantity::Temperature, temperature.celsius())
+                .with_quality(quality),
+        )
+    }
+}
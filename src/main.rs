@@ -2,10 +2,56 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
 
-use embedded_hal::i2c::I2c;
+use clap::Parser;
+use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_hal::i2c::{ErrorType, I2c};
+use hydro_sense::ads1115::mux_for_channel;
+use hydro_sense::calibration::CalibrationStore;
+use hydro_sense::cli::{
+    AdapterList, BusArgs, CalibrationReport, Cli, Command, DeviceReport, Format, MeasurementReport,
+    ScanReport, Target,
+};
+use hydro_sense::config::{
+    AdapterConfig, Config, DeviceKind, ModelConfig, SensorConfig, DEFAULT_CONFIG_FILE,
+};
 use hydro_sense::df0991::*;
+use hydro_sense::i2c::lock::{BusLock, LockedI2c, DEFAULT_LOCK_FILE};
+use hydro_sense::i2c::retry::{RetryI2c, RetryPolicy};
+use hydro_sense::i2c::shared::SharedI2c;
+use hydro_sense::i2c::{
+    list_adapters, scan, AdapterSource, ReconnectingI2c, SysfsAdapter, SCAN_ADDRESSES,
+};
+use hydro_sense::ph::{PhCalibration, PhSensor, REFERENCE_TEMPERATURE};
+use hydro_sense::ph_calibrator::{CalibrationAbort, CalibrationState, PhCalibrator};
+use hydro_sense::sensor::{Measurement, Quantity, Sensor};
+use hydro_sense::units::{Ph, Temperature};
+use ssd1306::prelude::*;
+use ssd1306::rotation::DisplayRotation;
+use ssd1306::size::DisplaySize128x64;
+use ssd1306::{I2CDisplayInterface, Ssd1306};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+/// Reconnecting and retrying bus on one adapter, shared by its devices
+type SharedBus = SharedI2c<RetryI2c<ReconnectingI2c<SysfsAdapter>>>;
+
+/// One driver's handle to the shared bus of an adapter, holding its lock
+type Bus = LockedI2c<SharedBus>;
+
+/// Configured sensor built on its adapter's `Bus`
+type BoxedSensor = Box<dyn Sensor<Error = <Bus as ErrorType>::Error>>;
+
+/// Default address of the SSD1306 OLED
+const SSD1306_DEFAULT_I2C_ADDR: u8 = 0x3C;
+
+/// Time between two polls of the button
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 // ┌──────────────────────────────────────────────────────────────┐
 // │                      Initialize App State                    │
 // │                                                              │
@@ -14,13 +60,11 @@ use std::time::{Duration, Instant};
 // │ and signals whether the display needs to be redrawn.         │
 // └──────────────────────────────────────────────────────────────┘
 struct AppState {
-    btn_press: bool,
+    button: ButtonDebouncer,
+    press: PressDetector,
     state_changed: bool,
-    last_change: Instant,
 }
 
-const DEBOUNCE_TIME: Duration = Duration::from_millis(50);
-
 // ┌──────────────────────────────────────────────────────────────┐
 // │                     Check Button Press                       │
 // │                                                              │
@@ -37,46 +81,765 @@ where
 {
     let current_pressed = button.get_button_status()?;
 
-    if current_pressed != state.btn_press {
-        // Check how long since last change
+    // Changes within the debounce time are ignored as bouncing
+    if state
+        .button
+        .update(current_pressed, Instant::now())
+        .is_some()
+    {
+        state.state_changed = true;
+    }
+
+    Ok(())
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                    Open I2C Device Adapter                   │
+// │                                                              │
+// │ Find the I2C adapter by its sysfs name (e.g. "MCP2221") and  │
+// │ open it. If either step fails, we bail out.                  │
+// │                                                              │
+// │ If the adapter is unplugged later on, the bus waits for it   │
+// │ to come back (under any /dev/i2c-N), reopens it and checks   │
+// │ the RGB button (if there is one) again before carrying on.   │
+// │ Transactions that are dropped on the USB link are retried a  │
+// │ few times first.                                             │
+// │                                                              │
+// │ Every transaction holds the shared bus lock file, and the    │
+// │ sensors hold it across each whole ADS1115 conversion, so     │
+// │ calibration tools and tests running at the same time cannot  │
+// │ interleave with us.                                          │
+// │                                                              │
+// │ Each adapter is opened once; the sensors, the button and the │
+// │ pH calibration all get a handle to the same bus and lock.    │
+// └──────────────────────────────────────────────────────────────┘
+struct Buses {
+    button_addr: Option<u8>,
+    opened: HashMap<String, (SharedBus, BusLock)>,
+}
+
+impl Buses {
+    /// No bus opened yet; reconnects check the button at `button_addr`
+    fn new(button_addr: Option<u8>) -> Self {
+        Self {
+            button_addr,
+            opened: HashMap::new(),
+        }
+    }
+
+    /// Handle to the bus of `adapter`, opened on first use
+    fn open(&mut self, adapter: &AdapterConfig) -> io::Result<Bus> {
+        if !self.opened.contains_key(&adapter.name) {
+            let i2c = SharedI2c::new(open_bus(adapter, self.button_addr)?);
+            let lock = BusLock::open(lock_file(adapter))?;
+            self.opened.insert(adapter.name.clone(), (i2c, lock));
+        }
+        let (i2c, lock) = &self.opened[&adapter.name];
+        Ok(LockedI2c::new(i2c.clone(), lock.clone()))
+    }
+}
+
+fn open_bus(
+    adapter: &AdapterConfig,
+    button_addr: Option<u8>,
+) -> io::Result<RetryI2c<ReconnectingI2c<SysfsAdapter>>> {
+    let mut i2c = ReconnectingI2c::new(SysfsAdapter::new(&adapter.sysfs_name))?;
+    if let Some(button_addr) = button_addr {
+        i2c = i2c.on_reconnect(move |bus| {
+            let mut button = DFRobotRGBButton::new(bus, button_addr)?;
+            if !button.begin()? {
+                log::warn!("RGB button not detected after reconnect.");
+            }
+            Ok(())
+        });
+    }
+    Ok(RetryI2c::new(i2c, RetryPolicy::default()))
+}
+
+fn lock_file(adapter: &AdapterConfig) -> &Path {
+    adapter
+        .lock_file
+        .as_deref()
+        .unwrap_or(Path::new(DEFAULT_LOCK_FILE))
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                      Load Configuration                      │
+// │                                                              │
+// │ Read adapters, devices and sensors from the --config file,   │
+// │ or hydro-sense.toml in the working directory. Without        │
+// │ either, the built-in stock setup is used.                    │
+// └──────────────────────────────────────────────────────────────┘
+fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
+    let config = match path {
+        Some(path) => Config::load(path)?,
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => Config::load(DEFAULT_CONFIG_FILE)?,
+        None => Config::default(),
+    };
+    Ok(config)
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                   Check Calibration Ages                     │
+// │                                                              │
+// │ Warn about pH and EC probes that were never calibrated or    │
+// │ whose last calibration is older than the configured age.     │
+// └──────────────────────────────────────────────────────────────┘
+fn check_calibrations(config: &Config, store: &CalibrationStore) {
+    let now = std::time::SystemTime::now();
+    for sensor in &config.sensors {
+        if let Some(probe) = &sensor.calibration {
+            let status = store.status(probe, now);
+            if status.needs_calibration() {
+                log::warn!("{}: {}", sensor.name, status);
+            } else {
+                log::info!("{}: {}", sensor.name, status);
+            }
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                  Initialize RGB Button Device                │
+// │                                                              │
+// │ - Use the address from the options, else the configured one  │
+// │   (0x2A unless changed by the address switch)                │
+// │ - Create new instance of DFRobotRGBButton on the I2C bus     │
+// │ - Call `begin()` to verify presence and read part ID         │
+// │ - If detection fails, bail out                               │
+// └──────────────────────────────────────────────────────────────┘
+fn button_target(config: &Config, bus: &BusArgs) -> anyhow::Result<Target> {
+    bus.target(config, DeviceKind::Df0991, RGBBUTTON_DEFAULT_I2C_ADDR)
+        .ok_or_else(|| anyhow::anyhow!("no I2C adapter given or configured"))
+}
+
+fn open_button(target: &Target, buses: &mut Buses) -> anyhow::Result<DFRobotRGBButton<Bus>> {
+    let i2c = buses.open(&target.adapter)?;
+    let mut button = DFRobotRGBButton::new(i2c, target.address)
+        .map_err(|e| anyhow::anyhow!("RGB button: {:?}", e))?;
+    let found = button
+        .begin()
+        .map_err(|e| anyhow::anyhow!("RGB button: {:?}", e))?;
+    if !found {
+        anyhow::bail!(
+            "RGB button not detected at 0x{:02X} on {}",
+            target.address,
+            target.adapter.name
+        );
+    }
+    Ok(button)
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                     Open the pH Sensor                       │
+// │                                                              │
+// │ Open the PH4502C sensor `name` (or the first one configured) │
+// │ on the bus of its adapter, with its stored calibration if    │
+// │ there is one.                                                │
+// └──────────────────────────────────────────────────────────────┘
+fn open_ph_sensor(
+    config: &Config,
+    store: &CalibrationStore,
+    name: Option<&str>,
+    buses: &mut Buses,
+) -> anyhow::Result<Option<(SensorConfig, PhSensor<Bus>)>> {
+    let found = match name {
+        Some(name) => {
+            let sensor = config
+                .sensor(name)
+                .ok_or_else(|| anyhow::anyhow!("no sensor named '{}'", name))?;
+            if sensor.model.ph4502c().is_none() {
+                anyhow::bail!("'{}' is not a pH sensor", name);
+            }
+            Some(sensor)
+        }
+        None => config.sensors.iter().find(|s| s.model.ph4502c().is_some()),
+    };
+    let Some(sensor) = found else {
+        return Ok(None);
+    };
+
+    let device = config.device(&sensor.device).expect("validated device");
+    let adapter = config.device_adapter(device).expect("validated adapter");
+    let mux = mux_for_channel(sensor.channel).expect("validated channel");
+    let mut probe = sensor.model.ph4502c().expect("pH model");
+    let stored = sensor.calibration.as_deref().and_then(|p| store.current(p));
+    if let Some(calibration) = stored.and_then(|r| r.ph_calibration()) {
+        probe = probe.with_calibration(calibration);
+    }
+    let bus = buses.open(adapter)?;
+    let ph_sensor = PhSensor::new(bus, device.address, mux, probe)
+        .map_err(|e| anyhow::anyhow!("{}: {:?}", sensor.name, e))?
+        .with_name(&sensor.name)
+        .with_bus_lock();
+    Ok(Some((sensor.clone(), ph_sensor)))
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                Measure the Buffer Temperature                │
+// │                                                              │
+// │ Read the first NTC sensor, taken as the temperature of the   │
+// │ water the pH buffers stand in, so the slope is scaled from   │
+// │ the right temperature. Without one, or when it cannot be     │
+// │ read, the buffers are assumed to be at 25 °C.                │
+// └──────────────────────────────────────────────────────────────┘
+fn buffer_temperature(config: &Config, store: &CalibrationStore, buses: &mut Buses) -> Temperature {
+    let reference = Temperature::from_celsius(REFERENCE_TEMPERATURE);
+    let Some(ntc) = config
+        .sensors
+        .iter()
+        .find(|s| matches!(s.model, ModelConfig::Ntc { .. }))
+    else {
+        log::info!(
+            "No water temperature sensor, assuming buffers at {}",
+            reference
+        );
+        return reference;
+    };
+
+    let measured = config
+        .build_calibrated_sensor(ntc, store, |adapter| buses.open(adapter))
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|mut sensor| sensor.measure().map_err(|e| format!("{:?}", e)));
+    match measured {
+        Ok(measurement) if measurement.is_good() => {
+            let temperature = Temperature::from_celsius(measurement.value);
+            log::info!("Buffers at {} ({})", temperature, ntc.name);
+            temperature
+        }
+        Ok(measurement) => {
+            log::warn!("{}, assuming buffers at {}", measurement, reference);
+            reference
+        }
+        Err(e) => {
+            log::warn!("{}: {}, assuming buffers at {}", ntc.name, e, reference);
+            reference
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                   Step the pH Calibration                    │
+// │                                                              │
+// │ Feed the calibration with the pH module voltage. Once it     │
+// │ finishes, the new calibration goes to the sensor and, if the │
+// │ probe has a store key, into the calibration store.           │
+// │                                                              │
+// │ A failed read is logged and retried on the next pass.        │
+// └──────────────────────────────────────────────────────────────┘
+fn step_calibration(
+    run: &mut PhCalibrator,
+    sensor: &mut PhSensor<Bus>,
+    probe_key: Option<&str>,
+    store: &mut CalibrationStore,
+    now: Instant,
+) -> Option<Result<PhCalibration, CalibrationAbort>> {
+    let voltage = match sensor.get_voltage() {
+        Ok(voltage) => Some(voltage),
+        Err(e) => {
+            log::error!("pH read failed: {:?}", e);
+            None
+        }
+    };
+    let outcome = run.update(now, voltage);
+    if let Some(Ok(_)) = outcome {
+        *sensor.probe_mut() = *run.probe();
+        log::info!(
+            "pH calibration done: slope {:.1}% of ideal, health {}",
+            run.probe().slope_efficiency(),
+            run.probe().health()
+        );
+        if let (Some(probe), Some(record)) = (probe_key, run.record()) {
+            store.record(probe, record);
+            if let Err(e) = store.save() {
+                log::error!("Saving the calibration failed: {}", e);
+            }
+        }
+    }
+    if let Some(buffer) = run.current_buffer() {
+        log::debug!(
+            "pH calibration: {:?} ({}), drift {:?} V/s",
+            run.state(),
+            buffer,
+            run.stability().drift_rate()
+        );
+    }
+    outcome
+}
+
+/// Show `color` on the button unless it already shows it
+fn show_led(
+    button: &mut DFRobotRGBButton<Bus>,
+    led: &mut Option<GeneralRGBColor>,
+    color: GeneralRGBColor,
+) {
+    if *led != Some(color) {
+        match button.set_rgb_color_enum(color) {
+            Ok(()) => *led = Some(color),
+            Err(e) => log::error!("Setting the LED failed: {:?}", e),
+        }
+    }
+}
+
+/// Debounced button state, starting from what the button reads now
+fn app_state(button: &mut DFRobotRGBButton<Bus>) -> anyhow::Result<AppState> {
+    let initial_press = button
+        .get_button_status()
+        .map_err(|e| anyhow::anyhow!("RGB button: {:?}", e))?;
+    Ok(AppState {
+        button: ButtonDebouncer::new(initial_press, Instant::now()),
+        press: PressDetector::default(),
+        state_changed: false,
+    })
+}
+
+/// Next short or long press, if the button was read
+fn next_press(state: &mut AppState, button: &mut DFRobotRGBButton<Bus>) -> Option<ButtonEvent> {
+    if let Err(e) = check_press(state, button) {
+        log::error!("Button read failed: {:?}", e);
+    }
+    if state.state_changed {
+        log::debug!("Button pressed: {}", state.button.is_pressed());
+        state.state_changed = false;
+    }
+    state
+        .press
+        .update(state.button.is_pressed(), Instant::now())
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                     Build the Sensors                        │
+// │                                                              │
+// │ Build the `names` sensors (all of them if empty) on the bus  │
+// │ of their adapter, each with its stored calibration. The      │
+// │ adapter and address options move their ADS1115s.             │
+// └──────────────────────────────────────────────────────────────┘
+fn build_sensors(
+    config: &Config,
+    store: &CalibrationStore,
+    names: &[String],
+    bus: &BusArgs,
+    buses: &mut Buses,
+) -> anyhow::Result<(Config, Vec<(SensorConfig, BoxedSensor)>)> {
+    let mut config = config.clone();
+    let selected: Vec<SensorConfig> = if names.is_empty() {
+        config.sensors.clone()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                config
+                    .sensor(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("no sensor named '{}'", name))
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+    for sensor in &selected {
+        bus.apply(&mut config, &sensor.device)?;
+    }
+
+    let mut sensors = Vec::new();
+    for sensor in selected {
+        let built =
+            config.build_calibrated_sensor(&sensor, store, |adapter| buses.open(adapter))?;
+        sensors.push((sensor, built));
+    }
+    Ok((config, sensors))
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                  Compensate pH and EC Readings               │
+// │                                                              │
+// │ Good temperature readings are kept by sensor name. Before a  │
+// │ pH or EC sensor is read, it gets the latest reading of its   │
+// │ `compensate_with` sensor; without one it reads at 25 °C and  │
+// │ flags the result as uncompensated.                           │
+// └──────────────────────────────────────────────────────────────┘
+fn compensate(
+    sensor: &SensorConfig,
+    built: &mut BoxedSensor,
+    latest: &HashMap<String, Temperature>,
+) {
+    if let Some(source) = &sensor.compensate_with {
+        built.set_sample_temperature(latest.get(source).copied());
+    }
+}
+
+fn record_temperature(measurement: &Measurement, latest: &mut HashMap<String, Temperature>) {
+    if measurement.quantity != Quantity::Temperature {
+        return;
+    }
+    let name = measurement.sensor_id.clone();
+    if measurement.is_good() {
+        latest.insert(name, Temperature::from_celsius(measurement.value));
+    } else {
+        latest.remove(&name);
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                       List Adapters                          │
+// └──────────────────────────────────────────────────────────────┘
+fn run_adapters(config: &Config, format: Format) -> anyhow::Result<()> {
+    // No sysfs class at all: no adapter driver is loaded
+    let adapters = match list_adapters() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        adapters => adapters?,
+    };
+    let adapters = AdapterList::new(adapters, config);
+    println!("{}", format.render(&adapters));
+    Ok(())
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                         Scan a Bus                           │
+// │                                                              │
+// │ Read one byte from every address (or only the one given) on  │
+// │ the raw adapter, holding the bus lock for the whole scan so  │
+// │ nobody else's transaction is taken for a device. Missing     │
+// │ devices are not retried.                                     │
+// └──────────────────────────────────────────────────────────────┘
+fn run_scan(config: &Config, bus: &BusArgs, format: Format) -> anyhow::Result<()> {
+    let adapter = bus
+        .adapter(config, None)
+        .ok_or_else(|| anyhow::anyhow!("no I2C adapter given or configured"))?;
+    let mut source = SysfsAdapter::new(&adapter.sysfs_name);
+    let location = source.locate()?;
+    let mut i2c = source.open(&location)?;
+
+    let lock = BusLock::open(lock_file(&adapter))?;
+    let found = {
+        let _guard = lock.hold()?;
+        let addresses = bus.address.map_or(SCAN_ADDRESSES, |a| a..=a);
+        scan(&mut i2c, addresses)
+    };
+
+    let report = ScanReport::new(&adapter.name, &location, &found, config);
+    println!("{}", format.render(&report));
+    Ok(())
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                       Read the Sensors                       │
+// │                                                              │
+// │ Take one reading of each selected sensor. A sensor that      │
+// │ cannot be read is logged; the command fails at the end.      │
+// │                                                              │
+// │ Temperatures are read first, so pH and EC are compensated    │
+// │ with this reading; a temperature sensor that only serves for │
+// │ compensation is read but not reported.                       │
+// └──────────────────────────────────────────────────────────────┘
+fn run_read(
+    config: &Config,
+    names: &[String],
+    bus: &BusArgs,
+    format: Format,
+) -> anyhow::Result<()> {
+    let store = config.calibration.open_store()?;
+    let mut buses = Buses::new(config.first_device(DeviceKind::Df0991).map(|d| d.address));
+    let mut selected = names.to_vec();
+    for name in names {
+        let source = config.sensor(name).and_then(|s| s.compensate_with.clone());
+        if let Some(source) = source.filter(|source| !selected.contains(source)) {
+            selected.push(source);
+        }
+    }
+    let (config, mut sensors) = build_sensors(config, &store, &selected, bus, &mut buses)?;
+
+    let mut order: Vec<usize> = (0..sensors.len()).collect();
+    order.sort_by_key(|&i| !sensors[i].0.model.is_temperature());
+    let mut latest = HashMap::new();
+    let mut results: Vec<_> = sensors.iter().map(|_| None).collect();
+    for i in order {
+        let (sensor, built) = &mut sensors[i];
+        compensate(sensor, built, &latest);
+        let result = built.measure();
+        if let Ok(measurement) = &result {
+            record_temperature(measurement, &mut latest);
+        }
+        results[i] = Some(result);
+    }
+
+    let mut failed = 0;
+    let mut reported = 0;
+    for ((sensor, _), result) in sensors.iter().zip(results) {
+        if !names.is_empty() && !names.contains(&sensor.name) {
+            continue;
+        }
+        reported += 1;
+        match result.expect("every sensor is read") {
+            Ok(measurement) => {
+                let report = MeasurementReport::new(&measurement, &config);
+                println!("{}", format.render(&report));
+            }
+            Err(e) => {
+                log::error!("{}: read failed: {:?}", sensor.name, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} sensors could not be read", failed, reported);
+    }
+    Ok(())
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                           Monitor                            │
+// │                                                              │
+// │ Continuously run the event loop:                             │
+// │                                                              │
+// │ 1. Check the button press and update the application state.  │
+// │ 2. Turn the debounced state into short and long presses: a   │
+// │    long press starts (or cancels) the pH calibration, a      │
+// │    short press confirms that the next buffer is in.          │
+// │ 3. Feed the calibration, save a finished calibration and     │
+// │    show its step on the LED.                                 │
+// │ 4. Print the readings of the sensors that are due, pH and EC │
+// │    compensated with the latest water temperature.            │
+// │ 5. Sleep briefly to reduce CPU usage and debounce input.     │
+// │                                                              │
+// │ A failed read is logged and retried on the next pass.        │
+// └──────────────────────────────────────────────────────────────┘
+fn run_monitor(config: &Config, bus: &BusArgs, format: Format) -> anyhow::Result<()> {
+    let mut store = config.calibration.open_store()?;
+    check_calibrations(config, &store);
+
+    // The address option is the ADS1115's; the button keeps its own
+    let button_bus = BusArgs {
+        adapter: bus.adapter.clone(),
+        address: None,
+    };
+    let button = button_target(config, &button_bus)?;
+    let mut buses = Buses::new(Some(button.address));
+
+    let (config, mut sensors) = build_sensors(config, &store, &[], bus, &mut buses)?;
+    let mut due: Vec<Instant> = vec![Instant::now(); sensors.len()];
+    let mut ph_cal_btn = open_button(&button, &mut buses)?;
+    let mut ph = open_ph_sensor(&config, &store, None, &mut buses)?;
+    let mut app_state = app_state(&mut ph_cal_btn)?;
+    let mut calibrator: Option<PhCalibrator> = None;
+    let mut led = None;
+    let mut latest = HashMap::new();
+
+    loop {
         let now = Instant::now();
-        if now.duration_since(state.last_change) >= DEBOUNCE_TIME {
-            state.btn_press = current_pressed;
-            state.state_changed = true;
-            state.last_change = now;
+        let event = next_press(&mut app_state, &mut ph_cal_btn);
+        match (event, ph.as_ref(), calibrator.as_mut()) {
+            (Some(ButtonEvent::Long), Some((_, sensor)), None) => {
+                log::info!("Starting pH calibration, insert the pH 7 buffer");
+                let temperature = buffer_temperature(&config, &store, &mut buses);
+                let mut run = PhCalibrator::new(*sensor.probe(), temperature);
+                run.start(now);
+                calibrator = Some(run);
+            }
+            (Some(ButtonEvent::Long), _, Some(run)) => {
+                run.cancel(now);
+            }
+            (Some(ButtonEvent::Short), _, Some(run)) => run.press(now),
+            (Some(_), None, None) => log::warn!("No pH sensor configured to calibrate"),
+            _ => {}
+        }
+
+        if let (Some(run), Some((ph_config, sensor))) = (calibrator.as_mut(), ph.as_mut()) {
+            let probe_key = ph_config.calibration.as_deref();
+            if let Some(Ok(_)) = step_calibration(run, sensor, probe_key, &mut store, now) {
+                // Later readings use the new calibration
+                if let Some(index) = sensors.iter().position(|(s, _)| s.name == ph_config.name) {
+                    let open = |adapter: &AdapterConfig| buses.open(adapter);
+                    match config.build_calibrated_sensor(ph_config, &store, open) {
+                        Ok(rebuilt) => sensors[index].1 = rebuilt,
+                        Err(e) => log::error!("{}: {}", ph_config.name, e),
+                    }
+                }
+            }
         }
-        // else ignore change as it is bouncing
+
+        // The LED is off outside of calibration
+        let color = calibrator.as_ref().and_then(|run| run.led(now));
+        if calibrator.as_ref().is_some_and(|run| !run.is_active()) {
+            calibrator = None;
+        }
+        show_led(
+            &mut ph_cal_btn,
+            &mut led,
+            color.unwrap_or(GeneralRGBColor::Black),
+        );
+
+        for ((sensor, built), next) in sensors.iter_mut().zip(&mut due) {
+            if now < *next {
+                continue;
+            }
+            *next = now + config.poll_interval(sensor);
+            compensate(sensor, built, &latest);
+            match built.measure() {
+                Ok(measurement) => {
+                    record_temperature(&measurement, &mut latest);
+                    let report = MeasurementReport::new(&measurement, &config);
+                    println!("{}", format.render(&report));
+                }
+                Err(e) => log::error!("{}: read failed: {:?}", sensor.name, e),
+            }
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                      Calibrate a pH Probe                    │
+// │                                                              │
+// │ Run one guided calibration right away: the LED asks for each │
+// │ buffer, a short press confirms it, a long press cancels.     │
+// │ The new calibration is saved to the store and reported.      │
+// └──────────────────────────────────────────────────────────────┘
+fn run_calibrate(
+    config: &Config,
+    sensor: Option<&str>,
+    buffers: &[f32],
+    bus: &BusArgs,
+    format: Format,
+) -> anyhow::Result<()> {
+    if !(2..=3).contains(&buffers.len()) {
+        anyhow::bail!("pH calibration needs two or three buffers");
+    }
+    let buffers: Vec<Ph> = buffers.iter().map(|&ph| Ph::new(ph)).collect();
+
+    let mut store = config.calibration.open_store()?;
+    let target = button_target(config, bus)?;
+    let mut buses = Buses::new(Some(target.address));
+    let mut button = open_button(&target, &mut buses)?;
+    let (ph_config, mut ph_sensor) = open_ph_sensor(config, &store, sensor, &mut buses)?
+        .ok_or_else(|| anyhow::anyhow!("no pH sensor configured"))?;
+    let probe_key = ph_config.calibration.as_deref();
+    if probe_key.is_none() {
+        log::warn!(
+            "{} has no calibration key, the result is not saved",
+            ph_config.name
+        );
     }
 
+    let temperature = buffer_temperature(config, &store, &mut buses);
+    let mut run = PhCalibrator::new(*ph_sensor.probe(), temperature).with_buffers(&buffers);
+    let mut app_state = app_state(&mut button)?;
+    let mut led = None;
+    let mut prompted = None;
+    let mut outcome = None;
+    run.start(Instant::now());
+
+    while run.is_active() {
+        let now = Instant::now();
+        match next_press(&mut app_state, &mut button) {
+            Some(ButtonEvent::Long) if run.cancel(now) => {
+                outcome = Some(Err(CalibrationAbort::Cancelled));
+            }
+            Some(ButtonEvent::Short) => run.press(now),
+            _ => {}
+        }
+        if let CalibrationState::AwaitBuffer { index, .. } = run.state() {
+            if prompted != Some(index) {
+                log::info!("Insert the {} buffer and press the button", buffers[index]);
+                prompted = Some(index);
+            }
+        }
+        if let Some(result) = step_calibration(&mut run, &mut ph_sensor, probe_key, &mut store, now)
+        {
+            outcome = Some(result);
+        }
+        let color = run.led(now).unwrap_or(GeneralRGBColor::Black);
+        show_led(&mut button, &mut led, color);
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    show_led(&mut button, &mut led, GeneralRGBColor::Black);
+
+    match outcome {
+        Some(Ok(_)) => {
+            let report = CalibrationReport::new(&ph_config.name, run.probe(), run.points());
+            println!("{}", format.render(&report));
+            Ok(())
+        }
+        Some(Err(abort)) => Err(abort.into()),
+        None => Err(CalibrationAbort::Cancelled.into()),
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                      Set the Button LED                      │
+// └──────────────────────────────────────────────────────────────┘
+fn run_led(
+    config: &Config,
+    color: GeneralRGBColor,
+    bus: &BusArgs,
+    format: Format,
+) -> anyhow::Result<()> {
+    let target = button_target(config, bus)?;
+    let mut button = open_button(&target, &mut Buses::new(Some(target.address)))?;
+    button
+        .set_rgb_color_enum(color)
+        .map_err(|e| anyhow::anyhow!("RGB button: {:?}", e))?;
+    let report = DeviceReport::new(&target, format!("LED set to {:?}", color));
+    println!("{}", format.render(&report));
     Ok(())
 }
 
 // ┌──────────────────────────────────────────────────────────────┐
-// │                     Update Display Logic                     │
+// │                       Test the OLED                          │
 // │                                                              │
-// │ Redraw the display based on the current app state.           │
-// │ This function assumes a redraw is required and does not      │
-// │ perform change detection itself.                             │
+// │ Initialise the SSD1306, light every pixel, then show the     │
+// │ name and address on a blank screen, each for `hold`, and     │
+// │ clear it again.                                              │
 // └──────────────────────────────────────────────────────────────┘
-fn update_display(state: &AppState) -> anyhow::Result<()> {
-    if state.btn_press {
-        println!("Button is pressed - updating display.");
-    } else {
-        println!("Button is not pressed - updating display.");
+fn run_display(
+    config: &Config,
+    hold: Duration,
+    bus: &BusArgs,
+    format: Format,
+) -> anyhow::Result<()> {
+    let target = bus
+        .target(config, DeviceKind::Ssd1306, SSD1306_DEFAULT_I2C_ADDR)
+        .ok_or_else(|| anyhow::anyhow!("no I2C adapter given or configured"))?;
+    let button_addr = config.first_device(DeviceKind::Df0991).map(|d| d.address);
+    let i2c = Buses::new(button_addr).open(&target.adapter)?;
+    let display_error = |e| anyhow::anyhow!("display at 0x{:02X}: {:?}", target.address, e);
+
+    let interface = I2CDisplayInterface::new_custom_address(i2c, target.address);
+    let mut display = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    display.init().map_err(display_error)?;
+
+    display.clear(BinaryColor::On).map_err(display_error)?;
+    display.flush().map_err(display_error)?;
+    std::thread::sleep(hold);
+
+    display.clear(BinaryColor::Off).map_err(display_error)?;
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let lines = [
+        "Hydro-Sense".to_string(),
+        format!("{} 0x{:02X}", target.adapter.name, target.address),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        Text::with_baseline(line, Point::new(0, 12 * i as i32), style, Baseline::Top)
+            .draw(&mut display)
+            .map_err(display_error)?;
     }
-    // Your OLED drawing code here
+    display.flush().map_err(display_error)?;
+    std::thread::sleep(hold);
+
+    display.clear(BinaryColor::Off).map_err(display_error)?;
+    display.flush().map_err(display_error)?;
+
+    let report = DeviceReport::new(&target, "display test done");
+    println!("{}", format.render(&report));
     Ok(())
 }
 
 // ┌──────────────────────────────────────────────────────────────────┐
-// │                           Main Loop                              │
-// │                                                                  │
-// │ Continuously run the event loop:                                 │
+// │                              Main                                │
 // │                                                                  │
-// │ 1. Check button press and update app state accordingly.          │
-// │ 2. If the state has changed, redraw the display.                 │
-// │ 3. Reset the state_changed flag to prevent unnecessary redraws. │
-// │ 4. Sleep briefly to prevent excessive CPU usage.                 │
+// │ Parse the command line, load the configuration and run the       │
+// │ subcommand; without one, monitor. Results go to stdout as text   │
+// │ or JSON, logs to stderr.                                         │
 // └──────────────────────────────────────────────────────────────────┘
 fn main() -> anyhow::Result<()> {
     // Set default RUST_LOG to info if not set by user
@@ -85,69 +848,21 @@ fn main() -> anyhow::Result<()> {
     }
     env_logger::init();
 
-    // ┌──────────────────────────────────────────────────────────────┐
-    // │                    Open I2C Device Adapter                   │
-    // │                                                              │
-    // │ Attempt to find the I2C adapter named "MCP2221" and open it. │
-    // │ If either step fails, the program will panic with an error.  │
-    // └──────────────────────────────────────────────────────────────┘
-    let device_name = "MCP2221";
-    let dev = hydro_sense::i2c::find_adapter(device_name)?;
-    let i2c = linux_embedded_hal::I2cdev::new(dev)?;
-
-    // ┌──────────────────────────────────────────────────────────────┐
-    // │                  Initialize RGB Button Device                │
-    // │                                                              │
-    // │ - Create new instance of DFRobotRGBButton using I2C bus      │
-    // │ - Use default I2C address (0x2A unless changed by switch)    │
-    // │ - Call `begin()` to verify presence and read part ID         │
-    // │ - If detection fails, handle gracefully or exit early        │
-    // └──────────────────────────────────────────────────────────────┘
-    let mut ph_cal_btn = hydro_sense::df0991::DFRobotRGBButton::new(
-        i2c,
-        hydro_sense::df0991::RGBBUTTON_DEFAULT_I2C_ADDR,
-    )?;
-
-    if !ph_cal_btn.begin()? {
-        eprintln!("RGB button not detected.");
-        return Ok(());
-    }
-
-    // ┌────────────────────────────────────────────────────────────┐
-    // │                  Initialize Application State              │
-    // │                                                            │
-    // │ - Tracks dynamic runtime state like button press           │
-    // │ - Includes a flag to indicate if a display redraw is needed│
-    // └────────────────────────────────────────────────────────────┘
-    let initial_press = ph_cal_btn.get_button_status()?;
-    let now = Instant::now();
-
-    let mut app_state = AppState {
-        btn_press: initial_press,
-        state_changed: false,
-        last_change: now,
-    };
-
-    // ┌──────────────────────────────────────────────────────────────┐
-    // │                           Main Loop                          │
-    // │                                                              │
-    // │ Continuously run the event loop:                             │
-    // │                                                              │
-    // │ 1. Check the button press and update the application state.  │
-    // │ 2. If the state changed, perform any actions such as         │
-    // │    updating the display or logging the event.                │
-    // │ 3. Reset the state_changed flag to avoid redundant updates.  │
-    // │ 4. Sleep briefly to reduce CPU usage and debounce input.     │
-    // └──────────────────────────────────────────────────────────────┘
-    loop {
-        check_press(&mut app_state, &mut ph_cal_btn)?;
-
-        if app_state.state_changed {
-            // update_display(&app_state)?;
-            log::info!("Button pressed");
-            app_state.state_changed = false;
-        }
+    let cli = Cli::parse();
+    let config = load_config(cli.config.as_deref())?;
+    let format = cli.format;
 
-        std::thread::sleep(Duration::from_millis(50));
+    match cli.command() {
+        Command::Adapters => run_adapters(&config, format),
+        Command::Scan { bus } => run_scan(&config, &bus, format),
+        Command::Read { sensors, bus } => run_read(&config, &sensors, &bus, format),
+        Command::Monitor { bus } => run_monitor(&config, &bus, format),
+        Command::Calibrate {
+            sensor,
+            buffers,
+            bus,
+        } => run_calibrate(&config, sensor.as_deref(), &buffers, &bus, format),
+        Command::Led { color, bus } => run_led(&config, color, &bus, format),
+        Command::Display { hold, bus } => run_display(&config, hold, &bus, format),
     }
 }
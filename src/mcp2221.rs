@@ -0,0 +1,1092 @@
+use embedded_hal::digital::{self, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use hidapi::{HidApi, HidDevice};
+use std::{
+    cell::RefCell,
+    fmt, io,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// USB vendor and product IDs of the MCP2221 / MCP2221A
+pub const MCP2221_VID: u16 = 0x04D8;
+pub const MCP2221_PID: u16 = 0x00DD;
+
+/// Every HID report exchanged with the MCP2221 is 64 bytes long
+pub const REPORT_LEN: usize = 64;
+
+/// Largest I2C payload carried by a single HID report
+pub const I2C_CHUNK_LEN: usize = 60;
+
+/// HID command codes
+pub const CMD_STATUS_SET_PARAMS: u8 = 0x10;
+pub const CMD_I2C_GET_DATA: u8 = 0x40;
+pub const CMD_I2C_WRITE: u8 = 0x90;
+pub const CMD_I2C_READ: u8 = 0x91;
+pub const CMD_I2C_WRITE_NO_STOP: u8 = 0x92;
+pub const CMD_I2C_READ_REPEATED_START: u8 = 0x93;
+pub const CMD_I2C_WRITE_REPEATED_START: u8 = 0x94;
+pub const CMD_SET_GPIO_VALUES: u8 = 0x50;
+pub const CMD_GET_GPIO_VALUES: u8 = 0x51;
+pub const CMD_SET_SRAM_SETTINGS: u8 = 0x60;
+pub const CMD_RESET_CHIP: u8 = 0x70;
+
+/// Sub-command of CMD_STATUS_SET_PARAMS that cancels the current transfer
+pub const STATUS_CANCEL_TRANSFER: u8 = 0x10;
+
+/// Sub-command of CMD_STATUS_SET_PARAMS that sets a new I2C clock divider
+pub const STATUS_SET_SPEED: u8 = 0x20;
+
+/// Status response value when the new I2C speed was not applied
+pub const STATUS_SPEED_REJECTED: u8 = 0x21;
+
+/// Byte offsets in the status response
+pub const STATUS_I2C_STATE: usize = 8;
+pub const STATUS_I2C_ACK: usize = 20;
+pub const STATUS_SCL_LEVEL: usize = 22;
+pub const STATUS_SDA_LEVEL: usize = 23;
+
+/// Bit set in STATUS_I2C_ACK when the target did not acknowledge
+pub const STATUS_I2C_NACK_MASK: u8 = 0x40;
+
+/// I2C engine states reported at STATUS_I2C_STATE
+pub const I2C_STATE_IDLE: u8 = 0x00;
+pub const I2C_STATE_WRITING_NO_STOP: u8 = 0x45;
+
+/// Byte offset of the first ADC result (GP1, LSB first) in the status response
+pub const STATUS_ADC_DATA: usize = 50;
+
+/// Flag in CMD_SET_SRAM_SETTINGS bytes telling the chip to apply the value
+pub const SRAM_ALTER: u8 = 0x80;
+
+/// Number of GP pins on the MCP2221
+pub const GP_COUNT: usize = 4;
+
+/// Full scale of the 10-bit ADC and the 5-bit DAC
+pub const ADC_MAX: u16 = 1023;
+pub const DAC_MAX: u8 = 31;
+
+/// Status byte values returned by CMD_I2C_GET_DATA
+const GET_DATA_OK: u8 = 0x00;
+const GET_DATA_ERROR_LEN: u8 = 0x7F;
+
+/// Number of status polls before a transfer is considered stuck
+const I2C_POLL_RETRIES: usize = 50;
+const I2C_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Cancel commands, and SCL clock-out reads, tried before the chip is reset
+const RECOVERY_ATTEMPTS: usize = 3;
+
+/// Reserved I2C address read from to clock SCL; no target answers it
+const CLOCK_OUT_ADDR: u8 = 0x7F;
+
+/// How long a reset MCP2221 gets to enumerate again, and the reopen interval
+const RESET_TIMEOUT: Duration = Duration::from_secs(5);
+const RESET_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Recovery events kept for `Mcp2221::recovery_events`
+const RECOVERY_EVENTS_KEPT: usize = 32;
+
+/// HID read timeout in milliseconds
+const HID_TIMEOUT_MS: i32 = 1000;
+
+/// Low level transport for 64-byte MCP2221 HID reports.
+///
+/// Implemented for `hidapi::HidDevice`. Tests can provide a fake transport
+/// that answers commands without any hardware attached.
+pub trait HidTransport {
+    /// Send one command report and return the matching response report
+    fn exchange(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<[u8; REPORT_LEN]>;
+
+    /// Send a command report that gets no response (chip reset)
+    fn send(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<()> {
+        self.exchange(command).map(|_| ())
+    }
+
+    /// Send the chip reset command and return once the device can be used
+    /// again. The default only sends it, for transports that survive a reset.
+    fn reset(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<()> {
+        self.send(command)
+    }
+}
+
+impl HidTransport for HidDevice {
+    fn exchange(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<[u8; REPORT_LEN]> {
+        // hidapi expects the report ID (always 0 for the MCP2221) in front
+        let mut out = [0u8; REPORT_LEN + 1];
+        out[1..].copy_from_slice(command);
+        self.write(&out).map_err(hid_to_io)?;
+
+        let mut response = [0u8; REPORT_LEN];
+        let n = self
+            .read_timeout(&mut response, HID_TIMEOUT_MS)
+            .map_err(hid_to_io)?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "MCP2221 did not answer HID report",
+            ));
+        }
+        Ok(response)
+    }
+
+    fn send(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<()> {
+        let mut out = [0u8; REPORT_LEN + 1];
+        out[1..].copy_from_slice(command);
+        self.write(&out).map_err(hid_to_io)?;
+        Ok(())
+    }
+
+    /// The chip drops off the USB bus and enumerates again, so this handle
+    /// is replaced by a new one to the same serial number
+    fn reset(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<()> {
+        let serial = self.get_serial_number_string().map_err(hid_to_io)?;
+        self.send(command)?;
+
+        let deadline = Instant::now() + RESET_TIMEOUT;
+        loop {
+            thread::sleep(RESET_POLL_INTERVAL);
+            let api = HidApi::new().map_err(hid_to_io)?;
+            let device = match &serial {
+                Some(serial) => api.open_serial(MCP2221_VID, MCP2221_PID, serial),
+                None => api.open(MCP2221_VID, MCP2221_PID),
+            };
+            match device {
+                Ok(device) => {
+                    *self = device;
+                    return Ok(());
+                }
+                Err(e) if Instant::now() >= deadline => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("MCP2221 did not come back after reset: {}", e),
+                    ));
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+fn hid_to_io(e: hidapi::HidError) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Errors raised by the MCP2221 HID backend
+#[derive(Debug)]
+pub enum Mcp2221Error {
+    /// The HID transport failed
+    Transport(io::Error),
+    /// The response did not echo the command that was sent, or (for
+    /// CMD_I2C_GET_DATA) carried another byte count than was asked for
+    UnexpectedResponse { command: u8, response: u8 },
+    /// The MCP2221 refused a command because its I2C engine was busy
+    Busy,
+    /// The target did not acknowledge its address
+    AddressNack,
+    /// The I2C engine reported a failed transfer
+    Bus,
+    /// The I2C transfer did not finish in time
+    Timeout,
+    /// Transfer is longer than the MCP2221 can handle (65535 bytes)
+    TooLong(usize),
+    /// The sequence of operations can not be expressed with MCP2221 commands
+    UnsupportedTransaction,
+    /// GP pin number outside 0-3
+    InvalidPin(u8),
+    /// The GP pin does not support, or is not configured for, that function
+    PinFunction { pin: u8, function: GpFunction },
+    /// DAC value above DAC_MAX
+    DacOutOfRange(u8),
+    /// The MCP2221 did not accept the new I2C speed
+    SpeedRejected,
+    /// SDA or SCL is still held low after recovery
+    BusStuck { sda_low: bool, scl_low: bool },
+}
+
+impl fmt::Display for Mcp2221Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mcp2221Error::Transport(e) => write!(f, "MCP2221 HID transport error: {}", e),
+            Mcp2221Error::UnexpectedResponse { command, response } => write!(
+                f,
+                "MCP2221 answered command 0x{:02X} with 0x{:02X}",
+                command, response
+            ),
+            Mcp2221Error::Busy => write!(f, "MCP2221 I2C engine busy"),
+            Mcp2221Error::AddressNack => write!(f, "I2C address not acknowledged"),
+            Mcp2221Error::Bus => write!(f, "MCP2221 reported an I2C bus error"),
+            Mcp2221Error::Timeout => write!(f, "MCP2221 I2C transfer timed out"),
+            Mcp2221Error::TooLong(len) => write!(f, "I2C transfer of {} bytes is too long", len),
+            Mcp2221Error::UnsupportedTransaction => {
+                write!(f, "I2C transaction not supported by the MCP2221")
+            }
+            Mcp2221Error::InvalidPin(pin) => write!(f, "MCP2221 has no GP{}", pin),
+            Mcp2221Error::PinFunction { pin, function } => {
+                write!(f, "GP{} is not usable as {:?}", pin, function)
+            }
+            Mcp2221Error::DacOutOfRange(value) => {
+                write!(f, "DAC value {} is above {}", value, DAC_MAX)
+            }
+            Mcp2221Error::SpeedRejected => write!(f, "MCP2221 rejected the I2C speed"),
+            Mcp2221Error::BusStuck { sda_low, scl_low } => write!(
+                f,
+                "I2C bus stuck (SDA low: {}, SCL low: {})",
+                sda_low, scl_low
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mcp2221Error {}
+
+impl From<io::Error> for Mcp2221Error {
+    fn from(e: io::Error) -> Self {
+        Mcp2221Error::Transport(e)
+    }
+}
+
+impl embedded_hal::i2c::Error for Mcp2221Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Mcp2221Error::AddressNack => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            Mcp2221Error::Busy | Mcp2221Error::Bus | Mcp2221Error::BusStuck { .. } => {
+                ErrorKind::Bus
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl Mcp2221Error {
+    /// Whether the failure may have left the I2C engine or bus stuck
+    fn may_stick_bus(&self) -> bool {
+        matches!(
+            self,
+            Mcp2221Error::Busy
+                | Mcp2221Error::Bus
+                | Mcp2221Error::Timeout
+                | Mcp2221Error::UnexpectedResponse { .. }
+        )
+    }
+}
+
+impl digital::Error for Mcp2221Error {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+/// I2C clock speed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2cSpeed {
+    /// 100 kHz
+    Standard,
+    /// 400 kHz
+    Fast,
+}
+
+impl I2cSpeed {
+    pub fn hz(self) -> u32 {
+        match self {
+            I2cSpeed::Standard => 100_000,
+            I2cSpeed::Fast => 400_000,
+        }
+    }
+
+    /// Clock divider for the 12 MHz MCP2221 I2C engine
+    pub fn divider(self) -> u8 {
+        (12_000_000 / self.hz() - 3) as u8
+    }
+}
+
+/// One attempt at freeing a stuck I2C bus
+#[derive(Clone, Debug)]
+pub struct RecoveryEvent {
+    pub at: std::time::SystemTime,
+    /// Error of the transaction that triggered the recovery
+    pub cause: String,
+    /// Line levels seen before recovery
+    pub sda_low: bool,
+    pub scl_low: bool,
+    /// Cancel commands and SCL clock-out reads sent
+    pub attempts: usize,
+    /// Whether the chip had to be reset
+    pub reset: bool,
+    pub recovered: bool,
+}
+
+impl fmt::Display for RecoveryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "I2C bus recovery after '{}' (SDA low: {}, SCL low: {}): {} after {} attempt(s){}",
+            self.cause,
+            self.sda_low,
+            self.scl_low,
+            if self.recovered {
+                "recovered"
+            } else {
+                "failed"
+            },
+            self.attempts,
+            if self.reset { " and a chip reset" } else { "" }
+        )
+    }
+}
+
+/// Function assigned to a GP pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpFunction {
+    /// Digital input, e.g. a float switch
+    Input,
+    /// Digital output with its initial level, e.g. a relay
+    Output(bool),
+    /// Analog input (GP1, GP2 and GP3 only)
+    Adc,
+    /// Analog output (GP2 and GP3 only, both share one DAC)
+    Dac,
+}
+
+impl GpFunction {
+    /// GP settings byte for CMD_SET_SRAM_SETTINGS, or None if `pin` lacks it
+    fn sram_byte(self, pin: u8) -> Option<u8> {
+        const DIRECTION_INPUT: u8 = 0b0000_1000;
+        const DESIGNATION_ALT0: u8 = 0b010; // ADC on GP1-GP3
+        const DESIGNATION_ALT1: u8 = 0b011; // DAC on GP2-GP3
+
+        match (self, pin) {
+            (GpFunction::Input, 0..=3) => Some(DIRECTION_INPUT),
+            (GpFunction::Output(high), 0..=3) => Some((high as u8) << 4),
+            (GpFunction::Adc, 1..=3) => Some(DESIGNATION_ALT0),
+            (GpFunction::Dac, 2..=3) => Some(DESIGNATION_ALT1),
+            _ => None,
+        }
+    }
+}
+
+/// Voltage reference for the ADC and DAC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoltageRef {
+    /// Supply voltage, see `Mcp2221::set_supply_voltage`
+    Vdd,
+    Vrm1_024V,
+    Vrm2_048V,
+    Vrm4_096V,
+}
+
+impl VoltageRef {
+    /// Reference byte for CMD_SET_SRAM_SETTINGS (bits 2-1 level, bit 0 source)
+    fn sram_byte(self) -> u8 {
+        match self {
+            VoltageRef::Vdd => 0b000,
+            VoltageRef::Vrm1_024V => 0b011,
+            VoltageRef::Vrm2_048V => 0b101,
+            VoltageRef::Vrm4_096V => 0b111,
+        }
+    }
+
+    fn volts(self, vdd: f32) -> f32 {
+        match self {
+            VoltageRef::Vdd => vdd,
+            VoltageRef::Vrm1_024V => 1.024,
+            VoltageRef::Vrm2_048V => 2.048,
+            VoltageRef::Vrm4_096V => 4.096,
+        }
+    }
+}
+
+/// MCP2221 USB-I2C bridge driven directly over HID reports.
+///
+/// This does not need the `hid-mcp2221` kernel driver and implements the
+/// embedded-hal `I2c` trait, so it can be handed to any driver in this crate
+/// in place of `linux_embedded_hal::I2cdev`. The four GP pins, the ADC and
+/// the DAC are reached through the methods below, or through `into_shared`
+/// when the pins and the I2C bus are needed at the same time.
+pub struct Mcp2221<T> {
+    transport: T,
+    gp: Option<[GpFunction; GP_COUNT]>,
+    adc_ref: VoltageRef,
+    dac_ref: VoltageRef,
+    vdd: f32,
+    speed: I2cSpeed,
+    recoveries: Vec<RecoveryEvent>,
+}
+
+impl Mcp2221<HidDevice> {
+    /// Open the first MCP2221 found on the USB bus
+    pub fn open() -> io::Result<Self> {
+        let api = HidApi::new().map_err(hid_to_io)?;
+        let device = api.open(MCP2221_VID, MCP2221_PID).map_err(hid_to_io)?;
+        Ok(Self::new(device))
+    }
+
+    /// Open the MCP2221 with the given USB serial number
+    pub fn open_serial(serial: &str) -> io::Result<Self> {
+        let api = HidApi::new().map_err(hid_to_io)?;
+        let device = api
+            .open_serial(MCP2221_VID, MCP2221_PID, serial)
+            .map_err(hid_to_io)?;
+        Ok(Self::new(device))
+    }
+}
+
+impl<T: HidTransport> Mcp2221<T> {
+    /// Wrap an already opened HID transport
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            gp: None,
+            adc_ref: VoltageRef::Vdd,
+            dac_ref: VoltageRef::Vdd,
+            vdd: 5.0,
+            speed: I2cSpeed::Standard,
+            recoveries: Vec::new(),
+        }
+    }
+
+    /// Release underlying HID transport
+    pub fn release(self) -> T {
+        self.transport
+    }
+
+    /// Send a command report (zero padded) and check the echoed command code
+    fn command(&mut self, bytes: &[u8]) -> Result<[u8; REPORT_LEN], Mcp2221Error> {
+        let mut report = [0u8; REPORT_LEN];
+        report[..bytes.len()].copy_from_slice(bytes);
+
+        let response = self.transport.exchange(&report)?;
+        if response[0] != report[0] {
+            return Err(Mcp2221Error::UnexpectedResponse {
+                command: report[0],
+                response: response[0],
+            });
+        }
+        Ok(response)
+    }
+
+    /// Read the status report (I2C engine state, ACK flag, line levels, ...)
+    pub fn status(&mut self) -> Result<[u8; REPORT_LEN], Mcp2221Error> {
+        self.command(&[CMD_STATUS_SET_PARAMS, 0x00, 0x00])
+    }
+
+    /// Cancel the current I2C transfer and release the bus
+    pub fn cancel(&mut self) -> Result<(), Mcp2221Error> {
+        self.command(&[CMD_STATUS_SET_PARAMS, 0x00, STATUS_CANCEL_TRANSFER])?;
+        Ok(())
+    }
+
+    /// Set the I2C clock; it is applied again after every bus recovery
+    pub fn set_speed(&mut self, speed: I2cSpeed) -> Result<(), Mcp2221Error> {
+        // The engine ignores speed changes while a transfer is pending
+        self.cancel()?;
+
+        let response = self.command(&[
+            CMD_STATUS_SET_PARAMS,
+            0x00,
+            0x00,
+            STATUS_SET_SPEED,
+            speed.divider(),
+        ])?;
+        if response[3] == STATUS_SPEED_REJECTED {
+            return Err(Mcp2221Error::SpeedRejected);
+        }
+
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// I2C clock last set with `set_speed`
+    pub fn speed(&self) -> I2cSpeed {
+        self.speed
+    }
+
+    /// Levels of SDA and SCL as `(sda_low, scl_low)`
+    pub fn lines_low(&mut self) -> Result<(bool, bool), Mcp2221Error> {
+        let status = self.status()?;
+        Ok((status[STATUS_SDA_LEVEL] == 0, status[STATUS_SCL_LEVEL] == 0))
+    }
+
+    /// Whether the bus is held low or the I2C engine is stuck mid-transfer
+    pub fn is_bus_stuck(&mut self) -> Result<bool, Mcp2221Error> {
+        let status = self.status()?;
+        Ok(status[STATUS_SDA_LEVEL] == 0
+            || status[STATUS_SCL_LEVEL] == 0
+            || status[STATUS_I2C_STATE] != I2C_STATE_IDLE)
+    }
+
+    /// Free a stuck bus, escalating until it is idle with both lines high:
+    ///
+    /// 1. cancel the pending transfer,
+    /// 2. clock SCL while a target holds SDA low in the middle of a byte,
+    /// 3. reset the chip, which also frees an I2C engine that ignores
+    ///    cancels.
+    ///
+    /// The configured speed (and after a reset the voltage references and
+    /// GP functions) are then applied again. Every call is logged and kept
+    /// as a `RecoveryEvent`.
+    pub fn recover_bus(&mut self, cause: &str) -> Result<(), Mcp2221Error> {
+        let (sda_low, scl_low) = self.lines_low()?;
+        let mut attempts = 0;
+        let mut recovered = false;
+        let mut reset = false;
+
+        while attempts < RECOVERY_ATTEMPTS {
+            attempts += 1;
+            self.cancel()?;
+            thread::sleep(I2C_POLL_INTERVAL);
+
+            if !self.is_bus_stuck()? {
+                recovered = true;
+                break;
+            }
+        }
+
+        if !recovered {
+            let clocks = self.clock_out_sda()?;
+            attempts += clocks;
+            recovered = clocks > 0 && !self.is_bus_stuck()?;
+        }
+
+        if !recovered {
+            reset = true;
+            self.reset_chip()?;
+            recovered = !self.is_bus_stuck()?;
+        } else {
+            self.set_speed(self.speed)?;
+        }
+
+        let event = RecoveryEvent {
+            at: std::time::SystemTime::now(),
+            cause: cause.to_string(),
+            sda_low,
+            scl_low,
+            attempts,
+            reset,
+            recovered,
+        };
+        if recovered {
+            log::warn!("{}", event);
+        } else {
+            log::error!("{}", event);
+        }
+
+        if self.recoveries.len() == RECOVERY_EVENTS_KEPT {
+            self.recoveries.remove(0);
+        }
+        self.recoveries.push(event);
+
+        if recovered {
+            Ok(())
+        } else {
+            let (sda_low, scl_low) = self.lines_low()?;
+            Err(Mcp2221Error::BusStuck { sda_low, scl_low })
+        }
+    }
+
+    /// Clock SCL while SDA is held low and SCL is free, so a target stopped
+    /// in the middle of a byte can finish it and release SDA. The MCP2221
+    /// can not toggle SCL directly; a one-byte read of a reserved address
+    /// makes the engine clock out the nine address and ACK bits instead.
+    /// Returns the number of reads sent.
+    fn clock_out_sda(&mut self) -> Result<usize, Mcp2221Error> {
+        let mut reads = 0;
+
+        while reads < RECOVERY_ATTEMPTS {
+            let (sda_low, scl_low) = self.lines_low()?;
+            if !sda_low || scl_low {
+                break;
+            }
+
+            reads += 1;
+            self.command(&[CMD_I2C_READ, 1, 0, (CLOCK_OUT_ADDR << 1) | 1])?;
+            thread::sleep(I2C_POLL_INTERVAL);
+            self.cancel()?;
+        }
+
+        Ok(reads)
+    }
+
+    /// Most recent bus recoveries, oldest first
+    pub fn recovery_events(&self) -> &[RecoveryEvent] {
+        &self.recoveries
+    }
+
+    /// Reset the MCP2221. It drops off the USB bus and enumerates again,
+    /// which the transport waits for. The chip comes back with its power-up
+    /// settings, so the speed, voltage references and GP functions set
+    /// through this driver are written again.
+    pub fn reset_chip(&mut self) -> Result<(), Mcp2221Error> {
+        let mut report = [0u8; REPORT_LEN];
+        report[..4].copy_from_slice(&[CMD_RESET_CHIP, 0xAB, 0xCD, 0xEF]);
+        self.transport.reset(&report)?;
+
+        self.set_speed(self.speed)?;
+        self.set_adc_reference(self.adc_ref)?;
+        self.set_dac_reference(self.dac_ref)
+    }
+
+    /// Assign a function to each GP pin (GP0 to GP3)
+    pub fn configure_gp(&mut self, functions: [GpFunction; GP_COUNT]) -> Result<(), Mcp2221Error> {
+        let mut report = [0u8; 12];
+        report[0] = CMD_SET_SRAM_SETTINGS;
+        report[7] = SRAM_ALTER;
+
+        for (pin, function) in functions.iter().enumerate() {
+            report[8 + pin] = function
+                .sram_byte(pin as u8)
+                .ok_or(Mcp2221Error::PinFunction {
+                    pin: pin as u8,
+                    function: *function,
+                })?;
+        }
+
+        self.command(&report)?;
+        self.gp = Some(functions);
+        Ok(())
+    }
+
+    /// Function last assigned to `pin` with `configure_gp`
+    pub fn gp_function(&self, pin: u8) -> Option<GpFunction> {
+        self.gp.and_then(|gp| gp.get(pin as usize).copied())
+    }
+
+    /// Check that `pin` exists and was configured for `function`
+    fn check_pin(&self, pin: u8, function: GpFunction) -> Result<(), Mcp2221Error> {
+        if pin as usize >= GP_COUNT {
+            return Err(Mcp2221Error::InvalidPin(pin));
+        }
+
+        let configured = self.gp_function(pin);
+        let matches = match (configured, function) {
+            (Some(GpFunction::Output(_)), GpFunction::Output(_)) => true,
+            (Some(configured), function) => configured == function,
+            (None, _) => false,
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(Mcp2221Error::PinFunction { pin, function })
+        }
+    }
+
+    /// Drive a GP pin configured as output
+    pub fn set_gpio(&mut self, pin: u8, high: bool) -> Result<(), Mcp2221Error> {
+        self.check_pin(pin, GpFunction::Output(high))?;
+
+        // Four bytes per pin: alter output, output value, alter direction, direction
+        let mut report = [0u8; 18];
+        report[0] = CMD_SET_GPIO_VALUES;
+        report[2 + 4 * pin as usize] = 0x01;
+        report[3 + 4 * pin as usize] = high as u8;
+
+        self.command(&report)?;
+        if let Some(gp) = self.gp.as_mut() {
+            gp[pin as usize] = GpFunction::Output(high);
+        }
+        Ok(())
+    }
+
+    /// Read the level of a GP pin configured as input or output
+    pub fn get_gpio(&mut self, pin: u8) -> Result<bool, Mcp2221Error> {
+        if self.check_pin(pin, GpFunction::Input).is_err() {
+            self.check_pin(pin, GpFunction::Output(false))?;
+        }
+
+        // Two bytes per pin: value, direction
+        let response = self.command(&[CMD_GET_GPIO_VALUES])?;
+        Ok(response[2 + 2 * pin as usize] == 0x01)
+    }
+
+    /// Supply voltage used when the ADC or DAC reference is `VoltageRef::Vdd`
+    pub fn set_supply_voltage(&mut self, vdd: f32) {
+        self.vdd = vdd;
+    }
+
+    /// Select the ADC voltage reference
+    pub fn set_adc_reference(&mut self, vref: VoltageRef) -> Result<(), Mcp2221Error> {
+        self.command(&[
+            CMD_SET_SRAM_SETTINGS,
+            0,
+            0,
+            0,
+            0,
+            SRAM_ALTER | vref.sram_byte(),
+        ])?;
+        self.adc_ref = vref;
+        self.restore_gp()
+    }
+
+    /// Select the DAC voltage reference
+    pub fn set_dac_reference(&mut self, vref: VoltageRef) -> Result<(), Mcp2221Error> {
+        self.command(&[CMD_SET_SRAM_SETTINGS, 0, 0, SRAM_ALTER | vref.sram_byte()])?;
+        self.dac_ref = vref;
+        self.restore_gp()
+    }
+
+    /// The chip drops its GP designations when a reference changes, so
+    /// write the last configuration back
+    fn restore_gp(&mut self) -> Result<(), Mcp2221Error> {
+        match self.gp {
+            Some(gp) => self.configure_gp(gp),
+            None => Ok(()),
+        }
+    }
+
+    /// Read the raw 10-bit ADC value of a GP pin configured as ADC
+    pub fn read_adc(&mut self, pin: u8) -> Result<u16, Mcp2221Error> {
+        self.check_pin(pin, GpFunction::Adc)?;
+
+        let status = self.status()?;
+        let offset = STATUS_ADC_DATA + 2 * (pin as usize - 1);
+        Ok(u16::from_le_bytes([status[offset], status[offset + 1]]))
+    }
+
+    /// Read a GP pin configured as ADC and convert it to volts
+    pub fn read_adc_voltage(&mut self, pin: u8) -> Result<f32, Mcp2221Error> {
+        let raw = self.read_adc(pin)?;
+        Ok(raw as f32 * self.adc_ref.volts(self.vdd) / (ADC_MAX as f32 + 1.0))
+    }
+
+    /// Set the 5-bit DAC output (0 to DAC_MAX) shared by GP2 and GP3
+    pub fn set_dac(&mut self, value: u8) -> Result<(), Mcp2221Error> {
+        if value > DAC_MAX {
+            return Err(Mcp2221Error::DacOutOfRange(value));
+        }
+        self.command(&[CMD_SET_SRAM_SETTINGS, 0, 0, 0, SRAM_ALTER | value])?;
+        Ok(())
+    }
+
+    /// Set the DAC output as close as possible to `volts`
+    pub fn set_dac_voltage(&mut self, volts: f32) -> Result<(), Mcp2221Error> {
+        let vref = self.dac_ref.volts(self.vdd);
+        let value = (volts / vref * (DAC_MAX as f32 + 1.0)).round();
+        self.set_dac(value.clamp(0.0, DAC_MAX as f32) as u8)
+    }
+
+    /// Share the bridge so GP pins, ADC and DAC can be used next to the I2C bus
+    pub fn into_shared(self) -> SharedMcp2221<T> {
+        SharedMcp2221(Rc::new(RefCell::new(self)))
+    }
+
+    /// Poll the status report until the I2C engine finished the transfer.
+    /// Busy and timeout errors leave the transfer pending for `recover_bus`.
+    fn wait_i2c_done(&mut self, cmd: u8) -> Result<(), Mcp2221Error> {
+        for _ in 0..I2C_POLL_RETRIES {
+            let status = self.status()?;
+
+            if status[STATUS_I2C_ACK] & STATUS_I2C_NACK_MASK != 0 {
+                self.cancel()?;
+                return Err(Mcp2221Error::AddressNack);
+            }
+
+            match status[STATUS_I2C_STATE] {
+                I2C_STATE_IDLE => return Ok(()),
+                I2C_STATE_WRITING_NO_STOP if cmd == CMD_I2C_WRITE_NO_STOP => return Ok(()),
+                _ => thread::sleep(I2C_POLL_INTERVAL),
+            }
+        }
+
+        Err(Mcp2221Error::Timeout)
+    }
+
+    /// Write `data` to `addr` with one of the CMD_I2C_WRITE* commands
+    fn i2c_write(&mut self, cmd: u8, addr: u8, data: &[u8]) -> Result<(), Mcp2221Error> {
+        let len = u16::try_from(data.len()).map_err(|_| Mcp2221Error::TooLong(data.len()))?;
+
+        // Zero length writes (address probes) still need one report
+        let mut chunks: Vec<&[u8]> = data.chunks(I2C_CHUNK_LEN).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+
+        for chunk in chunks {
+            let mut report = vec![cmd, (len & 0xFF) as u8, (len >> 8) as u8, addr << 1];
+            report.extend_from_slice(chunk);
+
+            let response = self.command(&report)?;
+            if response[1] != 0x00 {
+                return Err(Mcp2221Error::Busy);
+            }
+        }
+
+        self.wait_i2c_done(cmd)
+    }
+
+    /// Read `buf.len()` bytes from `addr` with one of the CMD_I2C_READ* commands
+    fn i2c_read(&mut self, cmd: u8, addr: u8, buf: &mut [u8]) -> Result<(), Mcp2221Error> {
+        let len = u16::try_from(buf.len()).map_err(|_| Mcp2221Error::TooLong(buf.len()))?;
+
+        let response =
+            self.command(&[cmd, (len & 0xFF) as u8, (len >> 8) as u8, (addr << 1) | 1])?;
+        if response[1] != 0x00 {
+            return Err(Mcp2221Error::Busy);
+        }
+
+        for chunk in buf.chunks_mut(I2C_CHUNK_LEN) {
+            let mut received = false;
+
+            for _ in 0..I2C_POLL_RETRIES {
+                let response = self.command(&[CMD_I2C_GET_DATA])?;
+
+                if response[1] == GET_DATA_OK && response[3] != GET_DATA_ERROR_LEN {
+                    // A short chunk would leave stale bytes at the end of `buf`
+                    if response[3] as usize != chunk.len() {
+                        return Err(Mcp2221Error::UnexpectedResponse {
+                            command: CMD_I2C_GET_DATA,
+                            response: response[3],
+                        });
+                    }
+                    chunk.copy_from_slice(&response[4..4 + chunk.len()]);
+                    received = true;
+                    break;
+                }
+
+                // Data not ready yet, or the target did not answer
+                let status = self.status()?;
+                if status[STATUS_I2C_ACK] & STATUS_I2C_NACK_MASK != 0 {
+                    self.cancel()?;
+                    return Err(Mcp2221Error::AddressNack);
+                }
+                thread::sleep(I2C_POLL_INTERVAL);
+            }
+
+            if !received {
+                return Err(Mcp2221Error::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> ErrorType for Mcp2221<T> {
+    type Error = Mcp2221Error;
+}
+
+impl<T: HidTransport> I2c for Mcp2221<T> {
+    /// Runs the transaction and, when it fails in a way that can leave the
+    /// bus stuck, recovers the bus before returning the error so the next
+    /// transaction starts clean.
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.run_transaction(address, operations);
+
+        if let Err(e) = &result {
+            if e.may_stick_bus() {
+                let cause = format!("0x{:02X}: {}", address, e);
+                self.recover_bus(&cause)?;
+            }
+        }
+
+        result
+    }
+}
+
+impl<T: HidTransport> Mcp2221<T> {
+    /// Maps embedded-hal transactions onto MCP2221 commands.
+    ///
+    /// Adjacent writes are merged into one transfer. A write followed by a
+    /// read uses a repeated start. The MCP2221 can not continue after a read
+    /// without a STOP, so reads must be the last operation.
+    fn run_transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Mcp2221Error> {
+        let mut pending_write: Vec<u8> = Vec::new();
+        let mut started = false;
+        let count = operations.len();
+
+        for i in 0..count {
+            let is_last = i + 1 == count;
+            let next_is_write = matches!(operations.get(i + 1), Some(Operation::Write(_)));
+
+            match &mut operations[i] {
+                Operation::Write(bytes) => {
+                    pending_write.extend_from_slice(bytes);
+                    if next_is_write {
+                        continue;
+                    }
+
+                    let cmd = match (started, is_last) {
+                        (false, true) => CMD_I2C_WRITE,
+                        (false, false) => CMD_I2C_WRITE_NO_STOP,
+                        (true, true) => CMD_I2C_WRITE_REPEATED_START,
+                        (true, false) => return Err(Mcp2221Error::UnsupportedTransaction),
+                    };
+                    self.i2c_write(cmd, address, &pending_write)?;
+                    pending_write.clear();
+                    started = true;
+                }
+                Operation::Read(buf) => {
+                    if !is_last {
+                        return Err(Mcp2221Error::UnsupportedTransaction);
+                    }
+
+                    let cmd = if started {
+                        CMD_I2C_READ_REPEATED_START
+                    } else {
+                        CMD_I2C_READ
+                    };
+                    self.i2c_read(cmd, address, buf)?;
+                    started = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Clonable handle to one MCP2221.
+///
+/// Every clone implements `I2c`, so it can be passed to the sensor drivers,
+/// while the same handle hands out GP pins, ADC channels and the DAC.
+pub struct SharedMcp2221<T>(Rc<RefCell<Mcp2221<T>>>);
+
+impl<T> Clone for SharedMcp2221<T> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<T: HidTransport> SharedMcp2221<T> {
+    /// Borrow the bridge for direct access
+    pub fn with<R>(&self, f: impl FnOnce(&mut Mcp2221<T>) -> R) -> R {
+        f(&mut self.0.borrow_mut())
+    }
+
+    /// GP pin configured as digital input or output
+    pub fn gpio(&self, pin: u8) -> Result<GpioPin<T>, Mcp2221Error> {
+        let dev = self.0.borrow();
+        if dev.check_pin(pin, GpFunction::Input).is_err() {
+            dev.check_pin(pin, GpFunction::Output(false))?;
+        }
+        Ok(GpioPin {
+            dev: self.clone(),
+            pin,
+        })
+    }
+
+    /// GP pin configured as ADC input
+    pub fn adc_channel(&self, pin: u8) -> Result<AdcChannel<T>, Mcp2221Error> {
+        self.0.borrow().check_pin(pin, GpFunction::Adc)?;
+        Ok(AdcChannel {
+            dev: self.clone(),
+            pin,
+        })
+    }
+
+    /// DAC output, available once GP2 or GP3 is configured as DAC
+    pub fn dac(&self) -> Result<DacOutput<T>, Mcp2221Error> {
+        let dev = self.0.borrow();
+        if dev.check_pin(2, GpFunction::Dac).is_err() {
+            dev.check_pin(3, GpFunction::Dac)?;
+        }
+        Ok(DacOutput { dev: self.clone() })
+    }
+}
+
+impl<T> ErrorType for SharedMcp2221<T> {
+    type Error = Mcp2221Error;
+}
+
+impl<T: HidTransport> I2c for SharedMcp2221<T> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().transaction(address, operations)
+    }
+}
+
+/// GP pin used as embedded-hal digital input or output
+pub struct GpioPin<T> {
+    dev: SharedMcp2221<T>,
+    pin: u8,
+}
+
+impl<T> digital::ErrorType for GpioPin<T> {
+    type Error = Mcp2221Error;
+}
+
+impl<T: HidTransport> InputPin for GpioPin<T> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.dev.with(|dev| dev.get_gpio(self.pin))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+impl<T: HidTransport> OutputPin for GpioPin<T> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.dev.with(|dev| dev.set_gpio(self.pin, false))
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.dev.with(|dev| dev.set_gpio(self.pin, true))
+    }
+}
+
+impl<T: HidTransport> StatefulOutputPin for GpioPin<T> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        match self.dev.with(|dev| dev.gp_function(self.pin)) {
+            Some(GpFunction::Output(high)) => Ok(high),
+            _ => Err(Mcp2221Error::PinFunction {
+                pin: self.pin,
+                function: GpFunction::Output(false),
+            }),
+        }
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_set_high()?)
+    }
+}
+
+/// GP pin used as analog input.
+///
+/// embedded-hal 1.0 has no ADC trait, so the channel offers raw counts and
+/// volts directly.
+pub struct AdcChannel<T> {
+    dev: SharedMcp2221<T>,
+    pin: u8,
+}
+
+impl<T: HidTransport> AdcChannel<T> {
+    /// Raw 10-bit conversion result
+    pub fn read_raw(&mut self) -> Result<u16, Mcp2221Error> {
+        self.dev.with(|dev| dev.read_adc(self.pin))
+    }
+
+    /// Conversion result in volts using the configured reference
+    pub fn read_voltage(&mut self) -> Result<f32, Mcp2221Error> {
+        self.dev.with(|dev| dev.read_adc_voltage(self.pin))
+    }
+}
+
+/// DAC output on GP2 / GP3
+pub struct DacOutput<T> {
+    dev: SharedMcp2221<T>,
+}
+
+impl<T: HidTransport> DacOutput<T> {
+    /// Set the raw 5-bit DAC value
+    pub fn set_raw(&mut self, value: u8) -> Result<(), Mcp2221Error> {
+        self.dev.with(|dev| dev.set_dac(value))
+    }
+
+    /// Set the output voltage using the configured reference
+    pub fn set_voltage(&mut self, volts: f32) -> Result<(), Mcp2221Error> {
+        self.dev.with(|dev| dev.set_dac_voltage(volts))
+    }
+}
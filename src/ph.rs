@@ -0,0 +1,398 @@
+use crate::ads1115::{is_clipped, AdsSensor, Mux, Pga};
+use crate::i2c::lock::LockedI2c;
+use crate::sensor::{Measurement, Quality, Quantity, Sensor};
+use crate::units::{Ph, Temperature, Unit, Voltage};
+use embedded_hal::i2c::{ErrorType, I2c};
+use std::fmt;
+
+/// Ideal glass electrode slope at 25 °C (volts per pH)
+pub const NERNST_SLOPE_25C: f32 = -0.05916;
+
+/// Temperature the Nernst slope is normalised to (°C)
+pub const REFERENCE_TEMPERATURE: f32 = 25.0;
+
+/// Buffer solutions used for calibration
+pub const BUFFER_PH4: Ph = Ph::new(4.01);
+pub const BUFFER_PH7: Ph = Ph::new(7.00);
+pub const BUFFER_PH10: Ph = Ph::new(10.01);
+
+/// One buffer reading: the buffer pH and the voltage measured in it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CalibrationPoint {
+    pub ph: Ph,
+    pub voltage: Voltage,
+}
+
+/// Calibration errors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhError {
+    /// Two buffers have (nearly) the same pH
+    SameBuffer,
+    /// The readings do not change with pH, or change the wrong way
+    InvalidSlope { slope: f32 },
+}
+
+impl fmt::Display for PhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhError::SameBuffer => write!(f, "calibration buffers must differ in pH"),
+            PhError::InvalidSlope { slope } => {
+                write!(f, "invalid probe slope {:.1} mV/pH", slope * 1000.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PhError {}
+
+/// Slope and offset calibration from buffer solutions.
+///
+/// The line is anchored at the middle buffer with separate slopes on the
+/// acid and alkaline side (equal for a two-point calibration). Slopes are
+/// stored as measured at `temperature` and scaled by absolute temperature
+/// (Nernst) when converting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhCalibration {
+    pub anchor: CalibrationPoint,
+    /// Volts per pH below the anchor
+    pub acid_slope: f32,
+    /// Volts per pH above the anchor
+    pub base_slope: f32,
+    /// Buffer temperature during calibration
+    pub temperature: Temperature,
+}
+
+impl PhCalibration {
+    /// Calibration from two buffers, e.g. pH 7 and pH 4
+    pub fn two_point(
+        a: CalibrationPoint,
+        b: CalibrationPoint,
+        temperature: Temperature,
+    ) -> Result<Self, PhError> {
+        let slope = segment_slope(a, b)?;
+        Ok(Self {
+            anchor: a,
+            acid_slope: slope,
+            base_slope: slope,
+            temperature,
+        })
+    }
+
+    /// Calibration from three buffers, e.g. pH 4, 7 and 10, in any order
+    pub fn three_point(
+        mut points: [CalibrationPoint; 3],
+        temperature: Temperature,
+    ) -> Result<Self, PhError> {
+        points.sort_by(|a, b| a.ph.value().total_cmp(&b.ph.value()));
+        let [acid, anchor, base] = points;
+        let acid_slope = segment_slope(acid, anchor)?;
+        let base_slope = segment_slope(anchor, base)?;
+
+        // Both halves have to fall (or rise) with pH
+        if acid_slope.signum() != base_slope.signum() {
+            return Err(PhError::InvalidSlope { slope: base_slope });
+        }
+
+        Ok(Self {
+            anchor,
+            acid_slope,
+            base_slope,
+            temperature,
+        })
+    }
+
+    /// Slopes scaled from the calibration temperature to `temperature`
+    fn slopes_at(&self, temperature: Temperature) -> (f32, f32) {
+        let factor = temperature.kelvin() / self.temperature.kelvin();
+        (self.acid_slope * factor, self.base_slope * factor)
+    }
+
+    /// pH from the probe voltage with the sample at `temperature`
+    pub fn voltage_to_ph(&self, voltage: Voltage, temperature: Temperature) -> Ph {
+        let (acid_slope, base_slope) = self.slopes_at(temperature);
+        let delta = voltage.volts() - self.anchor.voltage.volts();
+        let anchor = self.anchor.ph.value();
+
+        let ph = anchor + delta / acid_slope;
+        if ph <= anchor {
+            Ph::new(ph)
+        } else {
+            Ph::new(anchor + delta / base_slope)
+        }
+    }
+
+    /// Probe voltage at `ph` with the sample at `temperature`, the inverse
+    /// of `voltage_to_ph`
+    pub fn ph_to_voltage(&self, ph: Ph, temperature: Temperature) -> Voltage {
+        let (acid_slope, base_slope) = self.slopes_at(temperature);
+        let anchor = self.anchor.ph.value();
+        let slope = if ph.value() <= anchor {
+            acid_slope
+        } else {
+            base_slope
+        };
+        Voltage::from_volts(self.anchor.voltage.volts() + (ph.value() - anchor) * slope)
+    }
+
+    /// Acid and base slopes normalised to 25 °C (volts per pH)
+    pub fn slopes_25c(&self) -> (f32, f32) {
+        self.slopes_at(Temperature::from_celsius(REFERENCE_TEMPERATURE))
+    }
+
+    /// Mean slope normalised to 25 °C (volts per pH)
+    pub fn slope_25c(&self) -> f32 {
+        let (acid, base) = self.slopes_25c();
+        (acid + base) / 2.0
+    }
+
+    /// Voltage the calibration expects at pH 7 and `temperature`
+    pub fn neutral_voltage(&self, temperature: Temperature) -> Voltage {
+        let (acid_slope, base_slope) = self.slopes_at(temperature);
+        let anchor = self.anchor.ph.value();
+        let slope = if anchor >= 7.0 {
+            acid_slope
+        } else {
+            base_slope
+        };
+        Voltage::from_volts(self.anchor.voltage.volts() + (7.0 - anchor) * slope)
+    }
+}
+
+fn segment_slope(a: CalibrationPoint, b: CalibrationPoint) -> Result<f32, PhError> {
+    let delta_ph = b.ph.value() - a.ph.value();
+    if delta_ph.abs() < 0.5 {
+        return Err(PhError::SameBuffer);
+    }
+
+    let slope = (b.voltage.volts() - a.voltage.volts()) / delta_ph;
+    if !slope.is_finite() || slope.abs() < 1e-4 {
+        return Err(PhError::InvalidSlope { slope });
+    }
+    Ok(slope)
+}
+
+/// Overall probe condition, from best to worst
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProbeHealth {
+    Good,
+    /// Usable, but clean the probe and recalibrate soon
+    Fair,
+    /// Slope or offset out of tolerance
+    Replace,
+}
+
+impl fmt::Display for ProbeHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeHealth::Good => write!(f, "good"),
+            ProbeHealth::Fair => write!(f, "fair (clean and recalibrate)"),
+            ProbeHealth::Replace => write!(f, "replace probe"),
+        }
+    }
+}
+
+/// PH4502C pH module: glass electrode plus amplifier with offset pot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ph4502c {
+    /// Amplifier gain from electrode to output (output falls as pH rises)
+    pub gain: f32,
+    /// Output at pH 7 with an ideal electrode (volts, set by the offset pot)
+    pub zero_voltage: f32,
+    pub calibration: PhCalibration,
+}
+
+impl Default for Ph4502c {
+    /// Uncalibrated module: 2.5 V at pH 7, gain 3 (about -177 mV/pH)
+    fn default() -> Self {
+        Self::new(3.0, 2.5)
+    }
+}
+
+impl Ph4502c {
+    /// Module with the given gain and zero voltage, calibrated to an ideal electrode
+    pub fn new(gain: f32, zero_voltage: f32) -> Self {
+        let slope = NERNST_SLOPE_25C * gain;
+        Self {
+            gain,
+            zero_voltage,
+            calibration: PhCalibration {
+                anchor: CalibrationPoint {
+                    ph: Ph::new(7.0),
+                    voltage: Voltage::from_volts(zero_voltage),
+                },
+                acid_slope: slope,
+                base_slope: slope,
+                temperature: Temperature::from_celsius(REFERENCE_TEMPERATURE),
+            },
+        }
+    }
+
+    pub fn with_calibration(mut self, calibration: PhCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Ideal module slope at 25 °C (volts per pH)
+    pub fn ideal_slope(&self) -> f32 {
+        NERNST_SLOPE_25C * self.gain
+    }
+
+    /// pH from the module voltage with the sample at `temperature`
+    pub fn voltage_to_ph(&self, voltage: Voltage, temperature: Temperature) -> Ph {
+        self.calibration.voltage_to_ph(voltage, temperature)
+    }
+
+    /// Module voltage at `ph` with the sample at `temperature`
+    pub fn ph_to_voltage(&self, ph: Ph, temperature: Temperature) -> Voltage {
+        self.calibration.ph_to_voltage(ph, temperature)
+    }
+
+    /// Calibrated slope as a percentage of the ideal Nernst slope
+    pub fn slope_efficiency(&self) -> f32 {
+        self.calibration.slope_25c() / self.ideal_slope() * 100.0
+    }
+
+    /// Acid and base slopes as percentages of the ideal Nernst slope
+    pub fn half_slope_efficiencies(&self) -> (f32, f32) {
+        let (acid, base) = self.calibration.slopes_25c();
+        let ideal = self.ideal_slope();
+        (acid / ideal * 100.0, base / ideal * 100.0)
+    }
+
+    /// Electrode offset at pH 7 (millivolts at the electrode)
+    pub fn offset_mv(&self) -> f32 {
+        let reference = Temperature::from_celsius(REFERENCE_TEMPERATURE);
+        let neutral = self.calibration.neutral_voltage(reference);
+        (neutral.volts() - self.zero_voltage) / self.gain * 1000.0
+    }
+
+    /// Probe condition from slope efficiency and offset
+    pub fn health(&self) -> ProbeHealth {
+        let efficiency = self.slope_efficiency();
+        let slope = if (95.0..=105.0).contains(&efficiency) {
+            ProbeHealth::Good
+        } else if (85.0..=110.0).contains(&efficiency) {
+            ProbeHealth::Fair
+        } else {
+            ProbeHealth::Replace
+        };
+
+        let offset = self.offset_mv().abs();
+        let offset = if offset <= 15.0 {
+            ProbeHealth::Good
+        } else if offset <= 30.0 {
+            ProbeHealth::Fair
+        } else {
+            ProbeHealth::Replace
+        };
+
+        slope.max(offset)
+    }
+}
+
+/// PH4502C read through an ADS1115 channel
+pub struct PhSensor<I2C: ErrorType> {
+    ads: AdsSensor<I2C>,
+    probe: Ph4502c,
+    sample_temperature: Option<Temperature>,
+}
+
+impl<I2C, E> PhSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// ADS1115 channel `mux` at `addr`; the 0-5 V output needs the 6.144V range
+    pub fn new(i2c: I2C, addr: u8, mux: Mux, probe: Ph4502c) -> Result<Self, E> {
+        let ads = AdsSensor::new(i2c, addr, mux, Pga::Gain6_144V, "PH4502C", Unit::Ph)?;
+        Ok(Self {
+            ads,
+            probe,
+            sample_temperature: None,
+        })
+    }
+
+    /// Solution temperature used by `measure`, e.g. from a temperature probe
+    pub fn set_sample_temperature(&mut self, temperature: Option<Temperature>) {
+        self.sample_temperature = temperature;
+    }
+
+    /// Name reported in logs and measurements instead of the default
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.ads.name = name.to_string();
+        self
+    }
+
+    pub fn probe(&self) -> &Ph4502c {
+        &self.probe
+    }
+
+    pub fn probe_mut(&mut self) -> &mut Ph4502c {
+        &mut self.probe
+    }
+
+    /// Raw module voltage, e.g. for a calibration point
+    pub fn get_voltage(&mut self) -> Result<Voltage, E> {
+        self.ads.get_voltage().map(Voltage::from_volts)
+    }
+
+    /// Read the channel and return pH for a sample at `temperature`
+    pub fn get_ph(&mut self, temperature: Temperature) -> Result<Ph, E> {
+        let voltage = self.get_voltage()?;
+        Ok(self.probe.voltage_to_ph(voltage, temperature))
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.ads.release()
+    }
+}
+
+impl<I2C: I2c> PhSensor<LockedI2c<I2C>> {
+    /// Hold the bus lock across each whole conversion
+    pub fn with_bus_lock(mut self) -> Self {
+        self.ads = self.ads.with_bus_lock();
+        self
+    }
+}
+
+impl<I2C, E> Sensor for PhSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    fn id(&self) -> &str {
+        &self.ads.name
+    }
+
+    fn quantity(&self) -> Quantity {
+        Quantity::Ph
+    }
+
+    /// pH at the sample temperature, or at 25 °C flagged as uncompensated
+    fn measure(&mut self) -> Result<Measurement, E> {
+        let voltage = self.ads.get_voltage()?;
+        let reference = Temperature::from_celsius(REFERENCE_TEMPERATURE);
+        let temperature = self.sample_temperature.unwrap_or(reference);
+        let ph = self
+            .probe
+            .voltage_to_ph(Voltage::from_volts(voltage), temperature);
+
+        let mut quality = Quality::GOOD;
+        if is_clipped(voltage, self.ads.full_scale()) {
+            quality.insert(Quality::CLIPPED);
+        }
+        if !(0.0..=14.0).contains(&ph.value()) {
+            quality.insert(Quality::OUT_OF_RANGE);
+        }
+        if self.sample_temperature.is_none() {
+            quality.insert(Quality::UNCOMPENSATED);
+        }
+
+        Ok(Measurement::new(&self.ads.name, Quantity::Ph, ph.value()).with_quality(quality))
+    }
+
+    fn set_sample_temperature(&mut self, temperature: Option<Temperature>) {
+        self.sample_temperature = temperature;
+    }
+}
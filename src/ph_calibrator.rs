@@ -0,0 +1,362 @@
+use crate::calibration::CalibrationRecord;
+use crate::df0991::GeneralRGBColor;
+use crate::ph::{CalibrationPoint, Ph4502c, PhCalibration, PhError, BUFFER_PH4, BUFFER_PH7};
+use crate::stability::{Stability, StabilityDetector};
+use crate::units::{Ph, Temperature, Voltage};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Readings must stay within this band to count as stable (5 mV)
+pub const DEFAULT_STABILITY_BAND: Voltage = Voltage::from_volts(0.005);
+
+/// Time the readings must stay within the band
+pub const DEFAULT_STABILITY_WINDOW: Duration = Duration::from_secs(10);
+
+/// Time allowed to insert a buffer and press the button
+pub const DEFAULT_INSERT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time allowed for the reading to settle in a buffer
+pub const DEFAULT_SETTLE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// How long the green "captured" and the final result are shown
+pub const RESULT_DISPLAY_TIME: Duration = Duration::from_secs(2);
+
+/// Slope efficiencies outside this range mean a bad buffer or probe (%)
+pub const PLAUSIBLE_EFFICIENCY: std::ops::RangeInclusive<f32> = 85.0..=110.0;
+
+/// LED blink half-period while stabilising
+const BLINK_TIME: Duration = Duration::from_millis(250);
+
+/// Why a calibration run was abandoned
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalibrationAbort {
+    /// Long press during the run
+    Cancelled,
+    /// No button press for buffer `buffer` in time
+    InsertTimeout { buffer: Ph },
+    /// The reading in buffer `buffer` did not settle in time
+    SettleTimeout { buffer: Ph },
+    /// The captured points do not give a calibration
+    Calibration(PhError),
+    /// Slope too far from the ideal electrode (% of Nernst)
+    ImplausibleSlope { efficiency: f32 },
+}
+
+impl fmt::Display for CalibrationAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationAbort::Cancelled => write!(f, "calibration cancelled"),
+            CalibrationAbort::InsertTimeout { buffer } => {
+                write!(f, "timed out waiting for the {} buffer", buffer)
+            }
+            CalibrationAbort::SettleTimeout { buffer } => {
+                write!(f, "reading did not settle in the {} buffer", buffer)
+            }
+            CalibrationAbort::Calibration(e) => write!(f, "{}", e),
+            CalibrationAbort::ImplausibleSlope { efficiency } => write!(
+                f,
+                "implausible slope ({:.0}% of ideal), check buffers and probe",
+                efficiency
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationAbort {}
+
+/// Step of a calibration run; `index` is the buffer being worked on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalibrationState {
+    Idle,
+    /// Waiting for the buffer to be inserted and the button pressed
+    AwaitBuffer {
+        index: usize,
+        since: Instant,
+    },
+    /// Waiting for the readings to settle
+    Stabilising {
+        index: usize,
+        since: Instant,
+    },
+    /// Point captured, shown before moving on
+    Captured {
+        index: usize,
+        since: Instant,
+    },
+    /// Run over, the result is shown until `RESULT_DISPLAY_TIME` passed
+    Finished {
+        success: bool,
+        since: Instant,
+    },
+}
+
+/// Guided PH4502C calibration driven by the RGB button.
+///
+/// The caller polls `update` with the current pH module voltage and
+/// forwards button presses; the calibrator tells it which colour to show:
+///
+/// - yellow: insert the next buffer and press the button
+/// - blinking yellow: waiting for the reading to settle
+/// - green: point captured, or calibration done
+/// - red: calibration aborted
+///
+/// After the last buffer the calibration is computed and checked against
+/// the ideal Nernst slope; the result is returned once by `update`.
+pub struct PhCalibrator {
+    probe: Ph4502c,
+    temperature: Temperature,
+    buffers: Vec<Ph>,
+    band: Voltage,
+    window: Duration,
+    insert_timeout: Duration,
+    settle_timeout: Duration,
+    state: CalibrationState,
+    stability: StabilityDetector,
+    points: Vec<CalibrationPoint>,
+}
+
+impl PhCalibrator {
+    /// Two-point calibration (pH 7 then pH 4) of `probe` with the buffers
+    /// at `temperature`
+    pub fn new(probe: Ph4502c, temperature: Temperature) -> Self {
+        Self {
+            probe,
+            temperature,
+            buffers: vec![BUFFER_PH7, BUFFER_PH4],
+            band: DEFAULT_STABILITY_BAND,
+            window: DEFAULT_STABILITY_WINDOW,
+            insert_timeout: DEFAULT_INSERT_TIMEOUT,
+            settle_timeout: DEFAULT_SETTLE_TIMEOUT,
+            state: CalibrationState::Idle,
+            stability: StabilityDetector::new(
+                DEFAULT_STABILITY_BAND.volts(),
+                DEFAULT_STABILITY_WINDOW,
+            ),
+            points: Vec::new(),
+        }
+    }
+
+    /// Buffers in the order they are asked for; two or three
+    pub fn with_buffers(mut self, buffers: &[Ph]) -> Self {
+        assert!(
+            (2..=3).contains(&buffers.len()),
+            "pH calibration needs two or three buffers"
+        );
+        self.buffers = buffers.to_vec();
+        self
+    }
+
+    /// Readings count as stable once they stay within `band` for `window`
+    pub fn with_stability(mut self, band: Voltage, window: Duration) -> Self {
+        self.band = band;
+        self.window = window;
+        self
+    }
+
+    pub fn with_timeouts(mut self, insert: Duration, settle: Duration) -> Self {
+        self.insert_timeout = insert;
+        self.settle_timeout = settle;
+        self
+    }
+
+    pub fn state(&self) -> CalibrationState {
+        self.state
+    }
+
+    /// Whether a run is in progress (or its result still shown)
+    pub fn is_active(&self) -> bool {
+        self.state != CalibrationState::Idle
+    }
+
+    /// Buffer asked for in the current step
+    pub fn current_buffer(&self) -> Option<Ph> {
+        match self.state {
+            CalibrationState::AwaitBuffer { index, .. }
+            | CalibrationState::Stabilising { index, .. }
+            | CalibrationState::Captured { index, .. } => Some(self.buffers[index]),
+            _ => None,
+        }
+    }
+
+    /// Settling of the current buffer, e.g. to show its drift rate
+    pub fn stability(&self) -> &StabilityDetector {
+        &self.stability
+    }
+
+    /// Points captured so far
+    pub fn points(&self) -> &[CalibrationPoint] {
+        &self.points
+    }
+
+    /// Probe the calibrator started from, with the new calibration once done
+    pub fn probe(&self) -> &Ph4502c {
+        &self.probe
+    }
+
+    /// Store record of the finished calibration
+    pub fn record(&self) -> Option<CalibrationRecord> {
+        match self.state {
+            CalibrationState::Finished { success: true, .. } => {
+                Some(CalibrationRecord::ph(&self.probe.calibration, &self.points))
+            }
+            _ => None,
+        }
+    }
+
+    /// Begin a new run with the first buffer
+    pub fn start(&mut self, now: Instant) {
+        self.points.clear();
+        self.state = CalibrationState::AwaitBuffer {
+            index: 0,
+            since: now,
+        };
+    }
+
+    /// Short press: the buffer is in, start waiting for a stable reading
+    pub fn press(&mut self, now: Instant) {
+        if let CalibrationState::AwaitBuffer { index, .. } = self.state {
+            self.stability = StabilityDetector::new(self.band.volts(), self.window)
+                .with_max_wait(self.settle_timeout);
+            self.stability.start(now);
+            self.state = CalibrationState::Stabilising { index, since: now };
+        }
+    }
+
+    /// Long press: abandon the run. Returns false if none was running.
+    pub fn cancel(&mut self, now: Instant) -> bool {
+        match self.state {
+            CalibrationState::Idle | CalibrationState::Finished { .. } => {
+                self.state = CalibrationState::Idle;
+                false
+            }
+            _ => {
+                self.abort(now, CalibrationAbort::Cancelled);
+                true
+            }
+        }
+    }
+
+    /// Advance the run with the module voltage, if one could be read.
+    /// Returns the outcome once, when the run ends.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        voltage: Option<Voltage>,
+    ) -> Option<Result<PhCalibration, CalibrationAbort>> {
+        match self.state {
+            CalibrationState::Idle => None,
+            CalibrationState::AwaitBuffer { index, since } => {
+                if now.duration_since(since) < self.insert_timeout {
+                    return None;
+                }
+                let buffer = self.buffers[index];
+                Some(Err(
+                    self.abort(now, CalibrationAbort::InsertTimeout { buffer })
+                ))
+            }
+            CalibrationState::Stabilising { index, .. } => {
+                let stability = match voltage {
+                    Some(voltage) => self.stability.push(now, voltage.volts()),
+                    None => self.stability.status(now),
+                };
+                let buffer = self.buffers[index];
+                match stability {
+                    Stability::Settling => None,
+                    Stability::Stable { mean } => {
+                        let voltage = Voltage::from_volts(mean);
+                        log::info!("{} buffer captured at {}", buffer, voltage);
+                        self.points.push(CalibrationPoint {
+                            ph: buffer,
+                            voltage,
+                        });
+                        self.state = CalibrationState::Captured { index, since: now };
+                        None
+                    }
+                    Stability::TimedOut => Some(Err(
+                        self.abort(now, CalibrationAbort::SettleTimeout { buffer })
+                    )),
+                }
+            }
+            CalibrationState::Captured { index, since } => {
+                if now.duration_since(since) < RESULT_DISPLAY_TIME {
+                    None
+                } else if index + 1 < self.buffers.len() {
+                    self.state = CalibrationState::AwaitBuffer {
+                        index: index + 1,
+                        since: now,
+                    };
+                    None
+                } else {
+                    Some(self.compute(now))
+                }
+            }
+            CalibrationState::Finished { since, .. } => {
+                if now.duration_since(since) >= RESULT_DISPLAY_TIME {
+                    self.state = CalibrationState::Idle;
+                }
+                None
+            }
+        }
+    }
+
+    /// Colour the button should show now; `None` when idle
+    pub fn led(&self, now: Instant) -> Option<GeneralRGBColor> {
+        match self.state {
+            CalibrationState::Idle => None,
+            CalibrationState::AwaitBuffer { .. } => Some(GeneralRGBColor::Yellow),
+            CalibrationState::Stabilising { since, .. } => {
+                let phase = now.duration_since(since).as_millis() / BLINK_TIME.as_millis();
+                if phase.is_multiple_of(2) {
+                    Some(GeneralRGBColor::Yellow)
+                } else {
+                    Some(GeneralRGBColor::Black)
+                }
+            }
+            CalibrationState::Captured { .. } => Some(GeneralRGBColor::Green),
+            CalibrationState::Finished { success: true, .. } => Some(GeneralRGBColor::Green),
+            CalibrationState::Finished { success: false, .. } => Some(GeneralRGBColor::Red),
+        }
+    }
+
+    fn compute(&mut self, now: Instant) -> Result<PhCalibration, CalibrationAbort> {
+        let calibration = match self.points[..] {
+            [a, b] => PhCalibration::two_point(a, b, self.temperature),
+            [a, b, c] => PhCalibration::three_point([a, b, c], self.temperature),
+            _ => unreachable!("two or three buffers"),
+        };
+        let calibration = match calibration {
+            Ok(calibration) => calibration,
+            Err(e) => return Err(self.abort(now, CalibrationAbort::Calibration(e))),
+        };
+
+        // Each half on its own, so a good mean cannot hide a bad buffer
+        let calibrated = self.probe.with_calibration(calibration);
+        let (acid, base) = calibrated.half_slope_efficiencies();
+        if let Some(efficiency) = [acid, base]
+            .into_iter()
+            .find(|efficiency| !PLAUSIBLE_EFFICIENCY.contains(efficiency))
+        {
+            return Err(self.abort(now, CalibrationAbort::ImplausibleSlope { efficiency }));
+        }
+
+        self.probe = calibrated;
+        self.finish(now, true);
+        Ok(calibration)
+    }
+
+    fn abort(&mut self, now: Instant, reason: CalibrationAbort) -> CalibrationAbort {
+        log::warn!("pH calibration aborted: {}", reason);
+        self.finish(now, false);
+        reason
+    }
+
+    fn finish(&mut self, now: Instant, success: bool) {
+        self.stability.reset();
+        self.state = CalibrationState::Finished {
+            success,
+            since: now,
+        };
+    }
+}
@@ -0,0 +1,168 @@
+use crate::units::{Temperature, Unit};
+use std::{fmt, ops, time::SystemTime};
+
+/// What a measurement is of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Quantity {
+    Voltage,
+    Temperature,
+    Ph,
+    Conductivity,
+}
+
+impl Quantity {
+    /// Unit measurements of this quantity are reported in
+    pub fn unit(&self) -> Unit {
+        match self {
+            Quantity::Voltage => Unit::Volts,
+            Quantity::Temperature => Unit::Celsius,
+            Quantity::Ph => Unit::Ph,
+            Quantity::Conductivity => Unit::MilliSiemensPerCm,
+        }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Quantity::Voltage => "voltage",
+            Quantity::Temperature => "temperature",
+            Quantity::Ph => "pH",
+            Quantity::Conductivity => "conductivity",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Flags qualifying a measurement; empty means good
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Quality(u8);
+
+impl Quality {
+    pub const GOOD: Quality = Quality(0);
+    /// The ADC input sat at full scale
+    pub const CLIPPED: Quality = Quality(1 << 0);
+    /// The value is outside what the probe can measure
+    pub const OUT_OF_RANGE: Quality = Quality(1 << 1);
+    /// The probe is open, shorted or otherwise faulty; the value is NaN
+    pub const PROBE_FAULT: Quality = Quality(1 << 2);
+    /// Converted at 25 °C because no sample temperature was set
+    pub const UNCOMPENSATED: Quality = Quality(1 << 3);
+
+    const NAMES: [(Quality, &'static str); 4] = [
+        (Quality::CLIPPED, "clipped"),
+        (Quality::OUT_OF_RANGE, "out of range"),
+        (Quality::PROBE_FAULT, "probe fault"),
+        (Quality::UNCOMPENSATED, "uncompensated"),
+    ];
+
+    pub fn is_good(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, flags: Quality) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    pub fn insert(&mut self, flags: Quality) {
+        self.0 |= flags.0;
+    }
+
+    /// Names of the set flags, e.g. `["clipped"]`
+    pub fn names(&self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl ops::BitOr for Quality {
+    type Output = Quality;
+
+    fn bitor(self, rhs: Quality) -> Quality {
+        Quality(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_good() {
+            write!(f, "good")
+        } else {
+            write!(f, "{}", self.names().join(", "))
+        }
+    }
+}
+
+/// One reading of one sensor
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub sensor_id: String,
+    pub quantity: Quantity,
+    pub value: f32,
+    pub unit: Unit,
+    pub timestamp: SystemTime,
+    pub quality: Quality,
+}
+
+impl Measurement {
+    /// Good measurement of `quantity` in its default unit, taken now
+    pub fn new(sensor_id: &str, quantity: Quantity, value: f32) -> Self {
+        Self {
+            sensor_id: sensor_id.to_string(),
+            quantity,
+            value,
+            unit: quantity.unit(),
+            timestamp: SystemTime::now(),
+            quality: Quality::GOOD,
+        }
+    }
+
+    pub fn with_quality(mut self, quality: Quality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn is_good(&self) -> bool {
+        self.quality.is_good()
+    }
+}
+
+impl fmt::Display for Measurement {
+    /// e.g. `pH Sensor: 6.20 pH` or `NTC: NaN °C (probe fault)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(match self.quantity {
+            Quantity::Voltage | Quantity::Conductivity => 3,
+            Quantity::Temperature | Quantity::Ph => 2,
+        });
+        write!(
+            f,
+            "{}: {:.*} {}",
+            self.sensor_id, precision, self.value, self.unit
+        )?;
+        if !self.quality.is_good() {
+            write!(f, " ({})", self.quality)?;
+        }
+        Ok(())
+    }
+}
+
+/// Anything that produces measurements, so logging, display and export
+/// code can treat every probe the same way
+pub trait Sensor {
+    type Error;
+
+    /// Name identifying the sensor, e.g. "pH Sensor"
+    fn id(&self) -> &str;
+
+    fn quantity(&self) -> Quantity;
+
+    /// Take one reading
+    fn measure(&mut self) -> Result<Measurement, Self::Error>;
+
+    /// Solution temperature for probes that compensate for it (pH, EC);
+    /// the others ignore it
+    fn set_sample_temperature(&mut self, _temperature: Option<Temperature>) {}
+}
@@ -0,0 +1,133 @@
+pub mod ads1115;
+pub mod df0991;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use std::{cell::RefCell, fmt, rc::Rc};
+
+/// A simulated I2C target
+pub trait SimDevice {
+    /// 7-bit address the device answers on
+    fn address(&self) -> u8;
+
+    /// Bytes written to the device in one write operation
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SimError>;
+
+    /// Fill `buf` with the bytes the device sends back
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), SimError>;
+}
+
+/// Error returned by simulated devices and the simulated bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimError(pub ErrorKind);
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "simulated I2C error: {}", self.0)
+    }
+}
+
+impl std::error::Error for SimError {}
+
+impl embedded_hal::i2c::Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+
+/// Simulated I2C bus routing transactions to the attached devices.
+///
+/// Devices are shared through `Rc<RefCell<_>>`, so a test keeps its handle
+/// to drive inputs and inspect state while a driver owns the bus.
+#[derive(Default)]
+pub struct SimBus {
+    devices: Vec<Rc<RefCell<dyn SimDevice>>>,
+}
+
+impl SimBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a device and return the bus, for chaining
+    pub fn with<D: SimDevice + 'static>(mut self, device: &Rc<RefCell<D>>) -> Self {
+        self.attach(device);
+        self
+    }
+
+    pub fn attach<D: SimDevice + 'static>(&mut self, device: &Rc<RefCell<D>>) {
+        let device: Rc<RefCell<dyn SimDevice>> = device.clone();
+        self.devices.push(device);
+    }
+
+    fn device(&self, address: u8) -> Option<&Rc<RefCell<dyn SimDevice>>> {
+        self.devices
+            .iter()
+            .find(|d| d.borrow().address() == address)
+    }
+}
+
+impl ErrorType for SimBus {
+    type Error = SimError;
+}
+
+impl I2c for SimBus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let device = self
+            .device(address)
+            .ok_or(SimError(ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address,
+            )))?;
+        let mut device = device.borrow_mut();
+
+        for op in operations {
+            match op {
+                Operation::Write(bytes) => device.write(bytes)?,
+                Operation::Read(buf) => device.read(buf)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Signal shape driving a simulated analog input, as a function of time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform {
+    /// Fixed voltage
+    Constant(f32),
+    /// `offset + amplitude * sin(2π t / period)`
+    Sine {
+        offset: f32,
+        amplitude: f32,
+        period: f32,
+    },
+    /// Linear change from `start` at `slope` volts per second
+    Ramp { start: f32, slope: f32 },
+    /// Alternates between `low` and `high` every half `period`
+    Square { low: f32, high: f32, period: f32 },
+}
+
+impl Waveform {
+    /// Voltage `t` seconds after the simulation started
+    pub fn at(&self, t: f32) -> f32 {
+        match *self {
+            Waveform::Constant(v) => v,
+            Waveform::Sine {
+                offset,
+                amplitude,
+                period,
+            } => offset + amplitude * (2.0 * std::f32::consts::PI * t / period).sin(),
+            Waveform::Ramp { start, slope } => start + slope * t,
+            Waveform::Square { low, high, period } => {
+                if (t % period) < period / 2.0 {
+                    low
+                } else {
+                    high
+                }
+            }
+        }
+    }
+}
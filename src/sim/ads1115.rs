@@ -0,0 +1,294 @@
+use super::{SimDevice, SimError, Waveform};
+use crate::ads1115::{CONFIG_REG, CONVERSION_REG};
+use std::time::{Duration, Instant};
+
+/// Remaining ADS1115 registers
+pub const LO_THRESH_REG: u8 = 0x02;
+pub const HI_THRESH_REG: u8 = 0x03;
+
+/// Power-on register values
+pub const CONFIG_DEFAULT: u16 = 0x8583;
+pub const LO_THRESH_DEFAULT: u16 = 0x8000;
+pub const HI_THRESH_DEFAULT: u16 = 0x7FFF;
+
+/// Config register fields
+const OS_BIT: u16 = 1 << 15;
+const MODE_SINGLE_SHOT: u16 = 1 << 8;
+const COMP_MODE_WINDOW: u16 = 1 << 4;
+const COMP_POL_ACTIVE_HIGH: u16 = 1 << 3;
+const COMP_LAT: u16 = 1 << 2;
+const COMP_QUE_MASK: u16 = 0b11;
+
+/// Drives one analog input: volts at `t` seconds after the simulation started
+type Input = Box<dyn FnMut(f32) -> f32>;
+
+/// Simulated ADS1115 16-bit ADC.
+///
+/// Implements the register map (conversion, config, Lo_thresh, Hi_thresh)
+/// with the OS bit, single-shot and continuous mode, conversion time from
+/// the data rate, PGA full-scale clipping, every MUX setting and the ALERT/RDY
+/// comparator. Each AINx is driven by a closure or a `Waveform`.
+pub struct SimAds1115 {
+    addr: u8,
+    pointer: u8,
+    config: u16,
+    lo_thresh: u16,
+    hi_thresh: u16,
+    conversion: i16,
+    inputs: [Input; 4],
+    started: Instant,
+    next_conversion: Option<Instant>,
+    conversions: u64,
+    alert: bool,
+    over_count: u8,
+}
+
+impl SimAds1115 {
+    /// New device at `addr` with all inputs at 0 V and power-on registers
+    pub fn new(addr: u8) -> Self {
+        Self {
+            addr,
+            pointer: CONVERSION_REG,
+            config: CONFIG_DEFAULT,
+            lo_thresh: LO_THRESH_DEFAULT,
+            hi_thresh: HI_THRESH_DEFAULT,
+            conversion: 0,
+            inputs: [
+                Box::new(|_| 0.0),
+                Box::new(|_| 0.0),
+                Box::new(|_| 0.0),
+                Box::new(|_| 0.0),
+            ],
+            started: Instant::now(),
+            next_conversion: None,
+            conversions: 0,
+            alert: false,
+            over_count: 0,
+        }
+    }
+
+    /// Drive AIN`channel` with a closure of the elapsed time in seconds
+    pub fn set_input<F>(&mut self, channel: usize, input: F)
+    where
+        F: FnMut(f32) -> f32 + 'static,
+    {
+        self.inputs[channel] = Box::new(input);
+    }
+
+    /// Drive AIN`channel` with a waveform
+    pub fn set_waveform(&mut self, channel: usize, waveform: Waveform) {
+        self.set_input(channel, move |t| waveform.at(t));
+    }
+
+    /// Hold AIN`channel` at a fixed voltage
+    pub fn set_voltage(&mut self, channel: usize, volts: f32) {
+        self.set_waveform(channel, Waveform::Constant(volts));
+    }
+
+    pub fn config(&self) -> u16 {
+        self.config
+    }
+
+    pub fn thresholds(&self) -> (i16, i16) {
+        (self.lo_thresh as i16, self.hi_thresh as i16)
+    }
+
+    /// Last conversion result
+    pub fn conversion(&self) -> i16 {
+        self.conversion
+    }
+
+    /// Number of finished conversions
+    pub fn conversions(&self) -> u64 {
+        self.conversions
+    }
+
+    /// Whether the comparator currently asserts ALERT/RDY
+    pub fn alert_asserted(&mut self) -> bool {
+        self.update();
+        self.alert
+    }
+
+    /// Electrical level of the ALERT/RDY pin (active low unless COMP_POL is set)
+    pub fn alert_pin_high(&mut self) -> bool {
+        let asserted = self.alert_asserted();
+        if self.config & COMP_POL_ACTIVE_HIGH != 0 {
+            asserted
+        } else {
+            !asserted
+        }
+    }
+
+    /// Full-scale voltage selected by the PGA bits
+    pub fn full_scale(&self) -> f32 {
+        match (self.config >> 9) & 0b111 {
+            0b000 => 6.144,
+            0b001 => 4.096,
+            0b010 => 2.048,
+            0b011 => 1.024,
+            0b100 => 0.512,
+            _ => 0.256,
+        }
+    }
+
+    /// Samples per second selected by the DR bits
+    pub fn data_rate(&self) -> u32 {
+        [8, 16, 32, 64, 128, 250, 475, 860][((self.config >> 5) & 0b111) as usize]
+    }
+
+    fn conversion_time(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.data_rate() as f64)
+    }
+
+    fn is_single_shot(&self) -> bool {
+        self.config & MODE_SINGLE_SHOT != 0
+    }
+
+    /// Differential voltage selected by the MUX bits at time `t`
+    fn sample(&mut self, t: f32) -> f32 {
+        let mut ain = |ch: usize| (self.inputs[ch])(t);
+        match (self.config >> 12) & 0b111 {
+            0b000 => ain(0) - ain(1),
+            0b001 => ain(0) - ain(3),
+            0b010 => ain(1) - ain(3),
+            0b011 => ain(2) - ain(3),
+            0b100 => ain(0),
+            0b101 => ain(1),
+            0b110 => ain(2),
+            _ => ain(3),
+        }
+    }
+
+    /// Finish any conversion that is due by now
+    fn update(&mut self) {
+        let now = Instant::now();
+        while let Some(due) = self.next_conversion {
+            if now < due {
+                break;
+            }
+
+            let t = due.duration_since(self.started).as_secs_f32();
+            let code = (self.sample(t) / self.full_scale() * 32768.0).round();
+            self.conversion = code.clamp(-32768.0, 32767.0) as i16;
+            self.conversions += 1;
+            self.update_comparator();
+
+            self.next_conversion = if self.is_single_shot() {
+                None
+            } else {
+                Some(due + self.conversion_time())
+            };
+        }
+    }
+
+    /// Hi_thresh MSB set and Lo_thresh MSB clear: ALERT/RDY signals conversion ready
+    fn is_ready_mode(&self) -> bool {
+        self.hi_thresh & 0x8000 != 0 && self.lo_thresh & 0x8000 == 0
+    }
+
+    fn update_comparator(&mut self) {
+        let queue = self.config & COMP_QUE_MASK;
+        if queue == COMP_QUE_MASK {
+            return;
+        }
+
+        if self.is_ready_mode() {
+            self.alert = true;
+            return;
+        }
+
+        let lo = self.lo_thresh as i16;
+        let hi = self.hi_thresh as i16;
+
+        let window = self.config & COMP_MODE_WINDOW != 0;
+        let beyond = self.conversion > hi || (window && self.conversion < lo);
+        let latching = self.config & COMP_LAT != 0;
+
+        if beyond {
+            self.over_count = self.over_count.saturating_add(1);
+            if self.over_count >= 1 << queue {
+                self.alert = true;
+            }
+        } else {
+            self.over_count = 0;
+            let released = window || self.conversion < lo;
+            if released && !latching {
+                self.alert = false;
+            }
+        }
+    }
+
+    fn register(&mut self, reg: u8) -> u16 {
+        self.update();
+        match reg {
+            CONVERSION_REG => {
+                // Reading the result clears a latched alert
+                if self.config & COMP_LAT != 0 {
+                    self.alert = false;
+                }
+                self.conversion as u16
+            }
+            CONFIG_REG => {
+                let busy = self.next_conversion.is_some() && self.is_single_shot();
+                if busy {
+                    self.config & !OS_BIT
+                } else {
+                    self.config | OS_BIT
+                }
+            }
+            LO_THRESH_REG => self.lo_thresh,
+            _ => self.hi_thresh,
+        }
+    }
+
+    fn write_config(&mut self, value: u16) {
+        self.update();
+        self.config = value & !OS_BIT;
+
+        // A single-shot conversion starts only when OS is written as 1 while idle
+        let start = if self.is_single_shot() {
+            value & OS_BIT != 0 && self.next_conversion.is_none()
+        } else {
+            self.next_conversion.is_none()
+        };
+
+        if start {
+            if self.is_ready_mode() {
+                self.alert = false;
+            }
+            self.next_conversion = Some(Instant::now() + self.conversion_time());
+        }
+    }
+}
+
+impl SimDevice for SimAds1115 {
+    fn address(&self) -> u8 {
+        self.addr
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SimError> {
+        let Some((&pointer, data)) = bytes.split_first() else {
+            return Ok(());
+        };
+        self.pointer = pointer & 0b11;
+
+        if data.len() >= 2 {
+            let value = u16::from_be_bytes([data[0], data[1]]);
+            match self.pointer {
+                CONFIG_REG => self.write_config(value),
+                LO_THRESH_REG => self.lo_thresh = value,
+                HI_THRESH_REG => self.hi_thresh = value,
+                _ => {} // conversion register is read only
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), SimError> {
+        let value = self.register(self.pointer).to_be_bytes();
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = value[i % 2];
+        }
+        Ok(())
+    }
+}
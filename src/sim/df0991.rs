@@ -0,0 +1,185 @@
+use super::{SimDevice, SimError};
+use crate::df0991::RGBBUTTON_PART_ID;
+use std::time::{Duration, Instant};
+
+/// Register addresses
+const ADDR_REG: usize = 0x00;
+const RED_REG: usize = 0x01;
+const GREEN_REG: usize = 0x02;
+const BLUE_REG: usize = 0x03;
+const BUTTON_SIGNAL_REG: usize = 0x04;
+const PID_MSB_REG: usize = 0x09;
+const PID_LSB_REG: usize = 0x0A;
+const REG_COUNT: usize = 0x10;
+
+/// One LED colour change, relative to the creation of the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LedChange {
+    pub at: Duration,
+    pub rgb: (u8, u8, u8),
+}
+
+/// Simulated DFRobot DF0991 RGB button.
+///
+/// Serves the register map used by `DFRobotRGBButton` (address, RGB, button
+/// signal, PID), plays back scripted press/release sequences and records
+/// every LED colour written to it.
+pub struct SimDf0991 {
+    addr: u8,
+    regs: [u8; REG_COUNT],
+    pointer: usize,
+    started: Instant,
+    script: Vec<(Duration, bool)>,
+    script_start: Instant,
+    manual: bool,
+    leds: Vec<LedChange>,
+}
+
+impl SimDf0991 {
+    /// New released button at `addr` with the LED off
+    pub fn new(addr: u8) -> Self {
+        let mut regs = [0u8; REG_COUNT];
+        regs[ADDR_REG] = addr;
+        regs[PID_MSB_REG] = (RGBBUTTON_PART_ID >> 8) as u8;
+        regs[PID_LSB_REG] = (RGBBUTTON_PART_ID & 0xFF) as u8;
+
+        let now = Instant::now();
+        Self {
+            addr,
+            regs,
+            pointer: 0,
+            started: now,
+            script: Vec::new(),
+            script_start: now,
+            manual: false,
+            leds: Vec::new(),
+        }
+    }
+
+    /// Press and hold the button
+    pub fn press(&mut self) {
+        self.script.clear();
+        self.manual = true;
+    }
+
+    /// Let go of the button
+    pub fn release(&mut self) {
+        self.script.clear();
+        self.manual = false;
+    }
+
+    /// Play a press/release sequence starting now. Each entry is the time
+    /// since the start of the script and the button state from then on.
+    pub fn script(&mut self, events: &[(Duration, bool)]) {
+        self.script = events.to_vec();
+        self.script.sort_by_key(|(at, _)| *at);
+        self.script_start = Instant::now();
+    }
+
+    /// Press for `hold` after `delay`, with `bounces` short contact bounces
+    /// of `bounce` each on press and release
+    pub fn script_press(
+        &mut self,
+        delay: Duration,
+        hold: Duration,
+        bounces: u32,
+        bounce: Duration,
+    ) {
+        let mut events = Vec::new();
+        let mut at = delay;
+        for _ in 0..bounces {
+            events.push((at, true));
+            events.push((at + bounce, false));
+            at += bounce * 2;
+        }
+        events.push((at, true));
+
+        at += hold;
+        for _ in 0..bounces {
+            events.push((at, false));
+            events.push((at + bounce, true));
+            at += bounce * 2;
+        }
+        events.push((at, false));
+
+        self.script(&events);
+    }
+
+    /// Whether the button is pressed right now
+    pub fn is_pressed(&self) -> bool {
+        if self.script.is_empty() {
+            return self.manual;
+        }
+
+        let elapsed = self.script_start.elapsed();
+        self.script
+            .iter()
+            .take_while(|(at, _)| *at <= elapsed)
+            .last()
+            .map(|(_, pressed)| *pressed)
+            .unwrap_or(self.manual)
+    }
+
+    /// Current LED colour
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        (
+            self.regs[RED_REG],
+            self.regs[GREEN_REG],
+            self.regs[BLUE_REG],
+        )
+    }
+
+    /// Every colour written to the LED, oldest first
+    pub fn led_history(&self) -> &[LedChange] {
+        &self.leds
+    }
+
+    /// Just the colours of `led_history`
+    pub fn led_colors(&self) -> Vec<(u8, u8, u8)> {
+        self.leds.iter().map(|c| c.rgb).collect()
+    }
+
+    pub fn clear_led_history(&mut self) {
+        self.leds.clear();
+    }
+}
+
+impl SimDevice for SimDf0991 {
+    fn address(&self) -> u8 {
+        self.addr
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SimError> {
+        let Some((&reg, data)) = bytes.split_first() else {
+            return Ok(());
+        };
+        self.pointer = reg as usize % REG_COUNT;
+
+        let mut led_written = false;
+        for (i, b) in data.iter().enumerate() {
+            let reg = (self.pointer + i) % REG_COUNT;
+            match reg {
+                // Read only
+                BUTTON_SIGNAL_REG | PID_MSB_REG | PID_LSB_REG => {}
+                _ => self.regs[reg] = *b,
+            }
+            led_written |= (RED_REG..=BLUE_REG).contains(&reg);
+        }
+
+        if led_written {
+            self.leds.push(LedChange {
+                at: self.started.elapsed(),
+                rgb: self.rgb(),
+            });
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), SimError> {
+        self.regs[BUTTON_SIGNAL_REG] = self.is_pressed() as u8;
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = self.regs[(self.pointer + i) % REG_COUNT];
+        }
+        Ok(())
+    }
+}
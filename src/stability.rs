@@ -0,0 +1,199 @@
+use std::{
+    collections::VecDeque,
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+/// Where a settling probe stands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stability {
+    /// Not enough history yet, or the readings still move too much
+    Settling,
+    /// The readings stayed within the band over the whole window
+    Stable { mean: f32 },
+    /// The maximum wait passed without the readings settling
+    TimedOut,
+}
+
+impl Stability {
+    pub fn is_stable(&self) -> bool {
+        matches!(self, Stability::Stable { .. })
+    }
+}
+
+impl fmt::Display for Stability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stability::Settling => write!(f, "settling"),
+            Stability::Stable { mean } => write!(f, "stable at {:.4}", mean),
+            Stability::TimedOut => write!(f, "did not settle"),
+        }
+    }
+}
+
+/// Decides when a probe has settled, e.g. after moving it between
+/// buffers or into the tank.
+///
+/// Readings (raw volts or converted values) are fed with the time they
+/// were taken. The probe is stable once the readings of the last
+/// `window` all lie within `band` of each other and the newest one is no
+/// older than the average interval between them, so failed reads cannot
+/// settle on stale data; if that does not happen within `max_wait` of the
+/// last `reset`, it timed out.
+#[derive(Clone, Debug)]
+pub struct StabilityDetector {
+    band: f32,
+    window: Duration,
+    max_wait: Option<Duration>,
+    started: Option<Instant>,
+    readings: VecDeque<(Instant, f32)>,
+}
+
+impl StabilityDetector {
+    /// Stable once the readings stay within `band` (in reading units)
+    /// for `window`; waits forever by default
+    pub fn new(band: f32, window: Duration) -> Self {
+        Self {
+            band,
+            window,
+            max_wait: None,
+            started: None,
+            readings: VecDeque::new(),
+        }
+    }
+
+    /// Give up after `max_wait`, counted from the first reading after `reset`
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    pub fn band(&self) -> f32 {
+        self.band
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Forget all readings and restart the wait, e.g. after moving the probe
+    pub fn reset(&mut self) {
+        self.started = None;
+        self.readings.clear();
+    }
+
+    /// Start the wait at `now` even before the first reading arrives
+    pub fn start(&mut self, now: Instant) {
+        self.reset();
+        self.started = Some(now);
+    }
+
+    /// Add the reading `value` taken at `now` and check the state
+    pub fn push(&mut self, now: Instant, value: f32) -> Stability {
+        self.started.get_or_insert(now);
+        if value.is_finite() {
+            self.readings.push_back((now, value));
+        }
+        self.status(now)
+    }
+
+    /// State at `now` without a new reading (e.g. when a read failed)
+    pub fn status(&mut self, now: Instant) -> Stability {
+        self.trim(now);
+
+        if let Some(mean) = self.stable_mean(now) {
+            return Stability::Stable { mean };
+        }
+        match (self.started, self.max_wait) {
+            (Some(started), Some(max_wait)) if now.duration_since(started) >= max_wait => {
+                Stability::TimedOut
+            }
+            _ => Stability::Settling,
+        }
+    }
+
+    /// Spread (max - min) of the readings in the window
+    pub fn spread(&self) -> Option<f32> {
+        let values = self.readings.iter().map(|&(_, v)| v);
+        let min = values.clone().reduce(f32::min)?;
+        let max = values.reduce(f32::max)?;
+        Some(max - min)
+    }
+
+    /// Least-squares slope of the readings in the window (units per
+    /// second); `None` with fewer than two readings
+    pub fn drift_rate(&self) -> Option<f32> {
+        let &(origin, _) = self.readings.front()?;
+        let points: Vec<(f64, f64)> = self
+            .readings
+            .iter()
+            .map(|&(at, v)| (at.duration_since(origin).as_secs_f64(), v as f64))
+            .collect();
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_v = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(t, v)| (t - mean_t) * (v - mean_v))
+            .sum();
+        let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+        if variance <= 0.0 {
+            return None;
+        }
+        Some((covariance / variance) as f32)
+    }
+
+    /// Read with `read` every `interval` until stable or timed out,
+    /// blocking the thread. Failed reads end the wait with the error.
+    pub fn wait<E, F>(&mut self, interval: Duration, mut read: F) -> Result<Stability, E>
+    where
+        F: FnMut() -> Result<f32, E>,
+    {
+        self.start(Instant::now());
+        loop {
+            let value = read()?;
+            let state = self.push(Instant::now(), value);
+            if state != Stability::Settling {
+                return Ok(state);
+            }
+            log::debug!(
+                "Settling: spread {:?}, drift {:?}/s",
+                self.spread(),
+                self.drift_rate()
+            );
+            thread::sleep(interval);
+        }
+    }
+
+    /// Drop readings older than the window, keeping the newest one that
+    /// is at least a window old so the history can cover it exactly
+    fn trim(&mut self, now: Instant) {
+        while self
+            .readings
+            .get(1)
+            .is_some_and(|&(at, _)| now.duration_since(at) >= self.window)
+        {
+            self.readings.pop_front();
+        }
+    }
+
+    /// Mean of the window once it is covered by at least two readings,
+    /// the newest within one poll interval of `now`, and within the band
+    fn stable_mean(&self, now: Instant) -> Option<f32> {
+        let count = self.readings.len();
+        if count < 2 {
+            return None;
+        }
+        let (&(first, _), &(last, _)) = (self.readings.front()?, self.readings.back()?);
+        let interval = last.duration_since(first) / (count - 1) as u32;
+        if now.duration_since(first) < self.window
+            || now.duration_since(last) > interval
+            || self.spread()? > self.band
+        {
+            return None;
+        }
+        let sum: f32 = self.readings.iter().map(|&(_, v)| v).sum();
+        Some(sum / self.readings.len() as f32)
+    }
+}
@@ -1,45 +1,609 @@
 /*
  * Always keep my coding and comment style.
  */
-use std::f32;
+use std::{
+    f32, fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::ads1115::{AdsSensor, Mux, Pga};
+use crate::i2c::lock::LockedI2c;
+use crate::sensor::{Measurement, Quality, Quantity, Sensor};
+use crate::units::{Temperature, Unit, Voltage};
+use embedded_hal::i2c::{ErrorType, I2c};
+
+/// Offset between Celsius and Kelvin
+pub const KELVIN_OFFSET: f32 = 273.15;
+
+/// Readings this close to 0 V or the supply (fraction of the supply) are
+/// treated as an open or shorted probe
+pub const RAIL_MARGIN: f32 = 0.01;
+
+/// Physical range of a water probe (°C); anything outside is a fault
+pub const MIN_TEMPERATURE: f32 = -40.0;
+pub const MAX_TEMPERATURE: f32 = 125.0;
+
+/// Why a thermistor reading could not be turned into a temperature
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TemperatureError {
+    /// Voltage at the rail a disconnected probe pulls to
+    OpenCircuit { voltage: Voltage },
+    /// Voltage at the rail a shorted probe pulls to
+    ShortCircuit { voltage: Voltage },
+    /// Voltage clipped at an ADC full scale below the supply rail, where
+    /// an open (high side) or shorted (low side) probe cannot be told
+    /// from a valid reading beyond full scale
+    OverRange {
+        voltage: Voltage,
+        full_scale: Voltage,
+        topology: Topology,
+    },
+    /// Resistance maps outside the physical range or the R–T table
+    OutOfRange { resistance: f32 },
+}
+
+impl fmt::Display for TemperatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemperatureError::OpenCircuit { voltage } => {
+                write!(f, "probe disconnected ({})", voltage)
+            }
+            TemperatureError::ShortCircuit { voltage } => {
+                write!(f, "probe shorted ({})", voltage)
+            }
+            TemperatureError::OverRange {
+                voltage,
+                full_scale,
+                topology,
+            } => {
+                let fault = match topology {
+                    Topology::HighSide => "disconnected",
+                    Topology::LowSide => "shorted",
+                };
+                write!(
+                    f,
+                    "ADC over range ({} at {} full scale): probe {} or beyond full scale, \
+                     the PGA range hides the supply rail",
+                    voltage, full_scale, fault
+                )
+            }
+            TemperatureError::OutOfRange { resistance } => {
+                write!(f, "probe out of range ({:.0} ohms)", resistance)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemperatureError {}
+
+/// Where the thermistor sits in the voltage divider read by the ADC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Thermistor on the high side (the original hydro-sense wiring):
+    /// Vout = Vsupply * R_thermistor / (R_fixed + R_thermistor)
+    HighSide,
+    /// Thermistor on the low side:
+    /// Vout = Vsupply * R_fixed / (R_fixed + R_thermistor)
+    LowSide,
+}
+
+/// Steinhart–Hart coefficients: 1/T = A + B * ln(R) + C * ln(R)³ (T in kelvin)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SteinhartHart {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl SteinhartHart {
+    /// Solve the coefficients from three (°C, ohms) calibration points.
+    ///
+    /// Returns `None` if a resistance is not positive or two points share
+    /// a temperature or resistance. Points spread over the working range
+    /// (e.g. 5, 20 and 35 °C) give the best fit.
+    pub fn fit(points: [(f32, f32); 3]) -> Option<Self> {
+        if points.iter().any(|&(_, r)| r <= 0.0) {
+            return None;
+        }
+
+        let [(t1, r1), (t2, r2), (t3, r3)] = points;
+        let (l1, l2, l3) = ((r1 as f64).ln(), (r2 as f64).ln(), (r3 as f64).ln());
+        let y1 = 1.0 / (t1 as f64 + KELVIN_OFFSET as f64);
+        let y2 = 1.0 / (t2 as f64 + KELVIN_OFFSET as f64);
+        let y3 = 1.0 / (t3 as f64 + KELVIN_OFFSET as f64);
+
+        if l1 == l2 || l1 == l3 || l2 == l3 || y1 == y2 || y1 == y3 || y2 == y3 {
+            return None;
+        }
+
+        let g2 = (y2 - y1) / (l2 - l1);
+        let g3 = (y3 - y1) / (l3 - l1);
+        let c = (g3 - g2) / (l3 - l2) / (l1 + l2 + l3);
+        let b = g2 - c * (l1 * l1 + l1 * l2 + l2 * l2);
+        let a = y1 - (b + c * l1 * l1) * l1;
+
+        let fitted = Self { a, b, c };
+        (a.is_finite() && b.is_finite() && c.is_finite()).then_some(fitted)
+    }
+
+    /// Temperature of the thermistor at `resistance` ohms
+    pub fn temperature(&self, resistance: f32) -> Temperature {
+        if resistance <= 0.0 {
+            return Temperature::from_celsius(f32::NAN); // invalid resistance
+        }
+
+        let ln_r = (resistance as f64).ln();
+        let inv_t = self.a + self.b * ln_r + self.c * ln_r.powi(3);
+        Temperature::from_kelvin((1.0 / inv_t) as f32)
+    }
+
+    /// Resistance (ohms) of the thermistor at `temperature`, the inverse of
+    /// `temperature`
+    pub fn resistance(&self, temperature: Temperature) -> f32 {
+        let inv_t = 1.0 / temperature.kelvin() as f64;
+
+        // C * x³ + B * x + (A - 1/T) = 0 with x = ln(R), solved by Cardano
+        let ln_r = if self.c == 0.0 {
+            (inv_t - self.a) / self.b
+        } else {
+            let p = self.b / self.c;
+            let q = (self.a - inv_t) / self.c;
+            let root = (q * q / 4.0 + p.powi(3) / 27.0).sqrt();
+            (-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt()
+        };
+        ln_r.exp() as f32
+    }
+}
+
+/// Resistance outside the range covered by an `RtTable`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutOfTable {
+    pub resistance: f32,
+    /// Lowest and highest resistance in the table (ohms)
+    pub min: f32,
+    pub max: f32,
+}
+
+impl fmt::Display for OutOfTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} ohms is outside the R-T table ({:.0} to {:.0} ohms)",
+            self.resistance, self.min, self.max
+        )
+    }
+}
+
+impl std::error::Error for OutOfTable {}
+
+/// Resistance/temperature table from a probe datasheet.
+///
+/// Loaded from CSV with one `°C,ohms` pair per line; a header line and
+/// lines starting with `#` are skipped. Temperatures between two rows are
+/// interpolated linearly against ln(R), which follows the NTC curve closely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RtTable {
+    /// (°C, ohms) sorted by rising resistance
+    points: Vec<(f32, f32)>,
+}
+
+impl RtTable {
+    /// Table from (°C, ohms) pairs in any order; needs at least two points
+    pub fn new(mut points: Vec<(f32, f32)>) -> io::Result<Self> {
+        if points.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "R-T table needs at least two points",
+            ));
+        }
+        if points
+            .iter()
+            .any(|&(t, r)| !t.is_finite() || !r.is_finite() || r <= 0.0)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "R-T table resistances must be positive",
+            ));
+        }
+
+        points.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if points.windows(2).any(|w| w[0].1 == w[1].1) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "R-T table lists the same resistance twice",
+            ));
+        }
+        Ok(Self { points })
+    }
+
+    /// Load a CSV table from `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_csv(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse a CSV table from any reader. Blank and `#` lines are skipped,
+    /// as is one line of column names before the first point.
+    pub fn from_csv<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut points = Vec::new();
+        let mut first = true;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split(',').map(|f| f.trim().parse::<f32>());
+            match (fields.next(), fields.next()) {
+                (Some(Ok(t)), Some(Ok(r))) => points.push((t, r)),
+                // Column names
+                (Some(Err(_)), _) if first => {}
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("bad R-T table line {}: '{}'", index + 1, line),
+                    ))
+                }
+            }
+            first = false;
+        }
+        Self::new(points)
+    }
+
+    /// (°C, ohms) points sorted by rising resistance
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Temperature at `resistance` ohms, interpolated log-linearly
+    pub fn temperature(&self, resistance: f32) -> Result<Temperature, OutOfTable> {
+        let (min, max) = (self.points[0].1, self.points[self.points.len() - 1].1);
+        if !(min..=max).contains(&resistance) {
+            return Err(OutOfTable {
+                resistance,
+                min,
+                max,
+            });
+        }
+
+        // First point at or above the resistance; the range check keeps it in bounds
+        let upper = self.points.partition_point(|&(_, r)| r < resistance).max(1);
+        let (t1, r1) = self.points[upper - 1];
+        let (t2, r2) = self.points[upper];
+
+        let fraction = (resistance.ln() - r1.ln()) / (r2.ln() - r1.ln());
+        Ok(Temperature::from_celsius(t1 + (t2 - t1) * fraction))
+    }
+
+    /// Resistance (ohms) at `temperature`, the inverse of `temperature`;
+    /// `None` outside the table
+    pub fn resistance(&self, temperature: Temperature) -> Option<f32> {
+        let t = temperature.celsius();
+        self.points.windows(2).find_map(|w| {
+            let ((t1, r1), (t2, r2)) = (w[0], w[1]);
+            if !(t1.min(t2)..=t1.max(t2)).contains(&t) {
+                return None;
+            }
+
+            let fraction = if t1 == t2 { 0.0 } else { (t - t1) / (t2 - t1) };
+            Some((r1.ln() + (r2.ln() - r1.ln()) * fraction).exp())
+        })
+    }
+}
+
+/// How resistance is turned into temperature
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThermistorModel {
+    /// Beta equation from `r0`, `t0` and `beta`
+    Beta,
+    /// Calibrated Steinhart–Hart coefficients
+    SteinhartHart(SteinhartHart),
+    /// Interpolated datasheet R–T table
+    Table(Arc<RtTable>),
+}
+
+/// NTC thermistor and divider parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThermistorParams {
+    /// Thermistor resistance at T0 (ohms)
+    pub r0: f32,
+    /// Reference temperature (°C)
+    pub t0: f32,
+    /// Beta coefficient (K)
+    pub beta: f32,
+    /// Fixed divider resistor (ohms)
+    pub r_fixed: f32,
+    pub topology: Topology,
+    pub model: ThermistorModel,
+}
+
+impl Default for ThermistorParams {
+    /// 10k NTC, B=3950 at 25 °C with a 10k resistor, thermistor on the high side
+    fn default() -> Self {
+        Self {
+            r0: 10_000.0,
+            t0: 25.0,
+            beta: 3950.0,
+            r_fixed: 10_000.0,
+            topology: Topology::HighSide,
+            model: ThermistorModel::Beta,
+        }
+    }
+}
+
+impl ThermistorParams {
+    /// Thermistor resistance (ohms) from the divider voltage, NaN if the
+    /// voltage is outside the divider range
+    pub fn resistance(&self, supply_voltage: Voltage, measured_voltage: Voltage) -> f32 {
+        let (supply_voltage, measured_voltage) = (supply_voltage.volts(), measured_voltage.volts());
+        if measured_voltage <= 0.0 || measured_voltage >= supply_voltage {
+            return f32::NAN;
+        }
+
+        // Solve the divider formula of the topology for R_thermistor
+        match self.topology {
+            Topology::HighSide => {
+                self.r_fixed * measured_voltage / (supply_voltage - measured_voltage)
+            }
+            Topology::LowSide => {
+                self.r_fixed * (supply_voltage - measured_voltage) / measured_voltage
+            }
+        }
+    }
+
+    /// Use calibrated Steinhart–Hart coefficients instead of the Beta equation
+    pub fn with_steinhart_hart(mut self, coefficients: SteinhartHart) -> Self {
+        self.model = ThermistorModel::SteinhartHart(coefficients);
+        self
+    }
+
+    /// Convert with a datasheet R–T table instead of the Beta equation
+    pub fn with_table(mut self, table: RtTable) -> Self {
+        self.model = ThermistorModel::Table(Arc::new(table));
+        self
+    }
+
+    /// Temperature from the thermistor resistance using the selected
+    /// model, NaN outside an R–T table
+    pub fn resistance_to_temperature(&self, resistance: f32) -> Temperature {
+        match &self.model {
+            ThermistorModel::Beta => self.beta_temperature(resistance),
+            ThermistorModel::SteinhartHart(sh) => sh.temperature(resistance),
+            ThermistorModel::Table(table) => table
+                .temperature(resistance)
+                .unwrap_or(Temperature::from_celsius(f32::NAN)),
+        }
+    }
+
+    /// Thermistor resistance (ohms) at `temperature` using the selected
+    /// model, NaN outside an R–T table
+    pub fn temperature_to_resistance(&self, temperature: Temperature) -> f32 {
+        match &self.model {
+            ThermistorModel::Beta => {
+                // R = R0 * e^(B * (1/T - 1/T0))
+                let t0_kelvin = self.t0 + KELVIN_OFFSET;
+                let exponent = self.beta * (1.0 / temperature.kelvin() - 1.0 / t0_kelvin);
+                self.r0 * exponent.exp()
+            }
+            ThermistorModel::SteinhartHart(sh) => sh.resistance(temperature),
+            ThermistorModel::Table(table) => table.resistance(temperature).unwrap_or(f32::NAN),
+        }
+    }
+
+    /// Divider output for a thermistor of `resistance` ohms
+    pub fn resistance_to_voltage(&self, supply_voltage: Voltage, resistance: f32) -> Voltage {
+        let thermistor_share = match self.topology {
+            Topology::HighSide => resistance / (self.r_fixed + resistance),
+            Topology::LowSide => self.r_fixed / (self.r_fixed + resistance),
+        };
+        Voltage::from_volts(supply_voltage.volts() * thermistor_share)
+    }
+
+    /// Voltage the ADC sees with the thermistor at `temperature`, the
+    /// inverse of `voltage_to_temperature`
+    pub fn temperature_to_voltage(
+        &self,
+        supply_voltage: Voltage,
+        temperature: Temperature,
+    ) -> Voltage {
+        let resistance = self.temperature_to_resistance(temperature);
+        self.resistance_to_voltage(supply_voltage, resistance)
+    }
+
+    /// Beta equation: 1/T = 1/T0 + 1/B * ln(R/R0)
+    fn beta_temperature(&self, resistance: f32) -> Temperature {
+        if resistance <= 0.0 {
+            return Temperature::from_celsius(f32::NAN); // invalid resistance
+        }
+
+        let t0_kelvin = self.t0 + KELVIN_OFFSET;
+        let inv_t = (1.0 / t0_kelvin) + (1.0 / self.beta) * (resistance / self.r0).ln();
+
+        Temperature::from_kelvin(1.0 / inv_t)
+    }
+
+    /// Temperature from the ADC voltage of the divider, NaN for invalid input signals
+    pub fn voltage_to_temperature(
+        &self,
+        supply_voltage: Voltage,   // e.g. 5.0 volts
+        pga_voltage: Voltage,      // e.g. 6.144 volts (ADS1115 PGA full scale)
+        measured_voltage: Voltage, // voltage measured at ADC
+    ) -> Temperature {
+        if measured_voltage > pga_voltage {
+            return Temperature::from_celsius(f32::NAN); // clipped by the ADC
+        }
+
+        self.resistance_to_temperature(self.resistance(supply_voltage, measured_voltage))
+    }
+
+    /// Temperature from the ADC voltage of the divider, with the reason
+    /// when the reading is not a valid temperature
+    pub fn try_voltage_to_temperature(
+        &self,
+        supply_voltage: Voltage,   // e.g. 5.0 volts
+        pga_voltage: Voltage,      // e.g. 6.144 volts (ADS1115 PGA full scale)
+        measured_voltage: Voltage, // voltage measured at ADC
+    ) -> Result<Temperature, TemperatureError> {
+        // An open thermistor leaves its end of the divider to the fixed
+        // resistor; a shorted one pulls it across
+        let (supply, measured) = (supply_voltage.volts(), measured_voltage.volts());
+        let margin = supply * RAIL_MARGIN;
+
+        // The highest ADS1115 code is one LSB below full scale. A clipped
+        // reading is at the supply rail only if full scale reaches it
+        let clipped = measured >= pga_voltage.volts() * 32767.0 / 32768.0;
+        let rail_visible = pga_voltage.volts() >= supply - margin;
+        if clipped && !rail_visible {
+            return Err(TemperatureError::OverRange {
+                voltage: measured_voltage,
+                full_scale: pga_voltage,
+                topology: self.topology,
+            });
+        }
+
+        let near_supply = clipped || measured >= supply - margin;
+        let near_ground = measured <= margin;
+        let voltage = measured_voltage;
+        match (self.topology, near_supply, near_ground) {
+            (Topology::HighSide, true, _) | (Topology::LowSide, _, true) => {
+                return Err(TemperatureError::OpenCircuit { voltage })
+            }
+            (Topology::HighSide, _, true) | (Topology::LowSide, true, _) => {
+                return Err(TemperatureError::ShortCircuit { voltage })
+            }
+            _ => {}
+        }
+
+        let resistance = self.resistance(supply_voltage, measured_voltage);
+        let temperature = self.resistance_to_temperature(resistance);
+        if (MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&temperature.celsius()) {
+            Ok(temperature)
+        } else {
+            Err(TemperatureError::OutOfRange { resistance })
+        }
+    }
+}
 
 /// Convert ADC voltage reading from 10k NTC thermistor voltage divider (thermistor on high side).
+///
+/// Shorthand for `ThermistorParams::default().voltage_to_temperature(..)`.
 pub fn voltage_to_temperature(
-    supply_voltage: f32,   // e.g. 5.0 volts
-    pga_voltage: f32,      // e.g. 6.144 volts (ADS1115 PGA full scale)
-    measured_voltage: f32, // voltage measured at ADC (volts)
-) -> f32 {
-    // Constants for thermistor and fixed resistor
-    let r_fixed = 10000.0_f32; // Fixed resistor in ohms (10k)
-    let r0 = 10000.0_f32; // Thermistor resistance at T0 (10k)
-    let b = 3950.0_f32; // Beta coefficient
-    let t0_kelvin = 25.0_f32 + 273.15_f32; // Reference temp in Kelvin (25°C)
-
-    // Validate input voltages
-    if measured_voltage <= 0.0
-        || measured_voltage >= supply_voltage
-        || measured_voltage > pga_voltage
-    {
-        return f32::NAN; // invalid input signals
-    }
-
-    // Calculate thermistor resistance from voltage divider formula:
-    // Vout = Vsupply * (R_thermistor) / (R_fixed + R_thermistor)
-    // => R_thermistor = R_fixed * Vout / (Vsupply - Vout)
-    let r_thermistor = r_fixed * measured_voltage / (supply_voltage - measured_voltage);
-    // let r_thermistor = r_fixed * (supply_voltage / measured_voltage - 1.0);
-
-    if r_thermistor <= 0.0 {
-        return f32::NAN; // invalid resistance
-    }
-
-    // Calculate temperature using Beta parameter equation:
-    // 1/T = 1/T0 + 1/B * ln(R/R0)
-    let ln_ratio = (r_thermistor / r0).ln();
-    let inv_t = (1.0 / t0_kelvin) + (1.0 / b) * ln_ratio;
-
-    let temperature_kelvin = 1.0 / inv_t;
-    let temperature_celsius = temperature_kelvin - 273.15_f32;
-
-    temperature_celsius
+    supply_voltage: Voltage,   // e.g. 5.0 volts
+    pga_voltage: Voltage,      // e.g. 6.144 volts (ADS1115 PGA full scale)
+    measured_voltage: Voltage, // voltage measured at ADC
+) -> Temperature {
+    ThermistorParams::default().voltage_to_temperature(
+        supply_voltage,
+        pga_voltage,
+        measured_voltage,
+    )
+}
+
+/// Divider voltage of the 10k high-side default probe at `temperature`,
+/// the inverse of `voltage_to_temperature`
+pub fn temperature_to_voltage(supply_voltage: Voltage, temperature: Temperature) -> Voltage {
+    ThermistorParams::default().temperature_to_voltage(supply_voltage, temperature)
+}
+
+/// Fallible `voltage_to_temperature` for the 10k high-side default probe
+pub fn try_voltage_to_temperature(
+    supply_voltage: Voltage,
+    pga_voltage: Voltage,
+    measured_voltage: Voltage,
+) -> Result<Temperature, TemperatureError> {
+    ThermistorParams::default().try_voltage_to_temperature(
+        supply_voltage,
+        pga_voltage,
+        measured_voltage,
+    )
+}
+
+/// NTC thermistor divider read through an ADS1115 channel
+pub struct NtcSensor<I2C: ErrorType> {
+    ads: AdsSensor<I2C>,
+    params: ThermistorParams,
+    supply_voltage: Voltage,
+}
+
+impl<I2C, E> NtcSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// ADS1115 channel `mux` at `addr` reading a divider fed from `supply_voltage`
+    pub fn new(
+        i2c: I2C,
+        addr: u8,
+        mux: Mux,
+        pga: Pga,
+        supply_voltage: Voltage,
+        params: ThermistorParams,
+    ) -> Result<Self, E> {
+        let ads = AdsSensor::new(i2c, addr, mux, pga, "NTC Thermistor", Unit::Celsius)?;
+        Ok(Self {
+            ads,
+            params,
+            supply_voltage,
+        })
+    }
+
+    /// Name reported in logs and measurements instead of the default
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.ads.name = name.to_string();
+        self
+    }
+
+    pub fn params(&self) -> &ThermistorParams {
+        &self.params
+    }
+
+    /// Read the channel and convert, with the reason for a faulty reading
+    pub fn get_temperature(&mut self) -> Result<Result<Temperature, TemperatureError>, E> {
+        let voltage = Voltage::from_volts(self.ads.get_voltage()?);
+        let full_scale = Voltage::from_volts(self.ads.full_scale());
+        Ok(self
+            .params
+            .try_voltage_to_temperature(self.supply_voltage, full_scale, voltage))
+    }
+
+    /// Release underlying I2C interface
+    pub fn release(self) -> I2C {
+        self.ads.release()
+    }
+}
+
+impl<I2C: I2c> NtcSensor<LockedI2c<I2C>> {
+    /// Hold the bus lock across each whole conversion
+    pub fn with_bus_lock(mut self) -> Self {
+        self.ads = self.ads.with_bus_lock();
+        self
+    }
+}
+
+impl<I2C, E> Sensor for NtcSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    fn id(&self) -> &str {
+        &self.ads.name
+    }
+
+    fn quantity(&self) -> Quantity {
+        Quantity::Temperature
+    }
+
+    /// Temperature in °C; probe faults give NaN with the matching flag
+    fn measure(&mut self) -> Result<Measurement, E> {
+        let (value, quality) = match self.get_temperature()? {
+            Ok(temperature) => (temperature.celsius(), Quality::GOOD),
+            Err(TemperatureError::OverRange { .. }) => (f32::NAN, Quality::CLIPPED),
+            Err(TemperatureError::OutOfRange { .. }) => (f32::NAN, Quality::OUT_OF_RANGE),
+            Err(_) => (f32::NAN, Quality::PROBE_FAULT),
+        };
+        Ok(Measurement::new(&self.ads.name, Quantity::Temperature, value).with_quality(quality))
+    }
 }
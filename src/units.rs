@@ -0,0 +1,201 @@
+use std::fmt;
+
+/// Unit a reading is reported in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Volts,
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+    Ph,
+    MilliSiemensPerCm,
+    Ppm,
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Volts => "V",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Kelvin => "K",
+            Unit::Ph => "pH",
+            Unit::MilliSiemensPerCm => "mS/cm",
+            Unit::Ppm => "ppm",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// Temperature, stored in degrees Celsius
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Temperature(f32);
+
+impl Temperature {
+    pub const fn from_celsius(celsius: f32) -> Self {
+        Self(celsius)
+    }
+
+    pub fn from_fahrenheit(fahrenheit: f32) -> Self {
+        Self((fahrenheit - 32.0) * 5.0 / 9.0)
+    }
+
+    pub fn from_kelvin(kelvin: f32) -> Self {
+        Self(kelvin - crate::temperature::KELVIN_OFFSET)
+    }
+
+    pub fn celsius(&self) -> f32 {
+        self.0
+    }
+
+    pub fn fahrenheit(&self) -> f32 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    pub fn kelvin(&self) -> f32 {
+        self.0 + crate::temperature::KELVIN_OFFSET
+    }
+
+    /// Value in `unit`; `unit` must be Celsius, Fahrenheit or Kelvin
+    pub fn value_in(&self, unit: Unit) -> f32 {
+        match unit {
+            Unit::Fahrenheit => self.fahrenheit(),
+            Unit::Kelvin => self.kelvin(),
+            _ => self.celsius(),
+        }
+    }
+
+    /// Formats in `unit` instead of Celsius, e.g. `{:.1}` gives "77.0 °F"
+    pub fn display(&self, unit: Unit) -> TemperatureDisplay {
+        TemperatureDisplay {
+            temperature: *self,
+            unit,
+        }
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+/// A `Temperature` formatted in a chosen unit
+pub struct TemperatureDisplay {
+    temperature: Temperature,
+    unit: Unit,
+}
+
+impl fmt::Display for TemperatureDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            Unit::Fahrenheit | Unit::Kelvin => self.unit,
+            _ => Unit::Celsius,
+        };
+        let precision = f.precision().unwrap_or(2);
+        write!(
+            f,
+            "{:.*} {}",
+            precision,
+            self.temperature.value_in(unit),
+            unit
+        )
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.display(Unit::Celsius), f)
+    }
+}
+
+/// Electric potential in volts
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Voltage(f32);
+
+impl Voltage {
+    pub const fn from_volts(volts: f32) -> Self {
+        Self(volts)
+    }
+
+    pub fn from_millivolts(millivolts: f32) -> Self {
+        Self(millivolts / 1000.0)
+    }
+
+    pub fn volts(&self) -> f32 {
+        self.0
+    }
+
+    pub fn millivolts(&self) -> f32 {
+        self.0 * 1000.0
+    }
+}
+
+impl fmt::Display for Voltage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.*} {}",
+            f.precision().unwrap_or(3),
+            self.0,
+            Unit::Volts
+        )
+    }
+}
+
+/// Acidity on the pH scale
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Ph(f32);
+
+impl Ph {
+    pub const fn new(ph: f32) -> Self {
+        Self(ph)
+    }
+
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+}
+
+impl fmt::Display for Ph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:.*}", Unit::Ph, f.precision().unwrap_or(2), self.0)
+    }
+}
+
+/// Electrical conductivity, stored in mS/cm
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Conductivity(f32);
+
+impl Conductivity {
+    pub const fn from_ms_per_cm(ms_per_cm: f32) -> Self {
+        Self(ms_per_cm)
+    }
+
+    pub fn from_us_per_cm(us_per_cm: f32) -> Self {
+        Self(us_per_cm / 1000.0)
+    }
+
+    pub fn ms_per_cm(&self) -> f32 {
+        self.0
+    }
+
+    pub fn us_per_cm(&self) -> f32 {
+        self.0 * 1000.0
+    }
+}
+
+impl fmt::Display for Conductivity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.*} {}",
+            f.precision().unwrap_or(3),
+            self.0,
+            Unit::MilliSiemensPerCm
+        )
+    }
+}
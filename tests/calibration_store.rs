@@ -0,0 +1,207 @@
+mod common;
+
+use hydro_sense::calibration::{
+    CalibrationRecord, CalibrationStatus, CalibrationStore, Coefficients, StoreError,
+};
+use hydro_sense::config::Config;
+use hydro_sense::ec::{EcMeter, EC_STANDARD_HIGH, EC_STANDARD_LOW};
+use hydro_sense::ph::{CalibrationPoint, PhCalibration, BUFFER_PH4, BUFFER_PH7};
+use hydro_sense::units::{Temperature, Voltage};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn store_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "hydro-sense-calibration-{}-{}.json",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// Whole-second timestamp, as stored in the file
+fn at_day(day: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_760_000_000) + DAY * day as u32
+}
+
+/// Two-point pH calibration with the pH 7 buffer reading at `neutral` volts
+fn ph_record(neutral: f32, day: u64) -> CalibrationRecord {
+    let points = [
+        CalibrationPoint {
+            ph: BUFFER_PH7,
+            voltage: Voltage::from_volts(neutral),
+        },
+        CalibrationPoint {
+            ph: BUFFER_PH4,
+            voltage: Voltage::from_volts(neutral + 0.5),
+        },
+    ];
+    let calibration =
+        PhCalibration::two_point(points[0], points[1], Temperature::from_celsius(22.0)).unwrap();
+    CalibrationRecord::ph(&calibration, &points).with_timestamp(at_day(day))
+}
+
+#[test]
+fn test_save_and_reload() -> anyhow::Result<()> {
+    common::init_logger();
+    let path = store_path("reload");
+
+    let mut meter = EcMeter::default();
+    let temperature = Temperature::from_celsius(25.0);
+    let low = (EC_STANDARD_LOW, Voltage::from_volts(0.24));
+    let high = (EC_STANDARD_HIGH, Voltage::from_volts(2.1));
+    meter.calibrate_two_point(low, high, temperature)?;
+
+    let mut store = CalibrationStore::open(&path)?;
+    assert_eq!(store.probes().count(), 0);
+    store.record("ph", ph_record(2.52, 0));
+    store.record(
+        "ec",
+        CalibrationRecord::ec(&meter, &[low, high], temperature).with_timestamp(at_day(0)),
+    );
+    store.save()?;
+    log::info!("{}", fs::read_to_string(&path)?);
+
+    let reloaded = CalibrationStore::open(&path)?;
+    assert_eq!(reloaded.probes().collect::<Vec<_>>(), ["ec", "ph"]);
+    assert_eq!(reloaded.current("ph"), store.current("ph"));
+
+    // The stored coefficients rebuild the same calibrations
+    let ph = reloaded.current("ph").unwrap();
+    let calibration = ph.ph_calibration().unwrap();
+    assert_eq!(calibration.anchor.ph, BUFFER_PH7);
+    assert_eq!(calibration.temperature.celsius(), 22.0);
+    assert_eq!(ph.points.len(), 2);
+    let at_7 = calibration.voltage_to_ph(Voltage::from_volts(2.52), calibration.temperature);
+    assert!((at_7.value() - 7.0).abs() < 1e-4);
+    assert!(ph.apply_to_ec(EcMeter::default()).is_none());
+
+    let ec = reloaded.current("ec").unwrap();
+    let restored = ec.apply_to_ec(EcMeter::default()).unwrap();
+    assert_eq!(
+        (restored.k, restored.zero_offset),
+        (meter.k, meter.zero_offset)
+    );
+    assert_eq!(ec.slope(), meter.k);
+    assert_eq!(ec.offset(), meter.zero_offset);
+    assert!(matches!(ec.coefficients, Coefficients::Ec { .. }));
+
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_slope_and_offset() {
+    // 0.5 V over 2.99 pH at 22 °C, normalised to 25 °C
+    let record = ph_record(2.52, 0);
+    let expected_slope = -0.5 / 2.99 * 298.15 / 295.15;
+    assert!((record.slope() - expected_slope).abs() < 1e-6);
+    assert!((record.offset() - 2.52).abs() < 1e-6);
+}
+
+#[test]
+fn test_history_and_rollback() -> anyhow::Result<()> {
+    let path = store_path("history");
+    let mut store = CalibrationStore::open(&path)?.with_history_limit(3);
+
+    for (day, neutral) in [2.50, 2.51, 2.52, 2.53].iter().enumerate() {
+        store.record("ph", ph_record(*neutral, day as u64));
+    }
+
+    // Only the three newest are kept
+    let offsets: Vec<f32> = store.history("ph").iter().map(|r| r.offset()).collect();
+    assert_eq!(offsets.len(), 3);
+    assert!((offsets[0] - 2.51).abs() < 1e-6);
+
+    // Roll back a bad calibration to the previous one
+    let previous = store.rollback("ph").unwrap();
+    assert!((previous.offset() - 2.52).abs() < 1e-6);
+    assert_eq!(store.rollback("ph").unwrap().timestamp, at_day(1));
+
+    // The last calibration stays
+    assert!(store.rollback("ph").is_none());
+    assert_eq!(store.history("ph").len(), 1);
+    assert!(store.rollback("ec").is_none());
+    assert!(store.history("ec").is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_expiry() -> anyhow::Result<()> {
+    let mut store = CalibrationStore::open(store_path("expiry"))?.with_max_age(DAY * 30);
+    store.record("ph", ph_record(2.5, 0));
+
+    assert_eq!(store.status("ec", at_day(0)), CalibrationStatus::Missing);
+    assert_eq!(
+        store.status("ph", at_day(10)),
+        CalibrationStatus::Current { age: DAY * 10 }
+    );
+    let expired = store.status("ph", at_day(31));
+    assert_eq!(expired, CalibrationStatus::Expired { age: DAY * 31 });
+    assert_eq!(expired.to_string(), "calibration expired (31 days old)");
+    assert!(expired.needs_calibration());
+    assert!(CalibrationStatus::Missing.needs_calibration());
+
+    // A clock set back before the calibration does not expire it
+    assert_eq!(
+        store.status("ph", at_day(0) - DAY),
+        CalibrationStatus::Current {
+            age: Duration::ZERO
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_store_from_config() -> anyhow::Result<()> {
+    let path = store_path("config");
+    let text = format!(
+        "[calibration]\nfile = \"{}\"\nmax_age_days = 7\nhistory = 2\n",
+        path.display()
+    );
+    let config = Config::parse(&text)?;
+    let mut store = config.calibration.open_store()?;
+    assert_eq!(store.path(), path);
+    assert_eq!(store.max_age(), DAY * 7);
+
+    for day in 0..3 {
+        store.record("ph", ph_record(2.5, day));
+    }
+    assert_eq!(store.history("ph").len(), 2);
+    assert!(store.status("ph", at_day(10)).needs_calibration());
+
+    // The stock setup uses defaults
+    assert_eq!(Config::default().calibration.max_age_days, 30);
+
+    Ok(())
+}
+
+#[test]
+fn test_corrupt_store() {
+    let path = store_path("corrupt");
+    fs::write(
+        &path,
+        "{ \"probes\": { \"ph\": [ { \"timestamp\": \"yesterday\" } ] } }",
+    )
+    .unwrap();
+    assert!(matches!(
+        CalibrationStore::open(&path),
+        Err(StoreError::Parse(_))
+    ));
+    let _ = fs::remove_file(&path);
+
+    // Only a missing file is treated as an empty store
+    let directory = std::env::temp_dir();
+    assert!(matches!(
+        CalibrationStore::open(directory),
+        Err(StoreError::Io(_))
+    ));
+}
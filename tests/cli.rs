@@ -0,0 +1,366 @@
+mod common;
+
+use clap::Parser;
+use hydro_sense::ads1115::ADS1115_ADDR_A;
+use hydro_sense::cli::{
+    parse_address, parse_color, AdapterList, BusArgs, CalibrationReport, Cli, Command,
+    DeviceReport, Format, MeasurementReport, ScanReport,
+};
+use hydro_sense::config::{Config, ConfigError, DeviceKind};
+use hydro_sense::df0991::{GeneralRGBColor, RGBBUTTON_DEFAULT_I2C_ADDR};
+use hydro_sense::i2c::{list_adapters_in, scan, AdapterInfo, SCAN_ADDRESSES};
+use hydro_sense::ph::{CalibrationPoint, Ph4502c, PhCalibration, BUFFER_PH4, BUFFER_PH7};
+use hydro_sense::sensor::{Measurement, Quality, Quantity};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::df0991::SimDf0991;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::{Temperature, Voltage};
+use serde_json::json;
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+fn parse(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("hydro-sense").chain(args.iter().copied()))
+        .unwrap_or_else(|e| panic!("{:?}: {}", args, e))
+}
+
+#[test]
+fn test_parse_subcommands() {
+    // No subcommand monitors, as the binary always did
+    let cli = parse(&[]);
+    assert_eq!(cli.format, Format::Text);
+    assert_eq!(
+        cli.command(),
+        Command::Monitor {
+            bus: BusArgs::default()
+        }
+    );
+
+    // Global options go before or after the subcommand
+    let cli = parse(&["read", "pH", "EC", "--format", "json", "-c", "lab.toml"]);
+    assert_eq!(cli.format, Format::Json);
+    assert_eq!(cli.config.as_deref(), Some("lab.toml".as_ref()));
+    assert_eq!(
+        cli.command(),
+        Command::Read {
+            sensors: vec!["pH".to_string(), "EC".to_string()],
+            bus: BusArgs::default()
+        }
+    );
+
+    let cli = parse(&["scan", "--adapter", "CH341", "--address", "0x48"]);
+    assert_eq!(
+        cli.command(),
+        Command::Scan {
+            bus: BusArgs {
+                adapter: Some("CH341".to_string()),
+                address: Some(0x48)
+            }
+        }
+    );
+
+    match parse(&["calibrate", "--buffers", "7,4,10"]).command() {
+        Command::Calibrate {
+            sensor, buffers, ..
+        } => {
+            assert_eq!(sensor, None);
+            assert_eq!(buffers, [7.0, 4.0, 10.0]);
+        }
+        other => panic!("expected calibrate, got {:?}", other),
+    }
+    match parse(&["calibrate", "pH"]).command() {
+        Command::Calibrate {
+            sensor, buffers, ..
+        } => {
+            assert_eq!(sensor.as_deref(), Some("pH"));
+            assert_eq!(buffers, [7.0, 4.0]);
+        }
+        other => panic!("expected calibrate, got {:?}", other),
+    }
+
+    assert!(matches!(
+        parse(&["led", "off", "-A", "42"]).command(),
+        Command::Led {
+            color: GeneralRGBColor::Black,
+            bus: BusArgs {
+                address: Some(42),
+                ..
+            }
+        }
+    ));
+    assert!(matches!(
+        parse(&["display", "--hold", "0.5"]).command(),
+        Command::Display { hold, .. } if hold == Duration::from_millis(500)
+    ));
+    assert_eq!(parse(&["adapters"]).command(), Command::Adapters);
+
+    // Bad input is rejected by the parser
+    for args in [
+        &["led", "pink"][..],
+        &["scan", "--address", "0x80"],
+        &["read", "--format", "xml"],
+        &["display", "--hold", "inf"],
+        &["display", "--hold", "1e30"],
+        &["flash"],
+    ] {
+        assert!(
+            Cli::try_parse_from(std::iter::once("hydro-sense").chain(args.iter().copied()))
+                .is_err(),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn test_parse_address_and_color() {
+    assert_eq!(parse_address("0x2A"), Ok(0x2A));
+    assert_eq!(parse_address("0X3c"), Ok(0x3C));
+    assert_eq!(parse_address("72"), Ok(0x48));
+    for bad in ["0x03", "0x78", "256", "0xZZ", ""] {
+        assert!(parse_address(bad).is_err(), "{}", bad);
+    }
+
+    assert_eq!(parse_color("Green"), Ok(GeneralRGBColor::Green));
+    assert_eq!(parse_color("black"), Ok(GeneralRGBColor::Black));
+    assert_eq!(
+        parse_color("pink"),
+        Err("unknown colour 'pink'".to_string())
+    );
+}
+
+#[test]
+fn test_bus_targets() {
+    let config = Config::default();
+
+    // The configured button, unless the options say otherwise
+    let target = BusArgs::default()
+        .target(&config, DeviceKind::Df0991, RGBBUTTON_DEFAULT_I2C_ADDR)
+        .unwrap();
+    assert_eq!(target.adapter.name, "mcp2221");
+    assert_eq!(target.address, 0x2A);
+    assert_eq!(target.device.as_deref(), Some("ph_cal_btn"));
+
+    let options = BusArgs {
+        adapter: Some("mcp2221".to_string()),
+        address: Some(0x3C),
+    };
+    let target = options
+        .target(&config, DeviceKind::Df0991, RGBBUTTON_DEFAULT_I2C_ADDR)
+        .unwrap();
+    assert_eq!(target.address, 0x3C);
+    assert_eq!(target.device.as_deref(), Some("oled"));
+    assert_eq!(target.adapter, config.adapters[0]);
+
+    // An adapter that is not configured is looked up by its sysfs name
+    let options = BusArgs {
+        adapter: Some("CH341".to_string()),
+        address: None,
+    };
+    let target = options
+        .target(&config, DeviceKind::Df0991, RGBBUTTON_DEFAULT_I2C_ADDR)
+        .unwrap();
+    assert_eq!(target.adapter.sysfs_name, "CH341");
+    assert_eq!(target.adapter.lock_file, None);
+    assert_eq!(target.address, 0x2A);
+    assert_eq!(target.device, None);
+
+    // Nothing configured: the default address, but no adapter to use
+    let empty = Config::parse("").unwrap();
+    assert!(BusArgs::default()
+        .target(&empty, DeviceKind::Ssd1306, 0x3C)
+        .is_none());
+}
+
+#[test]
+fn test_apply_to_config() {
+    let mut config = Config::default();
+    BusArgs {
+        adapter: Some("CH341".to_string()),
+        address: Some(0x49),
+    }
+    .apply(&mut config, "adc")
+    .unwrap();
+
+    let adc = config.device("adc").unwrap();
+    assert_eq!((adc.adapter.as_str(), adc.address), ("CH341", 0x49));
+    assert_eq!(config.adapter("CH341").unwrap().sysfs_name, "CH341");
+    assert!(config.validate().is_ok());
+
+    // The button stays where it was
+    let button = config.device("ph_cal_btn").unwrap();
+    assert_eq!((button.adapter.as_str(), button.address), ("mcp2221", 0x2A));
+
+    // No options change nothing
+    let mut unchanged = Config::default();
+    BusArgs::default().apply(&mut unchanged, "adc").unwrap();
+    assert_eq!(unchanged, Config::default());
+
+    // Nor can the ADC move onto the OLED's address
+    let mut config = Config::default();
+    let moved = BusArgs {
+        adapter: None,
+        address: Some(0x3C),
+    }
+    .apply(&mut config, "adc");
+    assert!(matches!(moved, Err(ConfigError::InvalidAddress { .. })));
+}
+
+#[test]
+fn test_scan_simulated_bus() {
+    common::init_logger();
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    let button = Rc::new(RefCell::new(SimDf0991::new(RGBBUTTON_DEFAULT_I2C_ADDR)));
+    let mut bus = SimBus::new().with(&ads).with(&button);
+
+    let found = scan(&mut bus, SCAN_ADDRESSES);
+    assert_eq!(found, [0x2A, 0x48]);
+    assert!(scan(&mut bus, 0x49..=0x49).is_empty());
+
+    let config = Config::default();
+    let report = ScanReport::new("mcp2221", "/dev/i2c-7", &found, &config);
+    assert_eq!(
+        Format::Text.render(&report),
+        "mcp2221 (/dev/i2c-7): 2 device(s)\n  0x2A  ph_cal_btn\n  0x48  adc"
+    );
+    let json: serde_json::Value = serde_json::from_str(&Format::Json.render(&report)).unwrap();
+    assert_eq!(
+        json["devices"][1],
+        json!({ "address": 72, "device": "adc" })
+    );
+}
+
+#[test]
+fn test_list_adapters() -> std::io::Result<()> {
+    // Fake sysfs tree, listed by bus number rather than name
+    let root = std::env::temp_dir().join(format!("hydro-sense-cli-{}", std::process::id()));
+    for (dir, name) in [
+        ("i2c-10", "CH341 I2C USB bus 003 device 004"),
+        ("i2c-0", "i915 gmbus dpb"),
+        ("i2c-7", "MCP2221 usb-i2c bridge"),
+    ] {
+        std::fs::create_dir_all(root.join(dir))?;
+        std::fs::write(root.join(dir).join("name"), format!("{}\n", name))?;
+    }
+    let adapters = list_adapters_in(&root)?;
+    std::fs::remove_dir_all(&root)?;
+
+    let devices: Vec<&str> = adapters.iter().map(|a| a.device.as_str()).collect();
+    assert_eq!(devices, ["/dev/i2c-0", "/dev/i2c-7", "/dev/i2c-10"]);
+    assert_eq!(
+        adapters[1],
+        AdapterInfo {
+            device: "/dev/i2c-7".to_string(),
+            name: "MCP2221 usb-i2c bridge".to_string()
+        }
+    );
+
+    // The configured adapter is named
+    let list = AdapterList::new(adapters, &Config::default());
+    let text = Format::Text.render(&list);
+    assert_eq!(text.lines().count(), 3);
+    assert_eq!(
+        text.lines().nth(1),
+        Some("/dev/i2c-7   MCP2221 usb-i2c bridge (mcp2221)")
+    );
+    let json: serde_json::Value = serde_json::from_str(&Format::Json.render(&list)).unwrap();
+    assert_eq!(json[0]["configured"], serde_json::Value::Null);
+    assert_eq!(json[1]["configured"], "mcp2221");
+
+    assert_eq!(
+        Format::Text.render(&AdapterList(Vec::new())),
+        "No I2C adapters found"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_measurement_reports() {
+    let config = Config::default();
+
+    // pH alarm is 5.5-6.5
+    let reading = Measurement::new("pH", Quantity::Ph, 6.8);
+    let report = MeasurementReport::new(&reading, &config);
+    assert!(report.alarm);
+    assert_eq!(Format::Text.render(&report), "pH: 6.80 pH (alarm)");
+
+    let json: serde_json::Value = serde_json::from_str(&Format::Json.render(&report)).unwrap();
+    assert_eq!(json["sensor"], "pH");
+    assert_eq!(json["quantity"], "pH");
+    assert_eq!(json["alarm"], true);
+    assert!((json["value"].as_f64().unwrap() - 6.8).abs() < 1e-6);
+    assert!(json["timestamp"].as_f64().unwrap() > 1.7e9);
+    assert_eq!(json["quality"], json!([]));
+
+    // pH without a sample temperature is flagged uncompensated, and
+    // still alarms
+    let uncompensated =
+        Measurement::new("pH", Quantity::Ph, 5.2).with_quality(Quality::UNCOMPENSATED);
+    let report = MeasurementReport::new(&uncompensated, &config);
+    assert!(report.alarm);
+    assert_eq!(
+        Format::Text.render(&report),
+        "pH: 5.20 pH (uncompensated) (alarm)"
+    );
+    let in_range = Measurement::new("pH", Quantity::Ph, 6.0).with_quality(Quality::UNCOMPENSATED);
+    assert!(!MeasurementReport::new(&in_range, &config).alarm);
+
+    // A faulty reading reports no alarm and no value
+    let fault = Measurement::new("Water Temp", Quantity::Temperature, f32::NAN)
+        .with_quality(Quality::PROBE_FAULT);
+    let report = MeasurementReport::new(&fault, &config);
+    assert!(!report.alarm);
+    assert!(Format::Text.render(&report).ends_with("(probe fault)"));
+    let json: serde_json::Value = serde_json::from_str(&Format::Json.render(&report)).unwrap();
+    assert_eq!(json["value"], serde_json::Value::Null);
+    assert_eq!(json["quality"], json!(["probe fault"]));
+
+    // Sensors without limits never alarm
+    let air = Measurement::new("Air Temp", Quantity::Temperature, 45.0);
+    assert!(!MeasurementReport::new(&air, &config).alarm);
+}
+
+#[test]
+fn test_calibration_and_device_reports() {
+    // An ideal electrode; the module voltage rises as the pH falls
+    let ideal = Ph4502c::default().ideal_slope();
+    let points = [
+        CalibrationPoint {
+            ph: BUFFER_PH7,
+            voltage: Voltage::from_volts(2.5),
+        },
+        CalibrationPoint {
+            ph: BUFFER_PH4,
+            voltage: Voltage::from_volts(2.5 + (BUFFER_PH4.value() - 7.0) * ideal),
+        },
+    ];
+    let calibration =
+        PhCalibration::two_point(points[0], points[1], Temperature::from_celsius(25.0)).unwrap();
+    let probe = Ph4502c::default().with_calibration(calibration);
+
+    let report = CalibrationReport::new("pH", &probe, &points);
+    let text = Format::Text.render(&report);
+    assert!(
+        text.starts_with("pH calibrated: slope 100.0% of ideal"),
+        "{}",
+        text
+    );
+    assert_eq!(text.lines().count(), 3);
+    let json: serde_json::Value = serde_json::from_str(&Format::Json.render(&report)).unwrap();
+    assert_eq!(json["buffers"].as_array().unwrap().len(), 2);
+    assert_eq!(json["health"], "good");
+
+    let config = Config::default();
+    let target = BusArgs::default()
+        .target(&config, DeviceKind::Df0991, RGBBUTTON_DEFAULT_I2C_ADDR)
+        .unwrap();
+    let report = DeviceReport::new(&target, "LED set to Green");
+    assert_eq!(
+        Format::Text.render(&report),
+        "mcp2221 0x2A: LED set to Green"
+    );
+    assert_eq!(
+        Format::Json.render(&report),
+        r#"{"adapter":"mcp2221","address":42,"result":"LED set to Green"}"#
+    );
+}
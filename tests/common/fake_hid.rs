@@ -0,0 +1,201 @@
+use hydro_sense::mcp2221::*;
+use std::{cell::RefCell, io, rc::Rc};
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                      Fake HID Transport                      │
+// │                                                              │
+// │ Answers MCP2221 HID reports without hardware. A single       │
+// │ byte-wide register file sits on the bus at `addr`: the first │
+// │ written byte sets the register pointer, further bytes are    │
+// │ stored from there, reads return bytes from the pointer.      │
+// │                                                              │
+// │ `stuck` holds SDA low and the engine busy until a cancel,    │
+// │ `stick_on_write` sets it on the next write and `jammed`      │
+// │ keeps it that way whatever the host sends. `wedged` keeps    │
+// │ the engine busy until a chip reset, which also drops the     │
+// │ speed and GP settings. `sda_held` is the number of reads     │
+// │ (SCL clock bursts) a target needs before it lets go of SDA.  │
+// │ `short_read` answers reads with one byte less than asked.    │
+// │                                                              │
+// │ It also keeps the GP settings, pin levels, ADC results and   │
+// │ DAC value so the GP commands can be checked, and logs every  │
+// │ SRAM settings report it is sent.                             │
+// └──────────────────────────────────────────────────────────────┘
+pub struct FakeHid {
+    pub addr: u8,
+    pub regs: [u8; 256],
+    pub pointer: usize,
+    pub nack: bool,
+    pub write_buf: Vec<u8>,
+    pub read_buf: Vec<u8>,
+    pub commands: Vec<u8>,
+    pub stuck: bool,
+    pub stick_on_write: bool,
+    pub jammed: bool,
+    pub wedged: bool,
+    pub sda_held: usize,
+    pub resets: usize,
+    pub short_read: bool,
+    pub divider: u8,
+    pub gp_settings: [u8; GP_COUNT],
+    pub levels: [bool; GP_COUNT],
+    pub adc: [u16; 3],
+    pub dac: u8,
+    pub sram_reports: Rc<RefCell<Vec<[u8; REPORT_LEN]>>>,
+}
+
+impl Default for FakeHid {
+    fn default() -> Self {
+        Self {
+            addr: 0x00,
+            regs: [0u8; 256],
+            pointer: 0,
+            nack: false,
+            write_buf: Vec::new(),
+            read_buf: Vec::new(),
+            commands: Vec::new(),
+            stuck: false,
+            stick_on_write: false,
+            jammed: false,
+            wedged: false,
+            sda_held: 0,
+            resets: 0,
+            short_read: false,
+            divider: 0,
+            gp_settings: [0u8; GP_COUNT],
+            levels: [false; GP_COUNT],
+            adc: [0u16; 3],
+            dac: 0,
+            sram_reports: Rc::default(),
+        }
+    }
+}
+
+impl FakeHid {
+    /// Fake with its register file on the bus at `addr`
+    pub fn new(addr: u8) -> Self {
+        Self {
+            addr,
+            ..Self::default()
+        }
+    }
+}
+
+impl HidTransport for FakeHid {
+    fn exchange(&mut self, command: &[u8; REPORT_LEN]) -> io::Result<[u8; REPORT_LEN]> {
+        let mut response = [0u8; REPORT_LEN];
+        response[0] = command[0];
+        self.commands.push(command[0]);
+
+        let len = u16::from_le_bytes([command[1], command[2]]) as usize;
+        let addr = command[3] >> 1;
+
+        match command[0] {
+            CMD_STATUS_SET_PARAMS => {
+                if command[2] == STATUS_CANCEL_TRANSFER {
+                    self.nack = false;
+                    self.stuck = self.jammed || self.wedged;
+                    self.write_buf.clear();
+                }
+                if command[3] == STATUS_SET_SPEED {
+                    self.divider = command[4];
+                    response[3] = STATUS_SET_SPEED;
+                }
+                if self.nack {
+                    response[STATUS_I2C_ACK] = STATUS_I2C_NACK_MASK;
+                }
+                if self.stuck {
+                    response[STATUS_I2C_STATE] = 0x55;
+                }
+                if !self.stuck && self.sda_held == 0 {
+                    response[STATUS_SDA_LEVEL] = 1;
+                }
+                response[STATUS_SCL_LEVEL] = 1;
+
+                for (i, value) in self.adc.iter().enumerate() {
+                    let offset = STATUS_ADC_DATA + 2 * i;
+                    response[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+                }
+            }
+            CMD_I2C_WRITE | CMD_I2C_WRITE_NO_STOP | CMD_I2C_WRITE_REPEATED_START => {
+                if addr != self.addr {
+                    self.nack = true;
+                    return Ok(response);
+                }
+                if self.stick_on_write {
+                    self.stick_on_write = false;
+                    self.stuck = true;
+                    return Ok(response);
+                }
+                let n = (len - self.write_buf.len()).min(I2C_CHUNK_LEN);
+                self.write_buf.extend_from_slice(&command[4..4 + n]);
+
+                if self.write_buf.len() == len && len > 0 {
+                    self.pointer = self.write_buf[0] as usize;
+                    for (i, b) in self.write_buf[1..].iter().enumerate() {
+                        self.regs[(self.pointer + i) % 256] = *b;
+                    }
+                    self.write_buf.clear();
+                }
+            }
+            CMD_I2C_READ | CMD_I2C_READ_REPEATED_START => {
+                if self.sda_held > 0 {
+                    self.sda_held -= 1;
+                    return Ok(response);
+                }
+                if addr != self.addr {
+                    self.nack = true;
+                    return Ok(response);
+                }
+                let len = if self.short_read { len - 1 } else { len };
+                self.read_buf = (0..len)
+                    .map(|i| self.regs[(self.pointer + i) % 256])
+                    .collect();
+            }
+            CMD_I2C_GET_DATA => {
+                if self.nack {
+                    response[1] = 0x41;
+                    response[3] = 0x7F;
+                    return Ok(response);
+                }
+                let n = self.read_buf.len().min(I2C_CHUNK_LEN);
+                response[3] = n as u8;
+                response[4..4 + n].copy_from_slice(&self.read_buf[..n]);
+                self.read_buf.drain(..n);
+            }
+            CMD_SET_SRAM_SETTINGS => {
+                self.sram_reports.borrow_mut().push(*command);
+                if command[4] & SRAM_ALTER != 0 {
+                    self.dac = command[4] & !SRAM_ALTER;
+                }
+                if command[7] & SRAM_ALTER != 0 {
+                    self.gp_settings.copy_from_slice(&command[8..12]);
+                }
+            }
+            CMD_SET_GPIO_VALUES => {
+                for pin in 0..GP_COUNT {
+                    if command[2 + 4 * pin] == 0x01 {
+                        self.levels[pin] = command[3 + 4 * pin] == 0x01;
+                    }
+                }
+            }
+            CMD_GET_GPIO_VALUES => {
+                for pin in 0..GP_COUNT {
+                    response[2 + 2 * pin] = self.levels[pin] as u8;
+                }
+            }
+            CMD_RESET_CHIP => {
+                self.resets += 1;
+                self.wedged = false;
+                self.stuck = self.jammed;
+                self.nack = false;
+                self.write_buf.clear();
+                self.divider = I2cSpeed::Standard.divider();
+                self.gp_settings = [0u8; GP_COUNT];
+            }
+            _ => {}
+        }
+
+        Ok(response)
+    }
+}
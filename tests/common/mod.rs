@@ -1,3 +1,9 @@
+// Not every test crate uses every helper
+#![allow(dead_code)]
+
+pub mod fake_hid;
+
+use hydro_sense::units::{Temperature, Voltage};
 use std::sync::Once;
 
 static INIT: Once = Once::new();
@@ -7,3 +13,22 @@ pub fn init_logger() {
         env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     });
 }
+
+/// Fail unless `got` is within `tolerance` of `expected`
+pub fn assert_close(got: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (got - expected).abs() <= tolerance,
+        "got {}, expected {} ± {}",
+        got,
+        expected,
+        tolerance
+    );
+}
+
+pub fn volts(volts: f32) -> Voltage {
+    Voltage::from_volts(volts)
+}
+
+pub fn celsius(celsius: f32) -> Temperature {
+    Temperature::from_celsius(celsius)
+}
@@ -0,0 +1,374 @@
+mod common;
+
+use hydro_sense::calibration::{CalibrationRecord, CalibrationStore};
+use hydro_sense::config::{
+    AlarmConfig, Config, ConfigError, DeviceKind, ModelConfig, DEFAULT_CONFIG,
+};
+use hydro_sense::i2c::lock::{BusLock, LockedI2c};
+use hydro_sense::ph::{CalibrationPoint, PhCalibration, BUFFER_PH4, BUFFER_PH7};
+use hydro_sense::sensor::Quantity;
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::{Temperature, Voltage};
+use std::{cell::RefCell, io, rc::Rc, time::Duration};
+
+/// Recognises the error a broken config should produce
+type ErrorCheck = fn(&ConfigError) -> bool;
+
+/// Simulated bus behind a lock file of its own
+fn locked_bus(ads: &Rc<RefCell<SimAds1115>>) -> io::Result<LockedI2c<SimBus>> {
+    let path = std::env::temp_dir().join(format!("hydro-sense-config-{}.lock", std::process::id()));
+    let lock = BusLock::open(path)?;
+    Ok(LockedI2c::new(SimBus::new().with(ads), lock))
+}
+
+const MINIMAL: &str = r#"
+[[adapter]]
+name = "usb"
+sysfs_name = "MCP2221"
+
+[[device]]
+name = "adc"
+type = "ads1115"
+adapter = "usb"
+address = 0x49
+"#;
+
+/// `MINIMAL` plus one sensor on AIN`channel`
+fn with_sensor(channel: u8, model: &str, extra: &str) -> String {
+    format!(
+        "{}\n[[sensor]]\nname = \"probe\"\ndevice = \"adc\"\nchannel = {}\nmodel = {}\n{}",
+        MINIMAL, channel, model, extra
+    )
+}
+
+#[test]
+fn test_default_config() {
+    let config = Config::default();
+    assert_eq!(config, Config::parse(DEFAULT_CONFIG).unwrap());
+
+    let button = config.first_device(DeviceKind::Df0991).unwrap();
+    assert_eq!(button.address, 0x2A);
+    assert_eq!(config.device_adapter(button).unwrap().sysfs_name, "MCP2221");
+
+    let names: Vec<&str> = config.sensors.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Water Temp", "pH", "Air Temp", "EC"]);
+
+    let ec = config.sensor("EC").unwrap();
+    assert_eq!(config.poll_interval(ec), Duration::from_millis(5000));
+    assert_eq!(
+        config.poll_interval(config.sensor("pH").unwrap()),
+        Duration::from_millis(1000)
+    );
+    assert_eq!(ec.calibration.as_deref(), Some("ec"));
+    assert_eq!(ec.compensate_with.as_deref(), Some("Water Temp"));
+}
+
+#[test]
+fn test_model_parameters() {
+    let text = with_sensor(
+        2,
+        r#"{ type = "ntc", supply = 3.3, pga = 4.096, beta = 3435.0, topology = "low_side" }"#,
+        "",
+    );
+    let config = Config::parse(&text).unwrap();
+    match &config.sensors[0].model {
+        ModelConfig::Ntc {
+            supply,
+            pga,
+            beta,
+            topology,
+            r0,
+            ..
+        } => {
+            assert_eq!((*supply, *pga, *beta), (3.3, 4.096, Some(3435.0)));
+            assert_eq!(topology.as_deref(), Some("low_side"));
+            assert_eq!(*r0, None);
+        }
+        model => panic!("expected an NTC model, got {:?}", model),
+    }
+
+    // The monitor section is optional
+    assert_eq!(
+        config.poll_interval(&config.sensors[0]),
+        Duration::from_millis(1000)
+    );
+}
+
+#[test]
+fn test_alarm() {
+    let alarm = AlarmConfig {
+        low: Some(5.5),
+        high: Some(6.5),
+    };
+    assert!(!alarm.is_triggered(6.0));
+    assert!(alarm.is_triggered(5.4));
+    assert!(alarm.is_triggered(6.6));
+
+    let high_only = AlarmConfig {
+        low: None,
+        high: Some(24.0),
+    };
+    assert!(!high_only.is_triggered(-10.0));
+    assert!(high_only.is_triggered(24.5));
+}
+
+#[test]
+fn test_invalid_configs() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Each broken config must be rejected with the matching error, │
+    // │ naming the offending entry.                                  │
+    // └──────────────────────────────────────────────────────────────┘
+    let ph = r#"{ type = "ph4502c" }"#;
+    let duplicate_device = format!(
+        "{}\n[[device]]\nname = \"adc\"\ntype = \"ads1115\"\nadapter = \"usb\"\naddress = 0x4A\n",
+        MINIMAL
+    );
+    let address_conflict = format!(
+        "{}\n[[device]]\nname = \"btn\"\ntype = \"df0991\"\nadapter = \"usb\"\naddress = 0x49\n",
+        MINIMAL
+    );
+    let unknown_adapter = MINIMAL.replace("adapter = \"usb\"", "adapter = \"ftdi\"");
+    let bad_address = MINIMAL.replace("0x49", "0x50");
+    let not_an_adc = format!(
+        "{}\n[[device]]\nname = \"oled\"\ntype = \"ssd1306\"\nadapter = \"usb\"\naddress = 0x3C\n\n\
+         [[sensor]]\nname = \"probe\"\ndevice = \"oled\"\nchannel = 0\nmodel = {}\n",
+        MINIMAL, ph
+    );
+
+    let cases: Vec<(String, ErrorCheck)> = vec![
+        (
+            duplicate_device,
+            |e| matches!(e, ConfigError::DuplicateName { section: "device", name } if name == "adc"),
+        ),
+        (address_conflict, |e| {
+            matches!(e, ConfigError::AddressConflict { address: 0x49, first, second, .. }
+                if first == "adc" && second == "btn")
+        }),
+        (
+            unknown_adapter,
+            |e| matches!(e, ConfigError::UnknownAdapter { adapter, .. } if adapter == "ftdi"),
+        ),
+        (bad_address, |e| {
+            matches!(e, ConfigError::InvalidAddress { address: 0x50, .. })
+        }),
+        (
+            not_an_adc,
+            |e| matches!(e, ConfigError::NotAnAdc { device, .. } if device == "oled"),
+        ),
+        (
+            with_sensor(0, ph, "")
+                .replace("device = \"adc\"\nchannel", "device = \"dac\"\nchannel"),
+            |e| matches!(e, ConfigError::UnknownDevice { device, .. } if device == "dac"),
+        ),
+        (with_sensor(4, ph, ""), |e| {
+            matches!(e, ConfigError::InvalidChannel { channel: 4, .. })
+        }),
+        (
+            with_sensor(0, r#"{ type = "voltage", pga = 5.0 }"#, ""),
+            |e| matches!(e, ConfigError::InvalidPga { .. }),
+        ),
+        (
+            with_sensor(
+                0,
+                r#"{ type = "ntc", supply = 5.0, pga = 6.144, topology = "middle" }"#,
+                "",
+            ),
+            |e| matches!(e, ConfigError::InvalidModel { .. }),
+        ),
+        (
+            with_sensor(0, r#"{ type = "ec", tds_scale = 640 }"#, ""),
+            |e| matches!(e, ConfigError::InvalidModel { .. }),
+        ),
+        (
+            with_sensor(0, r#"{ type = "ntc", supply = 0.0, pga = 6.144 }"#, ""),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("supply")),
+        ),
+        (
+            with_sensor(
+                0,
+                r#"{ type = "ntc", supply = 5.0, pga = 6.144, beta = nan }"#,
+                "",
+            ),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("beta")),
+        ),
+        (
+            with_sensor(
+                0,
+                r#"{ type = "ntc", supply = 5.0, pga = 6.144, r_fixed = -10000.0 }"#,
+                "",
+            ),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("r_fixed")),
+        ),
+        (
+            with_sensor(
+                0,
+                r#"{ type = "ntc", supply = 5.0, pga = 6.144, r0 = 0.0 }"#,
+                "",
+            ),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("r0")),
+        ),
+        (
+            with_sensor(
+                0,
+                r#"{ type = "ntc", supply = 5.0, pga = 6.144, table = "/nonexistent/rt.csv" }"#,
+                "",
+            ),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("table")),
+        ),
+        (
+            with_sensor(0, r#"{ type = "ec", cell_constant = 0.0 }"#, ""),
+            |e| {
+                matches!(e, ConfigError::InvalidModel { reason, .. }
+                    if reason.contains("cell_constant"))
+            },
+        ),
+        (
+            with_sensor(0, r#"{ type = "lm35", gain = 0.0 }"#, ""),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("gain")),
+        ),
+        (
+            with_sensor(0, r#"{ type = "ph4502c", gain = -3.0 }"#, ""),
+            |e| matches!(e, ConfigError::InvalidModel { reason, .. } if reason.contains("gain")),
+        ),
+        (
+            with_sensor(0, ph, "alarm = { low = 7.0, high = 6.0 }"),
+            |e| matches!(e, ConfigError::InvalidAlarm { sensor } if sensor == "probe"),
+        ),
+        (
+            with_sensor(0, ph, "compensate_with = \"Water Temp\""),
+            |e| {
+                matches!(e, ConfigError::NotATemperatureSensor { compensate_with, .. }
+                    if compensate_with == "Water Temp")
+            },
+        ),
+        (with_sensor(0, ph, "compensate_with = \"probe\""), |e| {
+            matches!(e, ConfigError::NotATemperatureSensor { compensate_with, .. }
+                    if compensate_with == "probe")
+        }),
+        (
+            with_sensor(0, r#"{ type = "lm35" }"#, "compensate_with = \"probe\""),
+            |e| matches!(e, ConfigError::InvalidModel { .. }),
+        ),
+        (
+            with_sensor(0, ph, "poll_interval_ms = 0"),
+            |e| matches!(e, ConfigError::InvalidPollInterval { name } if name == "probe"),
+        ),
+        (with_sensor(0, r#"{ type = "orp" }"#, ""), |e| {
+            matches!(e, ConfigError::Parse(_))
+        }),
+        (with_sensor(0, ph, "colour = \"red\""), |e| {
+            matches!(e, ConfigError::Parse(_))
+        }),
+    ];
+
+    for (text, expected) in cases {
+        let error = Config::parse(&text).expect_err(&text);
+        log::info!("{}", error);
+        assert!(expected(&error), "unexpected error: {:?}", error);
+    }
+
+    assert!(matches!(
+        Config::load("/nonexistent/hydro-sense.toml"),
+        Err(ConfigError::Io(_))
+    ));
+}
+
+#[test]
+fn test_build_sensors() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Build the stock setup on a simulated ADS1115 and check that  │
+    // │ every sensor reads its own channel under its configured      │
+    // │ name.                                                        │
+    // └──────────────────────────────────────────────────────────────┘
+    let ads = Rc::new(RefCell::new(SimAds1115::new(0x48)));
+    {
+        let mut ads = ads.borrow_mut();
+        ads.set_voltage(0, 2.5); // 25°C on the 10k divider
+        ads.set_voltage(1, 2.5); // pH 7
+        ads.set_voltage(2, 0.215); // 21.5°C
+        ads.set_voltage(3, 0.246); // 1.5 mS/cm
+    }
+
+    let config = Config::default();
+    let mut opened = Vec::new();
+    let mut sensors = config.build_sensors(|adapter| {
+        opened.push(adapter.name.clone());
+        locked_bus(&ads)
+    })?;
+    assert_eq!(opened, ["mcp2221"; 4]);
+
+    let mut readings = Vec::new();
+    for sensor in sensors.iter_mut() {
+        let measurement = sensor.measure()?;
+        log::info!("{}", measurement);
+        readings.push((
+            sensor.id().to_string(),
+            sensor.quantity(),
+            measurement.value,
+        ));
+    }
+    assert_eq!(readings[0].0, "Water Temp");
+    assert_eq!(readings[3].1, Quantity::Conductivity);
+    common::assert_close(readings[0].2, 25.0, 0.01);
+    common::assert_close(readings[1].2, 7.0, 0.002);
+    common::assert_close(readings[2].2, 21.5, 0.01);
+    common::assert_close(readings[3].2, 1.5, 0.001);
+
+    // A table that cannot be read fails the build, not the first reading,
+    // even if it went missing after the config was loaded
+    let text = with_sensor(0, r#"{ type = "ntc", supply = 5.0, pga = 6.144 }"#, "");
+    let mut config = Config::parse(&text)?;
+    if let ModelConfig::Ntc { table, .. } = &mut config.sensors[0].model {
+        *table = Some("/nonexistent/ntc.csv".into());
+    }
+    let error = config
+        .build_sensors(|_| locked_bus(&ads))
+        .err()
+        .expect("missing table");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    Ok(())
+}
+
+#[test]
+fn test_build_calibrated_sensor() -> anyhow::Result<()> {
+    // pH 7 buffer read at 2.52 V instead of the nominal 2.5 V
+    let points = [
+        CalibrationPoint {
+            ph: BUFFER_PH7,
+            voltage: Voltage::from_volts(2.52),
+        },
+        CalibrationPoint {
+            ph: BUFFER_PH4,
+            voltage: Voltage::from_volts(3.02),
+        },
+    ];
+    let calibration =
+        PhCalibration::two_point(points[0], points[1], Temperature::from_celsius(25.0))?;
+    let path = std::env::temp_dir().join(format!(
+        "hydro-sense-config-store-{}.json",
+        std::process::id()
+    ));
+    let mut store = CalibrationStore::open(&path)?;
+    store.record("ph", CalibrationRecord::ph(&calibration, &points));
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(0x48)));
+    ads.borrow_mut().set_voltage(1, 2.52);
+    let config = Config::default();
+    let ph = config.sensor("pH").unwrap();
+
+    let mut calibrated = config.build_calibrated_sensor(ph, &store, |_| locked_bus(&ads))?;
+    common::assert_close(calibrated.measure()?.value, 7.0, 0.002);
+
+    // Without a stored calibration the driver defaults are used
+    let mut nominal = config.build_sensor(ph, |_| locked_bus(&ads))?;
+    assert!(nominal.measure()?.value < 6.9);
+    let ec = config.sensor("EC").unwrap();
+    let mut uncalibrated = config.build_calibrated_sensor(ec, &store, |_| locked_bus(&ads))?;
+    assert!(uncalibrated.measure().is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use hydro_sense::ds18b20::{find_probes_in, parse_w1_slave, Ds18b20, Ds18b20Error};
+use std::{fs, io, path::PathBuf};
+
+const GOOD_READING: &str = "72 01 4b 46 7f ff 0e 10 57 : crc=57 YES\n\
+                            72 01 4b 46 7f ff 0e 10 57 t=23125\n";
+
+/// Fake `/sys/bus/w1/devices` with a probe directory per (id, w1_slave)
+fn fake_w1_tree(name: &str, devices: &[(&str, Option<&str>)]) -> io::Result<PathBuf> {
+    let root = std::env::temp_dir().join(format!("hydro-sense-w1-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (id, w1_slave) in devices {
+        fs::create_dir_all(root.join(id))?;
+        if let Some(contents) = w1_slave {
+            fs::write(root.join(id).join("w1_slave"), contents)?;
+        }
+    }
+    Ok(root)
+}
+
+#[test]
+fn test_parse_w1_slave() {
+    let temperature = parse_w1_slave(GOOD_READING).unwrap();
+    assert!((temperature.celsius() - 23.125).abs() < 1e-6);
+
+    // Below zero the driver prints a negative millidegree value
+    let frozen = "5f ff 4b 46 7f ff 01 10 e4 : crc=e4 YES\n\
+                  5f ff 4b 46 7f ff 01 10 e4 t=-10062\n";
+    let temperature = parse_w1_slave(frozen).unwrap();
+    assert!((temperature.celsius() + 10.062).abs() < 1e-6);
+}
+
+#[test]
+fn test_parse_w1_slave_faults() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ A CRC mismatch still carries a t= value, which must not be   │
+    // │ trusted. 85°C is the power-on scratchpad of a probe that     │
+    // │ lost power before converting, unless byte 6 shows a real     │
+    // │ conversion.                                                  │
+    // └──────────────────────────────────────────────────────────────┘
+    let bad_crc = "72 01 4b 46 7f ff 0e 10 ff : crc=57 NO\n\
+                   72 01 4b 46 7f ff 0e 10 ff t=23125\n";
+    assert!(matches!(
+        parse_w1_slave(bad_crc),
+        Err(Ds18b20Error::Crc { line }) if line.ends_with("crc=57 NO")
+    ));
+
+    let power_on = "50 05 4b 46 7f ff 0c 10 1c : crc=1c YES\n\
+                    50 05 4b 46 7f ff 0c 10 1c t=85000\n";
+    assert!(matches!(
+        parse_w1_slave(power_on),
+        Err(Ds18b20Error::PowerOnReset)
+    ));
+    let hot = "50 05 4b 46 7f ff 10 10 21 : crc=21 YES\n\
+               50 05 4b 46 7f ff 10 10 21 t=85000\n";
+    assert_eq!(parse_w1_slave(hot).unwrap().celsius(), 85.0);
+
+    assert!(matches!(
+        parse_w1_slave("00 00 00 00 00 00 00 00 00 : crc=00 YES\n"),
+        Err(Ds18b20Error::Malformed { .. })
+    ));
+    assert!(matches!(
+        parse_w1_slave("crc=57 YES\nt=oops\n"),
+        Err(Ds18b20Error::Malformed { .. })
+    ));
+}
+
+#[test]
+fn test_find_probes_in_fake_sysfs() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let root = fake_w1_tree(
+        "scan",
+        &[
+            ("28-0316a2795bff", Some(GOOD_READING)),
+            ("28-01144e8a35aa", Some("garbage")),
+            ("10-000802c5e2f1", Some(GOOD_READING)), // DS18S20, other family
+            ("w1_bus_master1", None),
+        ],
+    )?;
+
+    let probes = find_probes_in(&root)?;
+    let ids: Vec<&str> = probes.iter().map(Ds18b20::id).collect();
+    assert_eq!(ids, ["28-01144e8a35aa", "28-0316a2795bff"]);
+    assert_eq!(probes[1].serial(), "0316a2795bff");
+
+    for probe in &probes {
+        match probe.read_temperature() {
+            Ok(temperature) => log::info!("{}: {}", probe.id(), temperature),
+            Err(e) => log::warn!("{}: {}", probe.id(), e),
+        }
+    }
+    assert!(probes[1].read_temperature().is_ok());
+    assert!(probes[0].read_temperature().is_err());
+
+    // An unplugged probe loses its directory
+    let gone = Ds18b20::with_root("28-000000000000", &root);
+    assert!(matches!(gone.read_temperature(), Err(Ds18b20Error::Io(_))));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_no_w1_driver() -> io::Result<()> {
+    let missing = std::env::temp_dir().join("hydro-sense-w1-not-loaded");
+    assert!(find_probes_in(missing)?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::{celsius, volts};
+use hydro_sense::ads1115::{Mux, ADS1115_ADDR_A};
+use hydro_sense::ec::*;
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::{Conductivity, Voltage};
+use std::{cell::RefCell, rc::Rc};
+
+fn assert_ec(got: Conductivity, expected: f32, tolerance: f32) {
+    common::assert_close(got.ms_per_cm(), expected, tolerance);
+}
+
+fn ms_per_cm(ms_per_cm: f32) -> Conductivity {
+    Conductivity::from_ms_per_cm(ms_per_cm)
+}
+
+/// Board voltage for a K=`k` probe in a solution of `ec25` mS/cm at `temperature`
+fn board_voltage(k: f32, ec25: f32, temperature: f32) -> Voltage {
+    let ec = ec25 * (1.0 + DEFAULT_TEMP_COEFFICIENT * (temperature - 25.0));
+    volts(ec / k / (1000.0 / 164.0))
+}
+
+#[test]
+fn test_cell_constant() {
+    let meter = EcMeter::default();
+    assert_ec(meter.voltage_to_ec(volts(0.164), celsius(25.0)), 1.0, 1e-5);
+
+    // A K=10 cell sees a tenth of the conductance for the same solution
+    let k10 = EcMeter::default().with_cell_constant(10.0);
+    let voltage = board_voltage(10.0, 12.88, 25.0);
+    assert_ec(k10.voltage_to_ec(voltage, celsius(25.0)), 12.88, 1e-4);
+}
+
+#[test]
+fn test_temperature_compensation() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ 1.413 mS/cm standard at 20°C conducts 10% less than at 25°C  │
+    // │ with a 2%/°C coefficient. Compensation reports 1.413 again.  │
+    // └──────────────────────────────────────────────────────────────┘
+    let meter = EcMeter::default();
+    let voltage = board_voltage(1.0, 1.413, 20.0);
+    assert_ec(meter.voltage_to_raw_ec(voltage), 1.2717, 1e-4);
+    assert_ec(meter.voltage_to_ec(voltage, celsius(20.0)), 1.413, 1e-5);
+
+    // A solution-specific coefficient
+    let meter = meter.with_temp_coefficient(0.0185);
+    let ec20 = 1.413 * (1.0 - 5.0 * 0.0185);
+    assert_ec(
+        meter.voltage_to_ec(volts(ec20 * 164.0 / 1000.0), celsius(20.0)),
+        1.413,
+        1e-5,
+    );
+}
+
+#[test]
+fn test_single_point_calibration() -> Result<(), EcError> {
+    let mut meter = EcMeter::default();
+    let k = meter.calibrate(
+        EC_STANDARD_LOW,
+        board_voltage(0.95, 1.413, 22.0),
+        celsius(22.0),
+    )?;
+    common::assert_close(k, 0.95, 1e-4);
+
+    let voltage = board_voltage(0.95, 2.0, 28.0);
+    assert_ec(meter.voltage_to_ec(voltage, celsius(28.0)), 2.0, 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn test_two_point_calibration() -> Result<(), EcError> {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ The board's line misses zero by 0.05 mS/cm. The two          │
+    // │ standards recover both the cell constant and the offset.     │
+    // └──────────────────────────────────────────────────────────────┘
+    let reading = |ec25: f32| board_voltage(1.05, ec25 - 0.05, 25.0);
+    let mut meter = EcMeter::default();
+    let k = meter.calibrate_two_point(
+        (EC_STANDARD_LOW, reading(1.413)),
+        (EC_STANDARD_HIGH, reading(12.88)),
+        celsius(25.0),
+    )?;
+    common::assert_close(k, 1.05, 1e-4);
+    common::assert_close(meter.zero_offset, 0.05, 1e-4);
+    assert_ec(meter.voltage_to_ec(reading(5.0), celsius(25.0)), 5.0, 1e-3);
+
+    Ok(())
+}
+
+#[test]
+fn test_calibration_errors() {
+    let mut meter = EcMeter::default();
+    assert_eq!(
+        meter.calibrate(EC_STANDARD_LOW, volts(0.0), celsius(25.0)),
+        Err(EcError::NoSignal { voltage: 0.0 })
+    );
+    assert_eq!(
+        meter.calibrate_two_point(
+            (EC_STANDARD_LOW, volts(0.2)),
+            (EC_STANDARD_HIGH, volts(0.2)),
+            celsius(25.0)
+        ),
+        Err(EcError::SameStandard)
+    );
+    assert!(matches!(
+        meter.calibrate_two_point(
+            (EC_STANDARD_LOW, volts(1.0)),
+            (EC_STANDARD_HIGH, volts(0.5)),
+            celsius(25.0)
+        ),
+        Err(EcError::InvalidCellConstant { .. })
+    ));
+
+    // A failed calibration keeps the previous one
+    assert_eq!(meter.k, 1.0);
+}
+
+#[test]
+fn test_tds_scales() {
+    let meter = EcMeter::default();
+    common::assert_close(meter.ec_to_tds(ms_per_cm(1.413)), 706.5, 1e-3);
+    let meter = meter.with_tds_scale(TdsScale::Ppm700);
+    common::assert_close(meter.ec_to_tds(ms_per_cm(1.413)), 989.1, 1e-3);
+}
+
+#[test]
+fn test_sensor_reads_ec_and_tds() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut()
+        .set_voltage(3, board_voltage(1.0, 1.8, 23.0).volts());
+    let bus = SimBus::new().with(&ads);
+
+    let mut sensor = EcSensor::new(bus, ADS1115_ADDR_A, Mux::Ain3Gnd, EcMeter::default())?;
+    let ec = sensor.get_ec(celsius(23.0))?;
+    let tds = sensor.get_tds(celsius(23.0))?;
+    log::info!("Simulated EC: {}, TDS: {} ppm", ec, tds);
+
+    assert_ec(ec, 1.8, 0.001);
+    common::assert_close(tds, 900.0, 0.5);
+
+    Ok(())
+}
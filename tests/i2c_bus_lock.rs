@@ -0,0 +1,159 @@
+mod common;
+
+use embedded_hal::i2c::I2c;
+use hydro_sense::ads1115::{AdsSensor, Mux, Pga, ADS1115_ADDR_A};
+use hydro_sense::i2c::lock::{BusLock, LockError, LockedI2c};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::Unit;
+use std::{
+    cell::RefCell,
+    io,
+    path::PathBuf,
+    rc::Rc,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+fn lock_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("hydro-sense-lock-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn test_lock_excludes_other_holders() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // Separate opens conflict like separate processes do
+    let path = lock_path("exclusive");
+    let ours = BusLock::open(&path)?;
+    let theirs = BusLock::open(&path)?.with_timeout(Some(Duration::from_millis(50)));
+
+    let guard = ours.hold()?;
+    assert!(ours.is_held());
+
+    let err = theirs.hold().err().expect("lock should be busy");
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+    // Nested holds through a clone do not block and keep the lock
+    let nested = ours.clone().hold()?;
+    drop(guard);
+    assert!(theirs.hold().is_err());
+    drop(nested);
+    assert!(!ours.is_held());
+
+    let _guard = theirs.hold()?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_lock_excludes_other_threads() -> anyhow::Result<()> {
+    let path = lock_path("threads");
+    let lock = BusLock::open(&path)?.with_timeout(Some(Duration::from_millis(50)));
+    let guard = lock.hold()?;
+
+    // A clone on another thread does not join the hold
+    let other = lock.clone();
+    let busy = thread::spawn(move || other.hold().map(drop))
+        .join()
+        .unwrap();
+    assert_eq!(busy.unwrap_err().kind(), io::ErrorKind::TimedOut);
+
+    // It gets the lock once this thread lets go
+    let other = lock.clone().with_timeout(None);
+    let (held_tx, held_rx) = mpsc::channel();
+    let waiter = thread::spawn(move || -> io::Result<()> {
+        let _guard = other.hold()?;
+        held_tx.send(()).unwrap();
+        Ok(())
+    });
+    assert!(held_rx.recv_timeout(Duration::from_millis(50)).is_err());
+    drop(guard);
+    held_rx.recv_timeout(Duration::from_secs(1))?;
+    waiter.join().unwrap()?;
+    assert!(!lock.is_held());
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_locked_bus_reports_busy_lock() -> anyhow::Result<()> {
+    let path = lock_path("busy");
+    let other = BusLock::open(&path)?;
+    let lock = BusLock::open(&path)?.with_timeout(Some(Duration::from_millis(20)));
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    let mut bus = LockedI2c::new(SimBus::new().with(&ads), lock);
+
+    let mut buf = [0u8; 2];
+    bus.write_read(ADS1115_ADDR_A, &[0x01], &mut buf)?;
+    assert!(!bus.lock().is_held(), "lock kept after the transaction");
+
+    let _guard = other.hold()?;
+    match bus.write_read(ADS1115_ADDR_A, &[0x01], &mut buf) {
+        Err(LockError::Lock(e)) => assert_eq!(e.kind(), io::ErrorKind::TimedOut),
+        other => panic!("expected lock error, got {:?}", other),
+    }
+
+    // A conversion reports the busy lock instead of running unlocked
+    let mut sensor = AdsSensor::new(
+        bus,
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain4_096V,
+        "AIN0",
+        Unit::Volts,
+    )?
+    .with_bus_lock();
+    assert!(matches!(sensor.get_voltage(), Err(LockError::Lock(_))));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_conversion_waits_for_other_process() -> anyhow::Result<()> {
+    let path = lock_path("conversion");
+    let lock = BusLock::open(&path)?;
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Another "process" holds the bus for 150 ms. The conversion   │
+    // │ must wait for it instead of interleaving, then hold the lock │
+    // │ itself until the result has been read.                       │
+    // └──────────────────────────────────────────────────────────────┘
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let other_path = path.clone();
+    let other = thread::spawn(move || -> io::Result<()> {
+        let lock = BusLock::open(other_path)?;
+        let _guard = lock.hold()?;
+        locked_tx.send(()).unwrap();
+        thread::sleep(Duration::from_millis(150));
+        Ok(())
+    });
+    locked_rx.recv()?;
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut().set_voltage(0, 1.25);
+    let bus = LockedI2c::new(SimBus::new().with(&ads), lock.clone());
+    let mut sensor = AdsSensor::new(
+        bus,
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain4_096V,
+        "AIN0",
+        Unit::Volts,
+    )?
+    .with_bus_lock();
+
+    let start = Instant::now();
+    let voltage = sensor.get_voltage()?;
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!((voltage - 1.25).abs() < 0.001, "got {}", voltage);
+    assert!(!lock.is_held());
+
+    other.join().unwrap()?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
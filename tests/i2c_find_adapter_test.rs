@@ -3,7 +3,7 @@ mod common;
 use std::io::Result;
 
 // Import the function from your library crate; adjust the path as needed.
-use hydro_sense::i2c::find_adapter;
+use hydro_sense::i2c::{find_adapter, find_adapter_in};
 
 #[test]
 fn test_find_mcp2221_adapter() -> Result<()> {
@@ -31,3 +31,22 @@ fn test_find_mcp2221_adapter() -> Result<()> {
     log::info!("{}", path);
     Ok(())
 }
+
+#[test]
+fn test_find_adapter_in_fake_sysfs() -> Result<()> {
+    // Fake sysfs tree with two adapters
+    let root = std::env::temp_dir().join(format!("hydro-sense-i2c-{}", std::process::id()));
+    for (dir, name) in [
+        ("i2c-0", "i915 gmbus dpb"),
+        ("i2c-7", "MCP2221 usb-i2c bridge"),
+    ] {
+        std::fs::create_dir_all(root.join(dir))?;
+        std::fs::write(root.join(dir).join("name"), format!("{}\n", name))?;
+    }
+
+    assert_eq!(find_adapter_in(&root, "MCP2221")?, "/dev/i2c-7");
+    assert!(find_adapter_in(&root, "CH341").is_err());
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -6,8 +6,13 @@ mod common;
 
 use hydro_sense::ads1115::{AdsSensor, Mux, Pga};
 use hydro_sense::i2c::find_adapter;
+use hydro_sense::i2c::record::{RecordingI2c, ReplayI2c};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
 use hydro_sense::temperature::voltage_to_temperature;
+use hydro_sense::units::{Unit, Voltage};
 use linux_embedded_hal::I2cdev;
+use std::{cell::RefCell, rc::Rc};
 
 #[test]
 fn test_read_ntc_voltage() {
@@ -35,7 +40,7 @@ fn test_read_ntc_voltage() {
         Mux::Ain0Gnd,
         Pga::Gain6_144V,
         "10k NTC Thermistor",
-        "Volts",
+        Unit::Volts,
     )
     .expect("Could not define sensor");
 
@@ -68,6 +73,74 @@ fn test_read_ntc_voltage() {
     // │ The voltage reading is converted to resistance first, then   │
     // │ temperature in Celsius.                                      │
     // └──────────────────────────────────────────────────────────────┘
-    let temperature = voltage_to_temperature(5.0, 6.144, voltage);
+    let temperature = voltage_to_temperature(
+        Voltage::from_volts(5.0),
+        Voltage::from_volts(6.144),
+        Voltage::from_volts(voltage),
+    );
     log::info!("NTC thermistor temperature: {}", temperature)
 }
+
+#[test]
+fn test_read_ntc_voltage_replay() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │                 Record Synthetic I2C Traffic                 │
+    // │                                                              │
+    // │ There is no hardware capture of this reading, so record one  │
+    // │ from the simulated ADS1115 with 2.8125V on AIN0. The result  │
+    // │ is synthetic: it shows the replay path, not real traffic.    │
+    // └──────────────────────────────────────────────────────────────┘
+    let ads = Rc::new(RefCell::new(SimAds1115::new(0x48)));
+    ads.borrow_mut().set_voltage(0, 2.8125);
+    let recorder = RecordingI2c::new(SimBus::new().with(&ads), Vec::new())?;
+
+    let mut ntc_sensor = AdsSensor::new(
+        recorder,
+        0x48,
+        Mux::Ain0Gnd,
+        Pga::Gain6_144V,
+        "10k NTC Thermistor",
+        Unit::Volts,
+    )?;
+    ntc_sensor.get_voltage()?;
+    let (_, recording) = ntc_sensor.release().release();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │                  Replay Recorded I2C Traffic                 │
+    // │                                                              │
+    // │ Serve the ADS1115 traffic of one NTC reading from the        │
+    // │ recording, so this runs without the MCP2221 and ADS1115.     │
+    // └──────────────────────────────────────────────────────────────┘
+    let i2c = ReplayI2c::from_reader(recording.as_slice())?;
+
+    let mut ntc_sensor = AdsSensor::new(
+        i2c,
+        0x48,
+        Mux::Ain0Gnd,
+        Pga::Gain6_144V,
+        "10k NTC Thermistor",
+        Unit::Volts,
+    )?;
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │             Read Voltage and Convert to Temperature          │
+    // │                                                              │
+    // │ Raw 0x3A98 = 15000 counts at 6.144V full scale gives         │
+    // │ 2.8125V, i.e. 12.86kΩ on the 10k divider, about 19.4°C.      │
+    // └──────────────────────────────────────────────────────────────┘
+    let voltage: f32 = ntc_sensor.get_voltage()?;
+    assert!((voltage - 2.8125).abs() < 1e-4);
+
+    let temperature = voltage_to_temperature(
+        Voltage::from_volts(5.0),
+        Voltage::from_volts(6.144),
+        Voltage::from_volts(voltage),
+    );
+    log::info!("Replayed NTC thermistor temperature: {}", temperature);
+    assert!((temperature.celsius() - 19.45).abs() < 0.1);
+
+    assert!(ntc_sensor.release().is_done());
+    Ok(())
+}
@@ -6,6 +6,8 @@ mod common;
 
 use hydro_sense::ads1115::{AdsSensor, Mux, Pga};
 use hydro_sense::i2c::find_adapter;
+use hydro_sense::lm35::Lm35;
+use hydro_sense::units::{Unit, Voltage};
 use linux_embedded_hal::I2cdev;
 
 #[test]
@@ -38,7 +40,7 @@ fn test_read_lm35_voltage() {
         Mux::Ain0Gnd,
         Pga::Gain0_512V,
         "LM35DZ Temp",
-        "Celsius",
+        Unit::Celsius,
     )
     .expect("Could not define sensor");
 
@@ -54,4 +56,8 @@ fn test_read_lm35_voltage() {
         .expect("Failed to get LM35DZ voltage");
 
     log::info!("LM35DZ voltage: {}", voltage);
+    log::info!(
+        "LM35DZ temperature: {}",
+        Lm35::default().voltage_to_temperature(Voltage::from_volts(voltage))
+    );
 }
@@ -0,0 +1,205 @@
+mod common;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use hydro_sense::i2c::{AdapterSource, ReconnectError, ReconnectingI2c};
+use std::{cell::RefCell, io, rc::Rc, time::Duration};
+
+// ┌──────────────────────────────────────────────────────────────┐
+// │                      Fake USB Adapter                        │
+// │                                                              │
+// │ `plugged` is the /dev path of the adapter, or None while it  │
+// │ is unplugged; `plugs` counts how often it was plugged in and │
+// │ serves as its identity. Each open bus remembers the path and │
+// │ plug it was opened at and, once the adapter is no longer     │
+// │ there (even if a replug reused the path), fails with the     │
+// │ kind linux-embedded-hal gives ENODEV. Targets other than     │
+// │ `TARGET` do not acknowledge their address. After an unplug,  │
+// │ `replug_after` lookups pass before it comes back.            │
+// └──────────────────────────────────────────────────────────────┘
+#[derive(Default)]
+struct Adapter {
+    plugged: Option<String>,
+    replug_at: Option<String>,
+    replug_after: usize,
+    plugs: usize,
+    opens: usize,
+    writes: Vec<u8>,
+}
+
+struct FakeSource(Rc<RefCell<Adapter>>);
+
+/// The only device on the fake bus
+const TARGET: u8 = 0x2A;
+
+struct FakeBus {
+    adapter: Rc<RefCell<Adapter>>,
+    location: String,
+    plug: usize,
+}
+
+impl AdapterSource for FakeSource {
+    type Bus = FakeBus;
+
+    fn locate(&mut self) -> io::Result<String> {
+        let mut adapter = self.0.borrow_mut();
+        if adapter.plugged.is_none() && adapter.replug_at.is_some() {
+            if adapter.replug_after == 0 {
+                adapter.plugged = adapter.replug_at.take();
+                adapter.plugs += 1;
+            } else {
+                adapter.replug_after -= 1;
+            }
+        }
+        adapter
+            .plugged
+            .clone()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unplugged"))
+    }
+
+    fn identify(&mut self, location: &str) -> io::Result<u64> {
+        let adapter = self.0.borrow();
+        match adapter.plugged.as_deref() {
+            Some(plugged) if plugged == location => Ok(adapter.plugs as u64),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, "unplugged")),
+        }
+    }
+
+    fn open(&mut self, location: &str) -> io::Result<FakeBus> {
+        let mut adapter = self.0.borrow_mut();
+        adapter.opens += 1;
+        Ok(FakeBus {
+            adapter: Rc::clone(&self.0),
+            location: location.to_string(),
+            plug: adapter.plugs,
+        })
+    }
+}
+
+impl ErrorType for FakeBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for FakeBus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        let mut adapter = self.adapter.borrow_mut();
+        let same_plug = adapter.plugs == self.plug;
+        if !same_plug || adapter.plugged.as_deref() != Some(self.location.as_str()) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+        }
+        if address != TARGET {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for op in operations {
+            if let Operation::Write(bytes) = op {
+                adapter.writes.extend_from_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn plugged_adapter() -> Rc<RefCell<Adapter>> {
+    Rc::new(RefCell::new(Adapter {
+        plugged: Some("/dev/i2c-6".to_string()),
+        ..Default::default()
+    }))
+}
+
+#[test]
+fn test_reconnect_after_replug() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let adapter = plugged_adapter();
+    let hook_runs = Rc::new(RefCell::new(0));
+    let runs = Rc::clone(&hook_runs);
+
+    let mut i2c = ReconnectingI2c::new(FakeSource(Rc::clone(&adapter)))?
+        .with_poll_interval(Duration::from_millis(1))
+        .on_reconnect(move |bus| {
+            *runs.borrow_mut() += 1;
+            bus.write(0x2A, &[0xAA])
+        });
+    i2c.write(0x2A, &[0x01])?;
+
+    // Unplug; the adapter comes back as /dev/i2c-7 a few polls later
+    {
+        let mut adapter = adapter.borrow_mut();
+        adapter.plugged = None;
+        adapter.replug_at = Some("/dev/i2c-7".to_string());
+        adapter.replug_after = 3;
+    }
+    i2c.write(0x2A, &[0x02])?;
+
+    assert_eq!(i2c.location(), "/dev/i2c-7");
+    assert_eq!(i2c.reconnects(), 1);
+    assert_eq!(*hook_runs.borrow(), 1);
+    assert_eq!(adapter.borrow().opens, 2);
+    assert_eq!(adapter.borrow().writes, vec![0x01, 0xAA, 0x02]);
+
+    Ok(())
+}
+
+#[test]
+fn test_reconnect_after_replug_at_same_location() -> anyhow::Result<()> {
+    let adapter = plugged_adapter();
+    let mut i2c = ReconnectingI2c::new(FakeSource(Rc::clone(&adapter)))?
+        .with_poll_interval(Duration::from_millis(1));
+    i2c.write(0x2A, &[0x01])?;
+
+    // Unplugged and back at /dev/i2c-6 before the next transaction
+    {
+        let mut adapter = adapter.borrow_mut();
+        adapter.plugged = None;
+        adapter.replug_at = Some("/dev/i2c-6".to_string());
+    }
+    i2c.write(0x2A, &[0x02])?;
+    i2c.write(0x2A, &[0x03])?;
+
+    assert_eq!(i2c.location(), "/dev/i2c-6");
+    assert_eq!(i2c.reconnects(), 1);
+    assert_eq!(adapter.borrow().opens, 2);
+    assert_eq!(adapter.borrow().writes, vec![0x01, 0x02, 0x03]);
+
+    Ok(())
+}
+
+#[test]
+fn test_gives_up_after_wait_timeout() -> anyhow::Result<()> {
+    let adapter = plugged_adapter();
+
+    let mut i2c = ReconnectingI2c::new(FakeSource(Rc::clone(&adapter)))?
+        .with_poll_interval(Duration::from_millis(1))
+        .with_wait_timeout(Duration::from_millis(20));
+
+    adapter.borrow_mut().plugged = None;
+    assert!(i2c.write(0x2A, &[0x01]).is_err());
+
+    // Later calls keep waiting for the adapter and work once it is back
+    adapter.borrow_mut().plugged = Some("/dev/i2c-8".to_string());
+    i2c.write(0x2A, &[0x02])?;
+    assert_eq!(i2c.reconnects(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_target_does_not_reopen() -> anyhow::Result<()> {
+    let adapter = plugged_adapter();
+    let mut i2c = ReconnectingI2c::new(FakeSource(Rc::clone(&adapter)))?;
+
+    // The adapter is still the one that was opened, so the NACK is returned
+    match i2c.write(0x48, &[0x01]) {
+        Err(ReconnectError::Bus(kind)) => {
+            assert_eq!(kind, ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+        }
+        other => panic!("expected the NACK, got {:?}", other),
+    }
+    assert_eq!(i2c.reconnects(), 0);
+    assert_eq!(adapter.borrow().opens, 1);
+
+    Ok(())
+}
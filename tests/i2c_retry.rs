@@ -0,0 +1,117 @@
+mod common;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use hydro_sense::df0991::{DFRobotRGBButton, RGBBUTTON_DEFAULT_I2C_ADDR};
+use hydro_sense::i2c::retry::{RetryI2c, RetryPolicy};
+use std::time::Duration;
+
+// Bus that fails the next `failures` transactions with `kind`
+struct FlakyBus {
+    failures: usize,
+    kind: ErrorKind,
+    calls: usize,
+}
+
+impl ErrorType for FlakyBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for FlakyBus {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        self.calls += 1;
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(self.kind);
+        }
+        for op in operations {
+            if let Operation::Read(buf) = op {
+                buf.fill(0x01);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fast_policy(attempts: u32) -> RetryPolicy {
+    RetryPolicy::new(attempts).with_backoff(Duration::from_micros(10), Duration::from_micros(50))
+}
+
+#[test]
+fn test_nack_is_retried() -> Result<(), ErrorKind> {
+    common::init_logger();
+
+    let bus = FlakyBus {
+        failures: 2,
+        kind: ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        calls: 0,
+    };
+    let i2c = RetryI2c::new(bus, fast_policy(3));
+    let stats = i2c.stats();
+
+    // The driver never sees the two dropped transactions
+    let mut button = DFRobotRGBButton::new(i2c, RGBBUTTON_DEFAULT_I2C_ADDR)?;
+    assert!(button.get_button_status()?);
+
+    let s = stats.get(RGBBUTTON_DEFAULT_I2C_ADDR);
+    assert_eq!(
+        (s.transactions, s.errors, s.retries, s.failures),
+        (1, 2, 2, 0)
+    );
+    assert_eq!(button.into_inner().release().calls, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_gives_up_after_last_attempt() {
+    let bus = FlakyBus {
+        failures: 10,
+        kind: ErrorKind::Bus,
+        calls: 0,
+    };
+    let mut i2c = RetryI2c::new(bus, fast_policy(4));
+
+    assert_eq!(i2c.write(0x48, &[0x00]), Err(ErrorKind::Bus));
+
+    let s = i2c.stats().get(0x48);
+    assert_eq!(
+        (s.transactions, s.errors, s.retries, s.failures),
+        (1, 4, 3, 1)
+    );
+    assert!(i2c.stats().to_csv().contains("0x48,1,4,3,1"));
+}
+
+#[test]
+fn test_non_retryable_errors_fail_at_once() {
+    let bus = FlakyBus {
+        failures: 1,
+        kind: ErrorKind::Other,
+        calls: 0,
+    };
+    let mut i2c = RetryI2c::new(bus, fast_policy(5));
+    assert!(i2c.write(0x48, &[0x00]).is_err());
+    assert_eq!(i2c.release().calls, 1);
+
+    // A custom rule can make any error retryable
+    let bus = FlakyBus {
+        failures: 1,
+        kind: ErrorKind::Other,
+        calls: 0,
+    };
+    let mut i2c = RetryI2c::new(bus, fast_policy(5).retry_if(|_| true));
+    assert!(i2c.write(0x48, &[0x00]).is_ok());
+}
+
+#[test]
+fn test_backoff_doubles_up_to_limit() {
+    let policy =
+        RetryPolicy::new(10).with_backoff(Duration::from_millis(5), Duration::from_millis(30));
+    assert_eq!(policy.delay(1), Duration::from_millis(5));
+    assert_eq!(policy.delay(2), Duration::from_millis(10));
+    assert_eq!(policy.delay(3), Duration::from_millis(20));
+    assert_eq!(policy.delay(4), Duration::from_millis(30));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use hydro_sense::ads1115::{AdsSensor, Mux, Pga, ADS1115_ADDR_A};
+use hydro_sense::df0991::{DFRobotRGBButton, RGBBUTTON_DEFAULT_I2C_ADDR};
+use hydro_sense::i2c::lock::{BusLock, LockedI2c};
+use hydro_sense::i2c::retry::{RetryI2c, RetryPolicy};
+use hydro_sense::i2c::shared::SharedI2c;
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::df0991::SimDf0991;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::Unit;
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn test_drivers_share_one_bus() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ The ADS1115 and the button sit on one adapter. Both drivers  │
+    // │ get a handle to the same retrying bus and lock, so their     │
+    // │ traffic shows up in one set of statistics.                   │
+    // └──────────────────────────────────────────────────────────────┘
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut().set_voltage(0, 1.25);
+    let button_sim = Rc::new(RefCell::new(SimDf0991::new(RGBBUTTON_DEFAULT_I2C_ADDR)));
+    let sim = SimBus::new().with(&ads).with(&button_sim);
+
+    let shared = SharedI2c::new(RetryI2c::new(sim, RetryPolicy::default()));
+    let path = std::env::temp_dir().join(format!("hydro-sense-shared-{}.lock", std::process::id()));
+    let lock = BusLock::open(path)?;
+
+    let locked = LockedI2c::new(shared.clone(), lock.clone());
+    let mut sensor = AdsSensor::new(
+        locked,
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain4_096V,
+        "probe",
+        Unit::Volts,
+    )?
+    .with_bus_lock();
+    let locked = LockedI2c::new(shared.clone(), lock);
+    let mut button = DFRobotRGBButton::new(locked, RGBBUTTON_DEFAULT_I2C_ADDR)?;
+    assert_eq!(shared.handles(), 3);
+
+    common::assert_close(sensor.get_voltage()?, 1.25, 0.001);
+    assert!(button.begin()?);
+    button_sim.borrow_mut().press();
+    assert!(button.get_button_status()?);
+    common::assert_close(sensor.get_voltage()?, 1.25, 0.001);
+
+    let stats = shared.with(|bus| bus.stats());
+    assert!(stats.get(ADS1115_ADDR_A).transactions > 0);
+    assert!(stats.get(RGBBUTTON_DEFAULT_I2C_ADDR).transactions > 0);
+
+    drop((sensor, button));
+    assert_eq!(shared.handles(), 1);
+    Ok(())
+}
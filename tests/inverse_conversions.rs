@@ -0,0 +1,139 @@
+use hydro_sense::ec::EcMeter;
+use hydro_sense::lm35::Lm35;
+use hydro_sense::ph::{CalibrationPoint, Ph4502c, PhCalibration};
+use hydro_sense::temperature::{
+    temperature_to_voltage, voltage_to_temperature, RtTable, SteinhartHart, ThermistorParams,
+    Topology,
+};
+use hydro_sense::units::{Conductivity, Ph, Temperature, Voltage};
+use proptest::prelude::*;
+
+const SUPPLY: Voltage = Voltage::from_volts(5.0);
+const FULL_SCALE: Voltage = Voltage::from_volts(6.144);
+
+fn ntc_10k_table() -> RtTable {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tables/ntc_10k.csv");
+    RtTable::load(path).expect("Failed to load R-T table")
+}
+
+fn steinhart_hart() -> SteinhartHart {
+    SteinhartHart {
+        a: 1.129148e-3,
+        b: 2.34125e-4,
+        c: 8.76741e-8,
+    }
+}
+
+#[test]
+fn test_known_inverse_values() {
+    // 25°C on the default 10k divider is half the supply
+    let room = Temperature::from_celsius(25.0);
+    assert!((temperature_to_voltage(SUPPLY, room).volts() - 2.5).abs() < 1e-4);
+
+    // Table rows come back exactly
+    let table = ntc_10k_table();
+    let r = table.resistance(Temperature::from_celsius(-10.0)).unwrap();
+    assert!((r - 55_303.0).abs() < 0.5, "got {}", r);
+    assert!(table.resistance(Temperature::from_celsius(80.0)).is_none());
+
+    // pH 4 sits three ideal module slopes (177.5 mV each) above 2.5V
+    let ph4 = Ph4502c::default().ph_to_voltage(Ph::new(4.0), room);
+    assert!((ph4.volts() - (2.5 + 3.0 * 3.0 * 0.05916)).abs() < 1e-4);
+}
+
+proptest! {
+    #[test]
+    fn prop_beta_round_trip(celsius in -30.0f32..110.0, low_side in any::<bool>()) {
+        let params = ThermistorParams {
+            topology: if low_side { Topology::LowSide } else { Topology::HighSide },
+            ..ThermistorParams::default()
+        };
+        let temperature = Temperature::from_celsius(celsius);
+        let voltage = params.temperature_to_voltage(SUPPLY, temperature);
+        let back = params.voltage_to_temperature(SUPPLY, FULL_SCALE, voltage);
+        prop_assert!((back.celsius() - celsius).abs() < 0.01, "{} -> {} -> {}", celsius, voltage, back);
+    }
+
+    #[test]
+    fn prop_default_probe_round_trip(celsius in -20.0f32..100.0) {
+        let voltage = temperature_to_voltage(SUPPLY, Temperature::from_celsius(celsius));
+        let back = voltage_to_temperature(SUPPLY, FULL_SCALE, voltage);
+        prop_assert!((back.celsius() - celsius).abs() < 0.01);
+    }
+
+    #[test]
+    fn prop_steinhart_hart_round_trip(celsius in -30.0f32..110.0) {
+        let sh = steinhart_hart();
+        let resistance = sh.resistance(Temperature::from_celsius(celsius));
+        prop_assert!((sh.temperature(resistance).celsius() - celsius).abs() < 0.01);
+
+        let params = ThermistorParams::default().with_steinhart_hart(sh);
+        let voltage = params.temperature_to_voltage(SUPPLY, Temperature::from_celsius(celsius));
+        let back = params.voltage_to_temperature(SUPPLY, FULL_SCALE, voltage);
+        prop_assert!((back.celsius() - celsius).abs() < 0.01);
+    }
+
+    #[test]
+    fn prop_table_round_trip(celsius in -10.0f32..=60.0) {
+        let params = ThermistorParams::default().with_table(ntc_10k_table());
+        let voltage = params.temperature_to_voltage(SUPPLY, Temperature::from_celsius(celsius));
+        let back = params.voltage_to_temperature(SUPPLY, FULL_SCALE, voltage);
+        prop_assert!((back.celsius() - celsius).abs() < 0.01);
+    }
+
+    #[test]
+    fn prop_lm35_round_trip(
+        celsius in -10.0f32..150.0,
+        gain in 0.9f32..1.1,
+        offset in -2.0f32..2.0,
+    ) {
+        let lm35 = Lm35::default()
+            .with_offset_circuit(1.2)
+            .with_calibration(gain, offset);
+        let voltage = lm35.temperature_to_voltage(Temperature::from_celsius(celsius));
+        let back = lm35.voltage_to_temperature(voltage);
+        prop_assert!((back.celsius() - celsius).abs() < 1e-3);
+    }
+
+    #[test]
+    fn prop_ph_round_trip(
+        ph in 0.0f32..14.0,
+        celsius in 5.0f32..40.0,
+        acid_efficiency in 85.0f32..105.0,
+        base_efficiency in 85.0f32..105.0,
+    ) {
+        let slope = |efficiency: f32| -0.05916 * 3.0 * efficiency / 100.0;
+        let point = |ph: f32, voltage: f32| CalibrationPoint {
+            ph: Ph::new(ph),
+            voltage: Voltage::from_volts(voltage),
+        };
+        let calibration = PhCalibration::three_point(
+            [
+                point(4.01, 2.5 - 2.99 * slope(acid_efficiency)),
+                point(7.0, 2.5),
+                point(10.01, 2.5 + 3.01 * slope(base_efficiency)),
+            ],
+            Temperature::from_celsius(25.0),
+        )
+        .unwrap();
+        let probe = Ph4502c::default().with_calibration(calibration);
+
+        let temperature = Temperature::from_celsius(celsius);
+        let voltage = probe.ph_to_voltage(Ph::new(ph), temperature);
+        let back = probe.voltage_to_ph(voltage, temperature);
+        prop_assert!((back.value() - ph).abs() < 1e-3, "{} -> {} -> {}", ph, voltage, back);
+    }
+
+    #[test]
+    fn prop_ec_round_trip(
+        ms_per_cm in 0.05f32..20.0,
+        celsius in 5.0f32..40.0,
+        k in 0.1f32..10.0,
+    ) {
+        let meter = EcMeter::default().with_cell_constant(k);
+        let temperature = Temperature::from_celsius(celsius);
+        let voltage = meter.ec_to_voltage(Conductivity::from_ms_per_cm(ms_per_cm), temperature);
+        let back = meter.voltage_to_ec(voltage, temperature);
+        prop_assert!((back.ms_per_cm() - ms_per_cm).abs() < ms_per_cm * 1e-5);
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{celsius, volts};
+use hydro_sense::ads1115::{pga_to_voltage, Mux, ADS1115_ADDR_A};
+use hydro_sense::lm35::{Lm35, Lm35Sensor};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn test_basic_conversion() {
+    let lm35 = Lm35::default();
+    common::assert_close(
+        lm35.voltage_to_temperature(volts(0.250)).celsius(),
+        25.0,
+        1e-4,
+    );
+    common::assert_close(
+        lm35.voltage_to_temperature(volts(1.0)).celsius(),
+        100.0,
+        1e-4,
+    );
+    common::assert_close(lm35.output_voltage(celsius(37.5)).volts(), 0.375, 1e-6);
+
+    // Reads 0.4°C high at 20°C and 0.9°C high at 70°C
+    let (gain, offset) = (50.0 / 50.5, 20.0 - 20.4 * 50.0 / 50.5);
+    let calibrated = lm35.with_calibration(gain, offset);
+    common::assert_close(
+        calibrated.voltage_to_temperature(volts(0.204)).celsius(),
+        20.0,
+        1e-3,
+    );
+    common::assert_close(
+        calibrated.voltage_to_temperature(volts(0.709)).celsius(),
+        70.0,
+        1e-3,
+    );
+}
+
+#[test]
+fn test_offset_circuit_reads_below_zero() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Two diodes lift the LM35 ground by about 1.2V, so -10°C      │
+    // │ still gives a positive 1.1V at the single-ended ADC input.   │
+    // └──────────────────────────────────────────────────────────────┘
+    let lm35 = Lm35::default().with_offset_circuit(1.2);
+    common::assert_close(
+        lm35.voltage_to_temperature(volts(1.1)).celsius(),
+        -10.0,
+        1e-4,
+    );
+    common::assert_close(
+        lm35.voltage_to_temperature(volts(1.45)).celsius(),
+        25.0,
+        1e-4,
+    );
+    common::assert_close(lm35.output_voltage(celsius(-5.0)).volts(), 1.15, 1e-6);
+}
+
+#[test]
+fn test_pga_selection() {
+    // 100°C = 1.0V fits 1.024V; a 50°C reservoir fits 0.512V
+    assert_eq!(pga_to_voltage(Lm35::default().pga()), 1.024);
+    let reservoir = Lm35::default().with_max_temperature(50.0);
+    assert_eq!(pga_to_voltage(reservoir.pga()), 0.512);
+    let full = Lm35::default().with_max_temperature(150.0);
+    assert_eq!(pga_to_voltage(full.pga()), 2.048);
+
+    // The lifted ground has to fit as well
+    let offset = Lm35::default().with_offset_circuit(1.2);
+    assert_eq!(pga_to_voltage(offset.pga()), 4.096);
+}
+
+#[test]
+fn test_sensor_reads_celsius() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut().set_voltage(2, 0.2345);
+    let bus = SimBus::new().with(&ads);
+
+    let lm35 = Lm35::default().with_max_temperature(50.0);
+    let mut sensor = Lm35Sensor::new(bus, ADS1115_ADDR_A, Mux::Ain2Gnd, lm35)?;
+    let temperature = sensor.get_temperature()?;
+    log::info!("Simulated LM35DZ temperature: {}", temperature);
+
+    // One LSB at 0.512V full scale is 1.6 m°C
+    common::assert_close(temperature.celsius(), 23.45, 0.002);
+    common::assert_close(ads.borrow().full_scale(), 0.512, 1e-6);
+
+    Ok(())
+}
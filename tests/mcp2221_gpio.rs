@@ -0,0 +1,109 @@
+mod common;
+
+use common::fake_hid::FakeHid;
+use embedded_hal::digital::{InputPin, OutputPin};
+use hydro_sense::mcp2221::*;
+use std::rc::Rc;
+
+#[test]
+fn test_float_switch_and_relay() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let mut hid = FakeHid::default();
+    hid.levels[0] = true; // float switch closed
+
+    let mcp = Mcp2221::new(hid).into_shared();
+    mcp.with(|dev| {
+        dev.configure_gp([
+            GpFunction::Input,
+            GpFunction::Output(false),
+            GpFunction::Adc,
+            GpFunction::Dac,
+        ])
+    })?;
+
+    let mut float_switch = mcp.gpio(0)?;
+    let mut relay = mcp.gpio(1)?;
+    assert!(float_switch.is_high()?);
+
+    relay.set_high()?;
+    assert!(relay.is_high()?);
+    relay.set_low()?;
+    assert!(relay.is_low()?);
+
+    // GP2 is an ADC input, not a digital pin
+    assert!(mcp.gpio(2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_adc_and_dac() -> anyhow::Result<()> {
+    let hid = FakeHid {
+        adc: [0, 512, 1023],
+        ..Default::default()
+    };
+    let sram_reports = Rc::clone(&hid.sram_reports);
+
+    let mcp = Mcp2221::new(hid).into_shared();
+    mcp.with(|dev| {
+        dev.configure_gp([
+            GpFunction::Input,
+            GpFunction::Input,
+            GpFunction::Adc,
+            GpFunction::Dac,
+        ])
+    })?;
+    mcp.with(|dev| dev.set_adc_reference(VoltageRef::Vrm2_048V))?;
+
+    let mut ch = mcp.adc_channel(2)?;
+    assert_eq!(ch.read_raw()?, 512);
+    assert!((ch.read_voltage()? - 1.024).abs() < 1e-6);
+
+    // GP1 is a digital input, not an analog one
+    assert!(mcp.adc_channel(1).is_err());
+
+    // Each DAC write is one SRAM settings report with the alter bit and
+    // the 5-bit value in byte 4; out-of-range values are never sent
+    let dac_report = |value: u8| {
+        let mut report = [0u8; REPORT_LEN];
+        report[0] = CMD_SET_SRAM_SETTINGS;
+        report[4] = SRAM_ALTER | value;
+        report
+    };
+    let mut dac = mcp.dac()?;
+    sram_reports.borrow_mut().clear();
+    dac.set_raw(16)?;
+    assert!(dac.set_raw(DAC_MAX + 1).is_err());
+    dac.set_voltage(5.0)?;
+    assert_eq!(
+        *sram_reports.borrow(),
+        [dac_report(16), dac_report(DAC_MAX)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unsupported_pin_functions() {
+    let mut mcp = Mcp2221::new(FakeHid::default());
+
+    // GP0 has no ADC and GP1 has no DAC
+    assert!(mcp
+        .configure_gp([
+            GpFunction::Adc,
+            GpFunction::Input,
+            GpFunction::Input,
+            GpFunction::Input
+        ])
+        .is_err());
+    assert!(mcp
+        .configure_gp([
+            GpFunction::Input,
+            GpFunction::Dac,
+            GpFunction::Input,
+            GpFunction::Input
+        ])
+        .is_err());
+    assert!(mcp.set_gpio(4, true).is_err());
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::fake_hid::FakeHid;
+use embedded_hal::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource};
+use hydro_sense::df0991::{DFRobotRGBButton, RGBBUTTON_DEFAULT_I2C_ADDR};
+use hydro_sense::mcp2221::*;
+
+#[test]
+fn test_df0991_through_mcp2221() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // Pretend a DF0991 RGB button with its part ID is on the bus
+    let mut hid = FakeHid::new(RGBBUTTON_DEFAULT_I2C_ADDR);
+    hid.regs[0x09] = 0x43;
+    hid.regs[0x0A] = 0xDF;
+    hid.regs[0x04] = 0x01;
+
+    let mut button = DFRobotRGBButton::new(Mcp2221::new(hid), RGBBUTTON_DEFAULT_I2C_ADDR)?;
+    assert!(button.begin()?);
+    assert!(button.get_button_status()?);
+
+    button.set_rgb_color(0x11, 0x22, 0x33)?;
+
+    let hid = button.into_inner().release();
+    assert_eq!(&hid.regs[0x01..0x04], &[0x11, 0x22, 0x33]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_read_uses_repeated_start() -> anyhow::Result<()> {
+    let mut hid = FakeHid::new(0x48);
+    hid.regs[0x00] = 0x12;
+    hid.regs[0x01] = 0x34;
+
+    let mut mcp = Mcp2221::new(hid);
+    let mut buf = [0u8; 2];
+    mcp.write_read(0x48, &[0x00], &mut buf)?;
+    assert_eq!(buf, [0x12, 0x34]);
+
+    let hid = mcp.release();
+    assert!(hid.commands.contains(&CMD_I2C_WRITE_NO_STOP));
+    assert!(hid.commands.contains(&CMD_I2C_READ_REPEATED_START));
+
+    Ok(())
+}
+
+#[test]
+fn test_long_transfers_are_chunked() -> anyhow::Result<()> {
+    let mut mcp = Mcp2221::new(FakeHid::new(0x50));
+
+    // Register pointer followed by 150 data bytes spans three HID reports
+    let mut data = vec![0x00];
+    data.extend((0..150).map(|i| i as u8));
+    mcp.write(0x50, &data)?;
+
+    let mut buf = [0u8; 150];
+    mcp.write_read(0x50, &[0x00], &mut buf)?;
+    assert_eq!(&buf[..], &data[1..]);
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_target_reports_address_nack() {
+    let mut mcp = Mcp2221::new(FakeHid::new(0x48));
+
+    let err = mcp.write(0x49, &[0x01]).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+
+    let mut buf = [0u8; 2];
+    let err = mcp.read(0x49, &mut buf).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+}
+
+#[test]
+fn test_short_read_is_an_error() {
+    let mut hid = FakeHid::new(0x48);
+    hid.short_read = true;
+    let mut mcp = Mcp2221::new(hid);
+
+    // Two bytes asked for, one returned: the buffer must not be trusted
+    let mut buf = [0u8; 2];
+    match mcp.write_read(0x48, &[0x00], &mut buf) {
+        Err(Mcp2221Error::UnexpectedResponse { command, response }) => {
+            assert_eq!(command, CMD_I2C_GET_DATA);
+            assert_eq!(response, 1);
+        }
+        other => panic!("expected a short read error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_bus_speed() -> anyhow::Result<()> {
+    let mut mcp = Mcp2221::new(FakeHid::new(0x48));
+
+    mcp.set_speed(I2cSpeed::Fast)?;
+    assert_eq!(mcp.speed(), I2cSpeed::Fast);
+    assert_eq!(mcp.release().divider, 27);
+
+    Ok(())
+}
+
+#[test]
+fn test_interrupted_transfer_is_cancelled() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let mut hid = FakeHid::new(0x48);
+    hid.stick_on_write = true;
+    let mut mcp = Mcp2221::new(hid);
+    mcp.set_speed(I2cSpeed::Fast)?;
+
+    // Interrupted transaction: the engine never returns to idle
+    assert!(mcp.write(0x48, &[0x01, 0x02]).is_err());
+
+    let events = mcp.recovery_events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].sda_low);
+    assert!(events[0].recovered);
+    assert!(!events[0].reset);
+
+    // The bus works again at the configured speed
+    mcp.write(0x48, &[0x01, 0x02])?;
+    assert_eq!(mcp.release().divider, I2cSpeed::Fast.divider());
+
+    Ok(())
+}
+
+#[test]
+fn test_held_sda_is_clocked_out() -> anyhow::Result<()> {
+    let mut hid = FakeHid::new(0x48);
+    hid.stick_on_write = true;
+    hid.sda_held = 2;
+    let mut mcp = Mcp2221::new(hid);
+
+    // Cancels free the engine, but a target still holds SDA mid-byte
+    assert!(mcp.write(0x48, &[0x01, 0x02]).is_err());
+
+    // Three cancels leave SDA low, then two reads clock it out
+    let event = &mcp.recovery_events()[0];
+    assert!(event.recovered);
+    assert!(!event.reset);
+    assert_eq!(event.attempts, 3 + 2);
+
+    mcp.write(0x48, &[0x01, 0x02])?;
+    assert_eq!(mcp.release().resets, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_stuck_bus_is_recovered() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // An engine that ignores cancels, at settings a reset would drop
+    let mut hid = FakeHid::new(0x48);
+    hid.wedged = true;
+    let mut mcp = Mcp2221::new(hid);
+    mcp.set_speed(I2cSpeed::Fast)?;
+    mcp.configure_gp([
+        GpFunction::Input,
+        GpFunction::Output(true),
+        GpFunction::Adc,
+        GpFunction::Dac,
+    ])?;
+
+    assert!(mcp.write(0x48, &[0x01, 0x02]).is_err());
+
+    let events = mcp.recovery_events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].sda_low);
+    assert!(events[0].reset);
+    assert!(events[0].recovered);
+
+    // The bus works again at the configured speed and GP functions
+    mcp.write(0x48, &[0x01, 0x02])?;
+    let hid = mcp.release();
+    assert_eq!(hid.resets, 1);
+    assert_eq!(hid.divider, I2cSpeed::Fast.divider());
+    assert_eq!(hid.gp_settings, [0x08, 0x10, 0x02, 0x03]);
+
+    Ok(())
+}
+
+#[test]
+fn test_jammed_bus_reports_bus_stuck() {
+    let mut hid = FakeHid::new(0x48);
+    hid.stuck = true;
+    hid.jammed = true;
+    let mut mcp = Mcp2221::new(hid);
+
+    // Not even a chip reset releases the lines
+    let err = mcp.write(0x48, &[0x01]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Bus);
+    assert!(mcp.recovery_events()[0].reset);
+    assert!(!mcp.recovery_events()[0].recovered);
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use common::{celsius, volts};
+use hydro_sense::ads1115::{Mux, ADS1115_ADDR_A};
+use hydro_sense::ph::*;
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use hydro_sense::units::{Ph, Voltage};
+use std::{cell::RefCell, rc::Rc};
+
+fn assert_ph(got: Ph, expected: f32, tolerance: f32) {
+    common::assert_close(got.value(), expected, tolerance);
+}
+
+fn point(ph: f32, voltage: Voltage) -> CalibrationPoint {
+    CalibrationPoint {
+        ph: Ph::new(ph),
+        voltage,
+    }
+}
+
+/// Module voltage of a probe with `efficiency` (%) and `offset_mv` at 25 °C
+fn probe_voltage(ph: f32, efficiency: f32, offset_mv: f32) -> Voltage {
+    let slope = NERNST_SLOPE_25C * 3.0 * efficiency / 100.0;
+    volts(2.5 + offset_mv / 1000.0 * 3.0 + (ph - 7.0) * slope)
+}
+
+#[test]
+fn test_uncalibrated_module() {
+    let probe = Ph4502c::default();
+    assert_ph(probe.voltage_to_ph(volts(2.5), celsius(25.0)), 7.0, 1e-4);
+    assert_ph(
+        probe.voltage_to_ph(probe_voltage(4.0, 100.0, 0.0), celsius(25.0)),
+        4.0,
+        1e-3,
+    );
+    common::assert_close(probe.slope_efficiency(), 100.0, 1e-3);
+    common::assert_close(probe.offset_mv(), 0.0, 1e-3);
+    assert_eq!(probe.health(), ProbeHealth::Good);
+}
+
+#[test]
+fn test_two_point_calibration() -> Result<(), PhError> {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ A slightly aged probe: 95% slope and +10 mV offset. The pH 7 │
+    // │ and pH 4.01 buffers recover both, and samples in between     │
+    // │ read correctly.                                              │
+    // └──────────────────────────────────────────────────────────────┘
+    let calibration = PhCalibration::two_point(
+        CalibrationPoint {
+            ph: BUFFER_PH7,
+            voltage: probe_voltage(BUFFER_PH7.value(), 95.0, 10.0),
+        },
+        CalibrationPoint {
+            ph: BUFFER_PH4,
+            voltage: probe_voltage(BUFFER_PH4.value(), 95.0, 10.0),
+        },
+        celsius(25.0),
+    )?;
+    let probe = Ph4502c::default().with_calibration(calibration);
+
+    common::assert_close(probe.slope_efficiency(), 95.0, 0.01);
+    common::assert_close(probe.offset_mv(), 10.0, 0.01);
+    assert_eq!(probe.health(), ProbeHealth::Good);
+
+    for ph in [5.0, 6.2, 7.0, 8.5, 9.0] {
+        let voltage = probe_voltage(ph, 95.0, 10.0);
+        assert_ph(probe.voltage_to_ph(voltage, celsius(25.0)), ph, 1e-3);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_three_point_calibration() -> Result<(), PhError> {
+    // Different efficiency on the acid (97%) and alkaline (91%) side
+    let acid = |ph: f32| probe_voltage(ph, 97.0, 0.0);
+    let base = |ph: f32| probe_voltage(ph, 91.0, 0.0);
+
+    let calibration = PhCalibration::three_point(
+        [
+            point(10.01, base(10.01)),
+            point(4.01, acid(4.01)),
+            point(7.0, acid(7.0)),
+        ],
+        celsius(25.0),
+    )?;
+    assert_eq!(calibration.anchor.ph, BUFFER_PH7);
+    let probe = Ph4502c::default().with_calibration(calibration);
+
+    assert_ph(probe.voltage_to_ph(acid(5.5), celsius(25.0)), 5.5, 1e-3);
+    assert_ph(probe.voltage_to_ph(base(8.5), celsius(25.0)), 8.5, 1e-3);
+    common::assert_close(probe.slope_efficiency(), 94.0, 0.01);
+
+    Ok(())
+}
+
+#[test]
+fn test_nernst_temperature_compensation() -> Result<(), PhError> {
+    let probe = Ph4502c::default().with_calibration(PhCalibration::two_point(
+        point(7.0, probe_voltage(7.0, 100.0, 0.0)),
+        point(10.01, probe_voltage(10.01, 100.0, 0.0)),
+        celsius(25.0),
+    )?);
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ At 35°C the electrode gives 308.15/298.15 times the 25°C     │
+    // │ slope. Compensation reads pH 9 correctly; the 25°C slope     │
+    // │ would read 9.07.                                             │
+    // └──────────────────────────────────────────────────────────────┘
+    let slope_35c = NERNST_SLOPE_25C * 3.0 * 308.15 / 298.15;
+    let voltage = volts(2.5 + 2.0 * slope_35c);
+    assert_ph(probe.voltage_to_ph(voltage, celsius(35.0)), 9.0, 1e-3);
+    assert_ph(probe.voltage_to_ph(voltage, celsius(25.0)), 9.067, 0.002);
+
+    // Efficiency is judged at 25°C whatever the calibration temperature
+    let warm = PhCalibration::two_point(
+        point(7.0, volts(2.5)),
+        point(4.0, volts(2.5 - 3.0 * slope_35c)),
+        celsius(35.0),
+    )?;
+    common::assert_close(
+        Ph4502c::default().with_calibration(warm).slope_efficiency(),
+        100.0,
+        0.01,
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_probe_health() -> Result<(), PhError> {
+    let calibrate = |efficiency: f32, offset_mv: f32| -> Result<Ph4502c, PhError> {
+        Ok(
+            Ph4502c::default().with_calibration(PhCalibration::two_point(
+                point(7.0, probe_voltage(7.0, efficiency, offset_mv)),
+                point(4.0, probe_voltage(4.0, efficiency, offset_mv)),
+                celsius(25.0),
+            )?),
+        )
+    };
+
+    assert_eq!(calibrate(98.0, -5.0)?.health(), ProbeHealth::Good);
+    assert_eq!(calibrate(90.0, 0.0)?.health(), ProbeHealth::Fair);
+    assert_eq!(calibrate(100.0, 20.0)?.health(), ProbeHealth::Fair);
+    assert_eq!(calibrate(80.0, 0.0)?.health(), ProbeHealth::Replace);
+    assert_eq!(calibrate(97.0, -45.0)?.health(), ProbeHealth::Replace);
+
+    Ok(())
+}
+
+#[test]
+fn test_calibration_errors() {
+    assert_eq!(
+        PhCalibration::two_point(
+            point(7.0, volts(2.5)),
+            point(7.0, volts(2.6)),
+            celsius(25.0)
+        ),
+        Err(PhError::SameBuffer)
+    );
+    assert!(matches!(
+        PhCalibration::two_point(
+            point(7.0, volts(2.5)),
+            point(4.0, volts(2.5)),
+            celsius(25.0)
+        ),
+        Err(PhError::InvalidSlope { .. })
+    ));
+
+    // One side rising, the other falling
+    assert!(matches!(
+        PhCalibration::three_point(
+            [
+                point(4.0, volts(3.0)),
+                point(7.0, volts(2.5)),
+                point(10.0, volts(3.0))
+            ],
+            celsius(25.0)
+        ),
+        Err(PhError::InvalidSlope { .. })
+    ));
+}
+
+#[test]
+fn test_sensor_reads_ph() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut()
+        .set_voltage(1, probe_voltage(6.0, 100.0, 0.0).volts());
+    let bus = SimBus::new().with(&ads);
+
+    let mut sensor = PhSensor::new(bus, ADS1115_ADDR_A, Mux::Ain1Gnd, Ph4502c::default())?;
+    let ph = sensor.get_ph(celsius(25.0))?;
+    log::info!("Simulated PH4502C: {}", ph);
+
+    // One LSB at 6.144V full scale is about 0.001 pH
+    assert_ph(ph, 6.0, 0.002);
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+mod common;
+
+use hydro_sense::calibration::Coefficients;
+use hydro_sense::df0991::GeneralRGBColor;
+use hydro_sense::ph::{Ph4502c, PhCalibration, BUFFER_PH10, BUFFER_PH4, BUFFER_PH7};
+use hydro_sense::ph_calibrator::{
+    CalibrationAbort, CalibrationState, PhCalibrator, RESULT_DISPLAY_TIME,
+};
+use hydro_sense::units::{Ph, Temperature, Voltage};
+use std::time::{Duration, Instant};
+
+type Outcome = Result<PhCalibration, CalibrationAbort>;
+
+const POLL: Duration = Duration::from_millis(100);
+
+fn room() -> Temperature {
+    Temperature::from_celsius(25.0)
+}
+
+fn new_calibrator() -> PhCalibrator {
+    PhCalibrator::new(Ph4502c::default(), room())
+        .with_stability(Voltage::from_millivolts(5.0), Duration::from_secs(5))
+}
+
+/// Poll `calibrator` for `duration` from `*now`, reading `voltage(t)` with
+/// `t` the seconds since the call; stops at the first outcome
+fn poll(
+    calibrator: &mut PhCalibrator,
+    now: &mut Instant,
+    duration: Duration,
+    voltage: impl Fn(f32) -> Option<f32>,
+) -> Option<Outcome> {
+    let start = *now;
+    while now.duration_since(start) < duration {
+        let t = now.duration_since(start).as_secs_f32();
+        let outcome = calibrator.update(*now, voltage(t).map(Voltage::from_volts));
+        *now += POLL;
+        if outcome.is_some() {
+            return outcome;
+        }
+    }
+    None
+}
+
+/// Voltage a module with `efficiency` percent of the ideal slope gives in `buffer`
+fn module_voltage(buffer: Ph, efficiency: f32) -> f32 {
+    let ideal = Ph4502c::default().ideal_slope();
+    2.5 + (buffer.value() - 7.0) * ideal * efficiency / 100.0
+}
+
+#[test]
+fn test_two_point_calibration() {
+    common::init_logger();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ A 95% electrode with a 10 mV offset: the reading settles     │
+    // │ from a transient in each buffer, then is captured. Both      │
+    // │ points give a calibration that reads the buffers back.       │
+    // └──────────────────────────────────────────────────────────────┘
+    let mut calibrator = new_calibrator();
+    let mut now = Instant::now();
+    calibrator.start(now);
+    assert_eq!(calibrator.current_buffer(), Some(BUFFER_PH7));
+    assert_eq!(calibrator.led(now), Some(GeneralRGBColor::Yellow));
+
+    let settle = |target: f32| move |t: f32| Some(target + 0.2 * (-t).exp());
+    for (index, buffer) in [BUFFER_PH7, BUFFER_PH4].into_iter().enumerate() {
+        let target = module_voltage(buffer, 95.0) + 0.03;
+        assert!(matches!(
+            calibrator.state(),
+            CalibrationState::AwaitBuffer { index: i, .. } if i == index
+        ));
+        // Nothing is captured until the button confirms the buffer
+        assert!(poll(
+            &mut calibrator,
+            &mut now,
+            Duration::from_secs(3),
+            settle(target)
+        )
+        .is_none());
+        assert!(calibrator.points().len() == index);
+
+        calibrator.press(now);
+        let blinking = (0..4)
+            .map(|i| calibrator.led(now + Duration::from_millis(250 * i)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            blinking,
+            [
+                Some(GeneralRGBColor::Yellow),
+                Some(GeneralRGBColor::Black),
+                Some(GeneralRGBColor::Yellow),
+                Some(GeneralRGBColor::Black)
+            ]
+        );
+
+        let outcome = poll(
+            &mut calibrator,
+            &mut now,
+            Duration::from_secs(20),
+            settle(target),
+        );
+        if index == 0 {
+            assert!(outcome.is_none());
+            assert!(matches!(
+                calibrator.state(),
+                CalibrationState::AwaitBuffer { index: 1, .. }
+            ));
+        } else {
+            let calibration = outcome.unwrap().unwrap();
+            let ph = calibration.voltage_to_ph(Voltage::from_volts(target), room());
+            assert!((ph.value() - buffer.value()).abs() < 0.02, "{}", ph);
+        }
+        let captured = calibrator.points()[index];
+        assert_eq!(captured.ph, buffer);
+        assert!((captured.voltage.volts() - target).abs() < 0.005);
+    }
+
+    assert_eq!(
+        calibrator.state(),
+        CalibrationState::Finished {
+            success: true,
+            since: now - POLL
+        }
+    );
+    assert_eq!(calibrator.led(now), Some(GeneralRGBColor::Green));
+    let efficiency = calibrator.probe().slope_efficiency();
+    assert!((efficiency - 95.0).abs() < 1.0, "{}", efficiency);
+
+    let record = calibrator.record().unwrap();
+    assert_eq!(record.points.len(), 2);
+    assert!(matches!(record.coefficients, Coefficients::Ph { .. }));
+
+    // The result is shown, then the calibrator goes idle
+    assert!(poll(
+        &mut calibrator,
+        &mut now,
+        RESULT_DISPLAY_TIME + POLL,
+        |_| None
+    )
+    .is_none());
+    assert!(!calibrator.is_active());
+    assert_eq!(calibrator.led(now), None);
+}
+
+#[test]
+fn test_three_buffers() {
+    let mut calibrator = new_calibrator().with_buffers(&[BUFFER_PH7, BUFFER_PH4, BUFFER_PH10]);
+    let mut now = Instant::now();
+    calibrator.start(now);
+
+    let mut outcome = None;
+    for buffer in [BUFFER_PH7, BUFFER_PH4, BUFFER_PH10] {
+        assert_eq!(calibrator.current_buffer(), Some(buffer));
+        calibrator.press(now);
+        let target = module_voltage(buffer, 100.0);
+        outcome = poll(&mut calibrator, &mut now, Duration::from_secs(10), |_| {
+            Some(target)
+        });
+    }
+    let calibration = outcome.unwrap().unwrap();
+    assert!((calibration.acid_slope - calibration.base_slope).abs() < 1e-4);
+    assert_eq!(calibrator.points().len(), 3);
+
+    // 70% and 140% halves average to a plausible 105%, but each is checked
+    let mut calibrator = new_calibrator().with_buffers(&[BUFFER_PH7, BUFFER_PH4, BUFFER_PH10]);
+    calibrator.start(now);
+    for (buffer, efficiency) in [
+        (BUFFER_PH7, 100.0),
+        (BUFFER_PH4, 70.0),
+        (BUFFER_PH10, 140.0),
+    ] {
+        calibrator.press(now);
+        let target = module_voltage(buffer, efficiency);
+        outcome = poll(&mut calibrator, &mut now, Duration::from_secs(10), |_| {
+            Some(target)
+        });
+    }
+    match outcome {
+        Some(Err(CalibrationAbort::ImplausibleSlope { efficiency })) => {
+            assert!((efficiency - 70.0).abs() < 1.0)
+        }
+        other => panic!("expected an implausible slope, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_aborts() {
+    common::init_logger();
+
+    // Nobody presses the button
+    let mut calibrator =
+        new_calibrator().with_timeouts(Duration::from_secs(30), Duration::from_secs(20));
+    let mut now = Instant::now();
+    calibrator.start(now);
+    let outcome = poll(&mut calibrator, &mut now, Duration::from_secs(60), |_| {
+        Some(2.5)
+    });
+    assert_eq!(
+        outcome,
+        Some(Err(CalibrationAbort::InsertTimeout { buffer: BUFFER_PH7 }))
+    );
+    assert_eq!(calibrator.led(now), Some(GeneralRGBColor::Red));
+    assert!(calibrator.record().is_none());
+
+    // A reading that keeps drifting never settles
+    calibrator.start(now);
+    calibrator.press(now);
+    let outcome = poll(&mut calibrator, &mut now, Duration::from_secs(60), |t| {
+        Some(2.5 + 0.002 * t)
+    });
+    assert_eq!(
+        outcome,
+        Some(Err(CalibrationAbort::SettleTimeout { buffer: BUFFER_PH7 }))
+    );
+
+    // Nor does one that cannot be read
+    calibrator.start(now);
+    calibrator.press(now);
+    let outcome = poll(&mut calibrator, &mut now, Duration::from_secs(60), |_| None);
+    assert!(matches!(
+        outcome,
+        Some(Err(CalibrationAbort::SettleTimeout { .. }))
+    ));
+
+    // The same buffer twice gives no slope at all
+    let mut calibrator = new_calibrator();
+    calibrator.start(now);
+    let mut outcome = None;
+    for _ in 0..2 {
+        calibrator.press(now);
+        outcome = poll(&mut calibrator, &mut now, Duration::from_secs(10), |_| {
+            Some(module_voltage(BUFFER_PH7, 100.0))
+        });
+    }
+    assert!(matches!(
+        outcome,
+        Some(Err(CalibrationAbort::Calibration(_)))
+    ));
+
+    // A worn electrode at 70% of the ideal slope
+    let mut calibrator = new_calibrator();
+    calibrator.start(now);
+    for buffer in [BUFFER_PH7, BUFFER_PH4] {
+        calibrator.press(now);
+        outcome = poll(&mut calibrator, &mut now, Duration::from_secs(10), |_| {
+            Some(module_voltage(buffer, 70.0))
+        });
+    }
+    match outcome {
+        Some(Err(CalibrationAbort::ImplausibleSlope { efficiency })) => {
+            assert!((efficiency - 70.0).abs() < 1.0)
+        }
+        other => panic!("expected an implausible slope, got {:?}", other),
+    }
+    // The probe keeps its old calibration
+    assert_eq!(*calibrator.probe(), Ph4502c::default());
+}
+
+#[test]
+fn test_cancel() {
+    let mut calibrator = new_calibrator();
+    let now = Instant::now();
+    assert!(!calibrator.cancel(now));
+
+    calibrator.start(now);
+    calibrator.press(now);
+    assert!(calibrator.cancel(now));
+    assert_eq!(
+        calibrator.state(),
+        CalibrationState::Finished {
+            success: false,
+            since: now
+        }
+    );
+    assert_eq!(calibrator.led(now), Some(GeneralRGBColor::Red));
+
+    // Cancelling again clears the result
+    assert!(!calibrator.cancel(now));
+    assert!(!calibrator.is_active());
+    assert_eq!(
+        CalibrationAbort::Cancelled.to_string(),
+        "calibration cancelled"
+    );
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use hydro_sense::ads1115::{AdsSensor, Mux, Pga, ADS1115_ADDR_A};
+use hydro_sense::ec::{EcMeter, EcSensor};
+use hydro_sense::lm35::{Lm35, Lm35Sensor};
+use hydro_sense::ph::{Ph4502c, PhSensor};
+use hydro_sense::sensor::{Measurement, Quality, Quantity, Sensor};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::{SimBus, SimError};
+use hydro_sense::temperature::{NtcSensor, ThermistorParams};
+use hydro_sense::units::{Temperature, Unit, Voltage};
+use std::{cell::RefCell, rc::Rc};
+
+type BoxedSensor = Box<dyn Sensor<Error = SimError>>;
+
+/// All four ADS1115-backed probes on one simulated converter
+fn reservoir(ads: &Rc<RefCell<SimAds1115>>) -> Result<Vec<BoxedSensor>, SimError> {
+    let bus = || SimBus::new().with(ads);
+    let supply = Voltage::from_volts(5.0);
+    let ntc = NtcSensor::new(
+        bus(),
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain6_144V,
+        supply,
+        ThermistorParams::default(),
+    )?;
+    let mut ph = PhSensor::new(bus(), ADS1115_ADDR_A, Mux::Ain1Gnd, Ph4502c::default())?;
+    ph.set_sample_temperature(Some(Temperature::from_celsius(25.0)));
+    let lm35 = Lm35Sensor::new(bus(), ADS1115_ADDR_A, Mux::Ain2Gnd, Lm35::default())?;
+    let ec = EcSensor::new(bus(), ADS1115_ADDR_A, Mux::Ain3Gnd, EcMeter::default())?;
+
+    Ok(vec![
+        Box::new(ntc),
+        Box::new(ph),
+        Box::new(lm35),
+        Box::new(ec),
+    ])
+}
+
+#[test]
+fn test_every_probe_is_a_sensor() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    {
+        let mut ads = ads.borrow_mut();
+        ads.set_voltage(0, 2.5); // 25°C on the 10k divider
+        ads.set_voltage(1, 2.5); // pH 7
+        ads.set_voltage(2, 0.215); // 21.5°C
+        ads.set_voltage(3, 0.246); // 1.5 mS/cm
+    }
+
+    let mut measurements = Vec::new();
+    for sensor in reservoir(&ads)?.iter_mut() {
+        let measurement = sensor.measure()?;
+        log::info!("{}", measurement);
+        assert_eq!(measurement.sensor_id, sensor.id());
+        assert_eq!(measurement.quantity, sensor.quantity());
+        measurements.push(measurement);
+    }
+
+    let quantities: Vec<Quantity> = measurements.iter().map(|m| m.quantity).collect();
+    assert_eq!(
+        quantities,
+        [
+            Quantity::Temperature,
+            Quantity::Ph,
+            Quantity::Temperature,
+            Quantity::Conductivity
+        ]
+    );
+    common::assert_close(measurements[0].value, 25.0, 0.01);
+    common::assert_close(measurements[1].value, 7.0, 0.002);
+    common::assert_close(measurements[2].value, 21.5, 0.01);
+    common::assert_close(measurements[3].value, 1.5, 0.001);
+    assert_eq!(measurements[3].unit, Unit::MilliSiemensPerCm);
+
+    // No temperature was given to the EC sensor
+    assert!(measurements[..3].iter().all(Measurement::is_good));
+    assert_eq!(measurements[3].quality, Quality::UNCOMPENSATED);
+
+    // Boxed, it still takes one; sensors without compensation ignore it
+    let mut sensors = reservoir(&ads)?;
+    for sensor in sensors.iter_mut() {
+        sensor.set_sample_temperature(Some(Temperature::from_celsius(25.0)));
+    }
+    assert!(sensors[3].measure()?.is_good());
+    assert!(sensors[2].measure()?.is_good());
+
+    Ok(())
+}
+
+#[test]
+fn test_quality_flags() -> anyhow::Result<()> {
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    {
+        let mut ads = ads.borrow_mut();
+        ads.set_voltage(0, 5.0); // unplugged NTC pulled to the supply
+        ads.set_voltage(1, 6.5); // beyond 6.144V full scale
+        ads.set_voltage(2, 0.0); // below 0°C on the basic circuit
+    }
+
+    let mut sensors = reservoir(&ads)?;
+    let ntc = sensors[0].measure()?;
+    assert!(ntc.value.is_nan());
+    assert_eq!(ntc.quality, Quality::PROBE_FAULT);
+
+    let ph = sensors[1].measure()?;
+    assert!(ph
+        .quality
+        .contains(Quality::CLIPPED | Quality::OUT_OF_RANGE));
+    assert_eq!(ph.quality.names(), ["clipped", "out of range"]);
+
+    assert_eq!(sensors[2].measure()?.quality, Quality::OUT_OF_RANGE);
+
+    // The raw ADS1115 channel reports volts
+    let mut raw = AdsSensor::new(
+        SimBus::new().with(&ads),
+        ADS1115_ADDR_A,
+        Mux::Ain1Gnd,
+        Pga::Gain6_144V,
+        "AIN1",
+        Unit::Volts,
+    )?;
+    let voltage = raw.measure()?;
+    assert_eq!(
+        (voltage.quantity, voltage.unit),
+        (Quantity::Voltage, Unit::Volts)
+    );
+    assert_eq!(voltage.quality, Quality::CLIPPED);
+
+    Ok(())
+}
+
+#[test]
+fn test_measurement_display() {
+    let ph = Measurement::new("PH4502C", Quantity::Ph, 6.2);
+    assert_eq!(ph.to_string(), "PH4502C: 6.20 pH");
+
+    let ntc = Measurement::new("NTC Thermistor", Quantity::Temperature, f32::NAN)
+        .with_quality(Quality::PROBE_FAULT);
+    assert_eq!(ntc.to_string(), "NTC Thermistor: NaN °C (probe fault)");
+
+    let ec = Measurement::new("EC Sensor", Quantity::Conductivity, 1.4134)
+        .with_quality(Quality::UNCOMPENSATED);
+    assert_eq!(
+        format!("{:.2}", ec),
+        "EC Sensor: 1.41 mS/cm (uncompensated)"
+    );
+}
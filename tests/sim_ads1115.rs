@@ -0,0 +1,155 @@
+mod common;
+
+use embedded_hal::i2c::I2c;
+use hydro_sense::ads1115::{AdsSensor, Mux, Pga, ADS1115_ADDR_A, CONFIG_REG, CONVERSION_REG};
+use hydro_sense::sim::ads1115::*;
+use hydro_sense::sim::{SimBus, Waveform};
+use hydro_sense::temperature::voltage_to_temperature;
+use hydro_sense::units::{Unit, Voltage};
+use std::{cell::RefCell, rc::Rc, thread, time::Duration};
+
+fn sim_ads() -> (Rc<RefCell<SimAds1115>>, SimBus) {
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    let bus = SimBus::new().with(&ads);
+    (ads, bus)
+}
+
+#[test]
+fn test_ads_sensor_reads_simulated_inputs() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let (ads, bus) = sim_ads();
+    ads.borrow_mut().set_voltage(1, 3.3);
+
+    let mut sensor = AdsSensor::new(
+        bus,
+        ADS1115_ADDR_A,
+        Mux::Ain1Gnd,
+        Pga::Gain4_096V,
+        "AIN1",
+        Unit::Volts,
+    )?;
+    let voltage = sensor.get_voltage()?;
+    assert!((voltage - 3.3).abs() < 0.001, "got {}", voltage);
+
+    // Signals beyond the PGA range clip at full scale
+    ads.borrow_mut().set_voltage(1, 5.0);
+    let mut sensor = AdsSensor::new(
+        sensor.release(),
+        ADS1115_ADDR_A,
+        Mux::Ain1Gnd,
+        Pga::Gain2_048V,
+        "AIN1",
+        Unit::Volts,
+    )?;
+    let voltage = sensor.get_voltage()?;
+    assert!((voltage - 2.048 * 32767.0 / 32768.0).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn test_ntc_temperature_from_simulated_divider() -> anyhow::Result<()> {
+    // 10k NTC at 25 °C on the high side of a 10k divider: half the supply
+    let (ads, bus) = sim_ads();
+    ads.borrow_mut().set_voltage(0, 2.5);
+
+    let mut sensor = AdsSensor::new(
+        bus,
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain6_144V,
+        "NTC",
+        Unit::Volts,
+    )?;
+    let measured = Voltage::from_volts(sensor.get_voltage()?);
+    let temperature = voltage_to_temperature(
+        Voltage::from_volts(5.0),
+        Voltage::from_volts(6.144),
+        measured,
+    );
+    assert!(
+        (temperature.celsius() - 25.0).abs() < 0.05,
+        "got {}",
+        temperature
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_os_bit_tracks_conversion() -> anyhow::Result<()> {
+    let (ads, mut bus) = sim_ads();
+    ads.borrow_mut().set_waveform(
+        0,
+        Waveform::Ramp {
+            start: 0.5,
+            slope: 0.0,
+        },
+    );
+
+    // Single shot, AIN0, 2.048 V, 8 SPS: a conversion takes 125 ms
+    bus.write(ADS1115_ADDR_A, &[CONFIG_REG, 0xC5, 0x03])?;
+
+    let mut config = [0u8; 2];
+    bus.write_read(ADS1115_ADDR_A, &[CONFIG_REG], &mut config)?;
+    assert_eq!(config[0] & 0x80, 0, "OS should read 0 while converting");
+
+    thread::sleep(Duration::from_millis(140));
+    bus.write_read(ADS1115_ADDR_A, &[CONFIG_REG], &mut config)?;
+    assert_eq!(config[0] & 0x80, 0x80, "OS should read 1 when done");
+
+    let mut result = [0u8; 2];
+    bus.write_read(ADS1115_ADDR_A, &[CONVERSION_REG], &mut result)?;
+    assert_eq!(i16::from_be_bytes(result), 8000);
+
+    Ok(())
+}
+
+#[test]
+fn test_differential_mux() -> anyhow::Result<()> {
+    let (ads, mut bus) = sim_ads();
+    ads.borrow_mut().set_voltage(0, 1.0);
+    ads.borrow_mut().set_voltage(1, 1.5);
+
+    // AIN0 - AIN1, 1.024 V, 860 SPS
+    bus.write(ADS1115_ADDR_A, &[CONFIG_REG, 0x87, 0xE3])?;
+    thread::sleep(Duration::from_millis(5));
+
+    let mut result = [0u8; 2];
+    bus.write_read(ADS1115_ADDR_A, &[CONVERSION_REG], &mut result)?;
+    assert_eq!(i16::from_be_bytes(result), -16000);
+
+    Ok(())
+}
+
+#[test]
+fn test_threshold_comparator() -> anyhow::Result<()> {
+    let (ads, mut bus) = sim_ads();
+    ads.borrow_mut().set_voltage(2, 1.0);
+
+    // Thresholds at 0.5 V and 1.5 V (2.048 V range)
+    bus.write(ADS1115_ADDR_A, &[LO_THRESH_REG, 0x1F, 0x40])?;
+    bus.write(ADS1115_ADDR_A, &[HI_THRESH_REG, 0x5D, 0xC0])?;
+    assert_eq!(ads.borrow().thresholds(), (8000, 24000));
+
+    // Continuous, AIN2, 860 SPS, traditional comparator, assert after one conversion
+    bus.write(ADS1115_ADDR_A, &[CONFIG_REG, 0x64, 0xE0])?;
+    thread::sleep(Duration::from_millis(5));
+    assert!(!ads.borrow_mut().alert_asserted());
+    assert!(ads.borrow_mut().alert_pin_high(), "ALERT/RDY is active low");
+
+    ads.borrow_mut().set_voltage(2, 1.8);
+    thread::sleep(Duration::from_millis(5));
+    assert!(ads.borrow_mut().alert_asserted());
+
+    // Traditional mode releases only below Lo_thresh
+    ads.borrow_mut().set_voltage(2, 1.0);
+    thread::sleep(Duration::from_millis(5));
+    assert!(ads.borrow_mut().alert_asserted());
+    ads.borrow_mut().set_voltage(2, 0.2);
+    thread::sleep(Duration::from_millis(5));
+    assert!(!ads.borrow_mut().alert_asserted());
+
+    Ok(())
+}
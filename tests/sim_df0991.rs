@@ -0,0 +1,128 @@
+mod common;
+
+use hydro_sense::df0991::*;
+use hydro_sense::sim::df0991::SimDf0991;
+use hydro_sense::sim::SimBus;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+fn sim_button() -> (Rc<RefCell<SimDf0991>>, DFRobotRGBButton<SimBus>) {
+    let sim = Rc::new(RefCell::new(SimDf0991::new(RGBBUTTON_DEFAULT_I2C_ADDR)));
+    let bus = SimBus::new().with(&sim);
+    let button = DFRobotRGBButton::new(bus, RGBBUTTON_DEFAULT_I2C_ADDR).unwrap();
+    (sim, button)
+}
+
+#[test]
+fn test_driver_against_simulated_button() -> anyhow::Result<()> {
+    common::init_logger();
+
+    let (sim, mut button) = sim_button();
+    assert!(button.begin()?);
+    assert_eq!(button.get_pid()?, RGBBUTTON_PART_ID);
+    assert_eq!(button.get_i2c_addr()?, RGBBUTTON_DEFAULT_I2C_ADDR);
+
+    assert!(!button.get_button_status()?);
+    sim.borrow_mut().press();
+    assert!(button.get_button_status()?);
+    sim.borrow_mut().release();
+    assert!(!button.get_button_status()?);
+
+    // Wrong address is not acknowledged
+    let bus = button.into_inner();
+    let mut other = DFRobotRGBButton::new(bus, 0x23)?;
+    assert!(other.begin().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_led_color_history() -> anyhow::Result<()> {
+    let (sim, mut button) = sim_button();
+
+    button.set_rgb_color_enum(GeneralRGBColor::Red)?;
+    button.set_rgb_color_enum(GeneralRGBColor::Green)?;
+    button.set_rgb_color(0x12, 0x34, 0x56)?;
+    button.set_rgb_color_enum(GeneralRGBColor::Black)?;
+
+    let sim = sim.borrow();
+    assert_eq!(
+        sim.led_colors(),
+        vec![
+            (0xFF, 0x00, 0x00),
+            (0x00, 0xFF, 0x00),
+            (0x12, 0x34, 0x56),
+            (0x00, 0x00, 0x00),
+        ]
+    );
+    assert_eq!(sim.rgb(), (0, 0, 0));
+
+    let history = sim.led_history();
+    assert!(history.windows(2).all(|w| w[0].at <= w[1].at));
+
+    Ok(())
+}
+
+#[test]
+fn test_debounce_with_scripted_bouncy_press() -> anyhow::Result<()> {
+    let (sim, mut button) = sim_button();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Press after 100 ms and hold for 200 ms, bouncing three times │
+    // │ for 3 ms on both edges. Polling much faster than the main    │
+    // │ loop must still yield exactly one press and one release.     │
+    // └──────────────────────────────────────────────────────────────┘
+    let mut debouncer = ButtonDebouncer::new(button.get_button_status()?, Instant::now());
+    sim.borrow_mut().script_press(
+        Duration::from_millis(100),
+        Duration::from_millis(200),
+        3,
+        Duration::from_millis(3),
+    );
+
+    let start = Instant::now();
+    let mut raw_changes = 0;
+    let mut last_raw = false;
+    let mut accepted = Vec::new();
+    while start.elapsed() < Duration::from_millis(500) {
+        let raw = button.get_button_status()?;
+        if raw != last_raw {
+            raw_changes += 1;
+            last_raw = raw;
+        }
+        if let Some(pressed) = debouncer.update(raw, Instant::now()) {
+            accepted.push(pressed);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    assert!(raw_changes > 2, "bounces were not visible: {}", raw_changes);
+    assert_eq!(accepted, vec![true, false]);
+    assert!(!debouncer.is_pressed());
+
+    Ok(())
+}
+
+#[test]
+fn test_short_and_long_presses() {
+    let start = Instant::now();
+    let at = |ms: u64| start + Duration::from_millis(ms);
+    let mut detector = PressDetector::default().with_long_press(Duration::from_millis(1000));
+
+    // Quick press and release
+    assert_eq!(detector.update(true, at(0)), None);
+    assert_eq!(detector.update(true, at(300)), None);
+    assert_eq!(detector.update(false, at(400)), Some(ButtonEvent::Short));
+    assert_eq!(detector.update(false, at(500)), None);
+
+    // Held: reported once while still held, release swallowed
+    assert_eq!(detector.update(true, at(1000)), None);
+    assert_eq!(detector.update(true, at(1999)), None);
+    assert_eq!(detector.update(true, at(2000)), Some(ButtonEvent::Long));
+    assert_eq!(detector.update(true, at(3500)), None);
+    assert_eq!(detector.update(false, at(4000)), None);
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use hydro_sense::ads1115::{AdsSensor, Mux, Pga, ADS1115_ADDR_A};
+use hydro_sense::sim::ads1115::SimAds1115;
+use hydro_sense::sim::SimBus;
+use hydro_sense::stability::{Stability, StabilityDetector};
+use hydro_sense::units::Unit;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+const POLL: Duration = Duration::from_millis(100);
+
+/// Feed `value(t)` every 100 ms for `duration`, `t` in seconds from the
+/// start; returns the last state
+fn feed(
+    detector: &mut StabilityDetector,
+    start: Instant,
+    duration: Duration,
+    value: impl Fn(f32) -> f32,
+) -> (Instant, Stability) {
+    let mut now = start;
+    let mut state = Stability::Settling;
+    while now.duration_since(start) <= duration {
+        state = detector.push(now, value(now.duration_since(start).as_secs_f32()));
+        if state != Stability::Settling {
+            break;
+        }
+        now += POLL;
+    }
+    (now, state)
+}
+
+#[test]
+fn test_settles_after_transient() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ A probe moved into a new solution: the reading decays from   │
+    // │ 100 mV off with a 2 s time constant, with ±1 mV of noise.    │
+    // │ It counts as stable once a whole 3 s window fits in the 5 mV │
+    // │ band, about 9.5 s in.                                        │
+    // └──────────────────────────────────────────────────────────────┘
+    let mut detector = StabilityDetector::new(0.005, Duration::from_secs(3));
+    let start = Instant::now();
+    let settling = |t: f32| 1.0 + 0.1 * (-t / 2.0).exp() + 0.001 * (t * 7.0).sin();
+    let (at, state) = feed(&mut detector, start, Duration::from_secs(30), settling);
+
+    let settled_after = at.duration_since(start).as_secs_f32();
+    assert!(
+        (8.5..=10.5).contains(&settled_after),
+        "stable after {} s",
+        settled_after
+    );
+    match state {
+        Stability::Stable { mean } => common::assert_close(mean, 1.0, 0.005),
+        other => panic!("expected stable, got {:?}", other),
+    }
+    assert!(detector.spread().unwrap() <= 0.005);
+    assert!(state.is_stable());
+}
+
+#[test]
+fn test_drift_rate() {
+    let mut detector = StabilityDetector::new(0.01, Duration::from_secs(5));
+    let start = Instant::now();
+    assert_eq!(detector.drift_rate(), None);
+
+    // 3 mV/s ramp: never within 10 mV over 5 s
+    let (at, state) = feed(&mut detector, start, Duration::from_secs(20), |t| {
+        2.5 + 0.003 * t
+    });
+    assert_eq!(state, Stability::Settling);
+    common::assert_close(detector.drift_rate().unwrap(), 0.003, 1e-5);
+    common::assert_close(detector.spread().unwrap(), 0.015, 1e-4);
+
+    // A level reading has no drift
+    let (_, state) = feed(&mut detector, at, Duration::from_secs(10), |_| 2.6);
+    assert!(state.is_stable());
+    common::assert_close(detector.drift_rate().unwrap(), 0.0, 1e-6);
+}
+
+#[test]
+fn test_max_wait() {
+    let mut detector = StabilityDetector::new(0.001, Duration::from_secs(2))
+        .with_max_wait(Duration::from_secs(10));
+    let start = Instant::now();
+
+    // Noise of ±5 mV never fits in a 1 mV band
+    let (at, state) = feed(&mut detector, start, Duration::from_secs(30), |t| {
+        1.0 + 0.005 * (t * 3.0).sin()
+    });
+    assert_eq!(state, Stability::TimedOut);
+    assert_eq!(at.duration_since(start), Duration::from_secs(10));
+    assert_eq!(state.to_string(), "did not settle");
+
+    // Failed reads still time out once started
+    detector.start(at);
+    assert_eq!(
+        detector.status(at + Duration::from_secs(9)),
+        Stability::Settling
+    );
+    assert_eq!(
+        detector.status(at + Duration::from_secs(10)),
+        Stability::TimedOut
+    );
+
+    // Unreadable values are skipped
+    detector.reset();
+    let (_, state) = feed(&mut detector, at, Duration::from_secs(5), |t| {
+        if t < 1.0 {
+            f32::NAN
+        } else {
+            1.0
+        }
+    });
+    assert!(state.is_stable());
+}
+
+#[test]
+fn test_stale_readings_do_not_settle() {
+    let mut detector = StabilityDetector::new(0.01, Duration::from_secs(2));
+    let start = Instant::now();
+
+    // One reading followed by failed reads only
+    assert_eq!(detector.push(start, 2.5), Stability::Settling);
+    for polls in 1..=50 {
+        assert_eq!(detector.status(start + POLL * polls), Stability::Settling);
+    }
+
+    // A settled probe falls back to settling when the reads stop
+    detector.reset();
+    let (at, state) = feed(&mut detector, start, Duration::from_secs(5), |_| 2.5);
+    assert!(state.is_stable());
+    assert!(detector.status(at + POLL).is_stable());
+    assert_eq!(detector.status(at + POLL * 2), Stability::Settling);
+    assert_eq!(
+        detector.status(at + Duration::from_secs(10)),
+        Stability::Settling
+    );
+}
+
+#[test]
+fn test_wait_on_simulated_ads1115() -> anyhow::Result<()> {
+    common::init_logger();
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Wait on a real (simulated) channel that settles from 0.5 V   │
+    // │ to 2.0 V with a 50 ms time constant.                         │
+    // └──────────────────────────────────────────────────────────────┘
+    let ads = Rc::new(RefCell::new(SimAds1115::new(ADS1115_ADDR_A)));
+    ads.borrow_mut()
+        .set_input(0, |t| 2.0 - 1.5 * (-t / 0.05).exp());
+    let mut sensor = AdsSensor::new(
+        SimBus::new().with(&ads),
+        ADS1115_ADDR_A,
+        Mux::Ain0Gnd,
+        Pga::Gain4_096V,
+        "AIN0",
+        Unit::Volts,
+    )?;
+
+    let mut detector = StabilityDetector::new(0.002, Duration::from_millis(100))
+        .with_max_wait(Duration::from_secs(5));
+    let started = Instant::now();
+    let state = detector.wait(Duration::from_millis(5), || sensor.get_voltage())?;
+    log::info!("{} after {:?}", state, started.elapsed());
+
+    match state {
+        Stability::Stable { mean } => common::assert_close(mean, 2.0, 0.003),
+        other => panic!("expected stable, got {:?}", other),
+    }
+    assert!(started.elapsed() >= Duration::from_millis(300));
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::volts;
+use hydro_sense::temperature::{
+    try_voltage_to_temperature, RtTable, TemperatureError, ThermistorParams, Topology,
+};
+
+#[test]
+fn test_valid_reading() {
+    let temperature = try_voltage_to_temperature(volts(5.0), volts(6.144), volts(2.5)).unwrap();
+    assert!((temperature.celsius() - 25.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_open_and_short_high_side() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ High side: the ADC reads the thermistor end of the divider.  │
+    // │ Unplugged, the fixed resistor pulls it to the supply rail;   │
+    // │ shorted, it sits at 0 V.                                     │
+    // └──────────────────────────────────────────────────────────────┘
+    assert_eq!(
+        try_voltage_to_temperature(volts(5.0), volts(6.144), volts(4.99)),
+        Err(TemperatureError::OpenCircuit {
+            voltage: volts(4.99)
+        })
+    );
+    assert_eq!(
+        try_voltage_to_temperature(volts(5.0), volts(6.144), volts(0.0)),
+        Err(TemperatureError::ShortCircuit {
+            voltage: volts(0.0)
+        })
+    );
+    assert_eq!(
+        try_voltage_to_temperature(volts(5.0), volts(6.144), volts(0.02)),
+        Err(TemperatureError::ShortCircuit {
+            voltage: volts(0.02)
+        })
+    );
+}
+
+#[test]
+fn test_open_and_short_low_side() {
+    let low = ThermistorParams {
+        topology: Topology::LowSide,
+        ..ThermistorParams::default()
+    };
+
+    assert!(matches!(
+        low.try_voltage_to_temperature(volts(3.3), volts(4.096), volts(0.01)),
+        Err(TemperatureError::OpenCircuit { .. })
+    ));
+    assert!(matches!(
+        low.try_voltage_to_temperature(volts(3.3), volts(4.096), volts(3.3)),
+        Err(TemperatureError::ShortCircuit { .. })
+    ));
+    assert!(low
+        .try_voltage_to_temperature(volts(3.3), volts(4.096), volts(1.65))
+        .is_ok());
+}
+
+#[test]
+fn test_over_pga_range() {
+    // 2.048V full scale clips a 5V divider at its highest code
+    let clipped = 2.048 * 32767.0 / 32768.0;
+    assert_eq!(
+        try_voltage_to_temperature(volts(5.0), volts(2.048), volts(clipped)),
+        Err(TemperatureError::OverRange {
+            voltage: volts(clipped),
+            full_scale: volts(2.048),
+            topology: Topology::HighSide,
+        })
+    );
+    assert!(try_voltage_to_temperature(volts(5.0), volts(2.048), volts(2.0)).is_ok());
+}
+
+#[test]
+fn test_faults_with_pga_below_supply() {
+    let low = ThermistorParams {
+        topology: Topology::LowSide,
+        ..ThermistorParams::default()
+    };
+
+    // 4.096V full scale hides the 5V rail: an open high-side or shorted
+    // low-side probe clips just like a reading beyond full scale
+    let clipped = 4.096 * 32767.0 / 32768.0;
+    let high_error = try_voltage_to_temperature(volts(5.0), volts(4.096), volts(clipped));
+    assert!(matches!(
+        high_error,
+        Err(TemperatureError::OverRange { .. })
+    ));
+    assert!(high_error.unwrap_err().to_string().contains("disconnected"));
+    let low_error = low.try_voltage_to_temperature(volts(5.0), volts(4.096), volts(clipped));
+    assert!(matches!(low_error, Err(TemperatureError::OverRange { .. })));
+    assert!(low_error.unwrap_err().to_string().contains("shorted"));
+
+    // The ground rail stays in range
+    assert_eq!(
+        try_voltage_to_temperature(volts(5.0), volts(4.096), volts(0.0)),
+        Err(TemperatureError::ShortCircuit {
+            voltage: volts(0.0)
+        })
+    );
+    assert_eq!(
+        low.try_voltage_to_temperature(volts(5.0), volts(4.096), volts(0.0)),
+        Err(TemperatureError::OpenCircuit {
+            voltage: volts(0.0)
+        })
+    );
+
+    // A 4.1V supply sits within the rail margin of 4.096V full scale,
+    // so clipping there is the supply rail
+    assert_eq!(
+        try_voltage_to_temperature(volts(4.1), volts(4.096), volts(clipped)),
+        Err(TemperatureError::OpenCircuit {
+            voltage: volts(clipped)
+        })
+    );
+    assert_eq!(
+        low.try_voltage_to_temperature(volts(4.1), volts(4.096), volts(clipped)),
+        Err(TemperatureError::ShortCircuit {
+            voltage: volts(clipped)
+        })
+    );
+}
+
+#[test]
+fn test_outside_physical_range() {
+    // 0.1V of 5V: 204Ω on a 10k B3950 probe is well above 125°C
+    match try_voltage_to_temperature(volts(5.0), volts(6.144), volts(0.1)) {
+        Err(TemperatureError::OutOfRange { resistance }) => {
+            assert!((resistance - 204.08).abs() < 0.1, "got {}", resistance)
+        }
+        other => panic!("expected out of range, got {:?}", other),
+    }
+
+    // Readings beyond an R-T table are out of range too
+    let table = RtTable::new(vec![(20.0, 12_493.0), (30.0, 8_056.0)]).unwrap();
+    let params = ThermistorParams::default().with_table(table);
+    assert!(matches!(
+        params.try_voltage_to_temperature(volts(5.0), volts(6.144), volts(2.5)),
+        Ok(t) if (t.celsius() - 25.07).abs() < 0.01
+    ));
+    assert!(matches!(
+        params.try_voltage_to_temperature(volts(5.0), volts(6.144), volts(1.0)),
+        Err(TemperatureError::OutOfRange { .. })
+    ));
+}
+
+#[test]
+fn test_error_messages() {
+    let open = TemperatureError::OpenCircuit {
+        voltage: volts(4.995),
+    };
+    assert_eq!(open.to_string(), "probe disconnected (4.995 V)");
+    let short = TemperatureError::ShortCircuit {
+        voltage: volts(0.0),
+    };
+    assert_eq!(short.to_string(), "probe shorted (0.000 V)");
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use common::volts;
+use hydro_sense::temperature::{
+    voltage_to_temperature, RtTable, SteinhartHart, ThermistorModel, ThermistorParams, Topology,
+};
+use hydro_sense::units::Temperature;
+use std::io;
+
+fn assert_celsius(got: Temperature, expected: f32, tolerance: f32) {
+    common::assert_close(got.celsius(), expected, tolerance);
+}
+
+#[test]
+fn test_beta_known_points() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Points on the Beta curve of a 10k B3950 NTC:                 │
+    // │   0°C = 33.62kΩ, 25°C = 10kΩ, 50°C = 3.588kΩ                 │
+    // └──────────────────────────────────────────────────────────────┘
+    let ntc = ThermistorParams::default();
+    assert_celsius(ntc.resistance_to_temperature(10_000.0), 25.0, 1e-3);
+    assert_celsius(ntc.resistance_to_temperature(33_620.0), 0.0, 0.05);
+    assert_celsius(ntc.resistance_to_temperature(3_588.0), 50.0, 0.1);
+
+    // The same curve scaled to a 100k NTC
+    let ntc_100k = ThermistorParams {
+        r0: 100_000.0,
+        r_fixed: 100_000.0,
+        ..ThermistorParams::default()
+    };
+    assert_celsius(ntc_100k.resistance_to_temperature(100_000.0), 25.0, 1e-3);
+    assert_celsius(ntc_100k.resistance_to_temperature(336_200.0), 0.0, 0.05);
+
+    // A different reference temperature and Beta
+    let ntc_b3435 = ThermistorParams {
+        t0: 20.0,
+        beta: 3435.0,
+        ..ThermistorParams::default()
+    };
+    assert_celsius(ntc_b3435.resistance_to_temperature(10_000.0), 20.0, 1e-3);
+}
+
+#[test]
+fn test_divider_topologies() {
+    let high = ThermistorParams::default();
+    let low = ThermistorParams {
+        topology: Topology::LowSide,
+        ..ThermistorParams::default()
+    };
+
+    // Equal resistors put half the supply on the ADC either way
+    common::assert_close(high.resistance(volts(5.0), volts(2.5)), 10_000.0, 0.1);
+    common::assert_close(low.resistance(volts(5.0), volts(2.5)), 10_000.0, 0.1);
+
+    // 1V of 5V: the thermistor takes 1/5 (high side) or 4/5 (low side)
+    common::assert_close(high.resistance(volts(5.0), volts(1.0)), 2_500.0, 0.1);
+    common::assert_close(low.resistance(volts(5.0), volts(1.0)), 40_000.0, 0.5);
+
+    // 47k pull-up with a 100k NTC on the low side at 25°C
+    let ntc_100k = ThermistorParams {
+        r0: 100_000.0,
+        r_fixed: 47_000.0,
+        topology: Topology::LowSide,
+        ..ThermistorParams::default()
+    };
+    let divider = 3.3 * 47_000.0 / 147_000.0;
+    assert_celsius(
+        ntc_100k.voltage_to_temperature(volts(3.3), volts(4.096), volts(divider)),
+        25.0,
+        0.01,
+    );
+}
+
+#[test]
+fn test_invalid_inputs_are_nan() {
+    let ntc = ThermistorParams::default();
+    assert!(ntc
+        .voltage_to_temperature(volts(5.0), volts(6.144), volts(0.0))
+        .is_nan());
+    assert!(ntc
+        .voltage_to_temperature(volts(5.0), volts(6.144), volts(5.0))
+        .is_nan());
+    assert!(ntc
+        .voltage_to_temperature(volts(5.0), volts(2.048), volts(2.5))
+        .is_nan());
+    assert!(ntc.resistance_to_temperature(-1.0).is_nan());
+
+    // The free function keeps the original 10k high-side defaults
+    assert_celsius(
+        voltage_to_temperature(volts(5.0), volts(6.144), volts(2.5)),
+        25.0,
+        1e-3,
+    );
+}
+
+#[test]
+fn test_steinhart_hart_three_point_fit() {
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ Resistances of a 10k NTC with A = 1.129148e-3,               │
+    // │ B = 2.34125e-4 and C = 8.76741e-8 at 5, 20 and 35°C. The fit │
+    // │ must recover the coefficients and hold outside the points.   │
+    // └──────────────────────────────────────────────────────────────┘
+    let sh = SteinhartHart::fit([(5.0, 25_394.62), (20.0, 12_493.15), (35.0, 6_530.19)])
+        .expect("valid calibration points");
+    assert!((sh.a - 1.129148e-3).abs() < 1e-8, "A = {}", sh.a);
+    assert!((sh.b - 2.34125e-4).abs() < 1e-9, "B = {}", sh.b);
+    assert!((sh.c - 8.76741e-8).abs() < 1e-10, "C = {}", sh.c);
+
+    assert_celsius(sh.temperature(25_394.62), 5.0, 1e-3);
+    assert_celsius(sh.temperature(32_650.37), 0.0, 0.01);
+    assert_celsius(sh.temperature(9_999.85), 25.0, 0.01);
+    assert_celsius(sh.temperature(3_601.03), 50.0, 0.01);
+
+    // Selected per sensor in place of the Beta equation
+    let beta = ThermistorParams::default();
+    let calibrated = ThermistorParams::default().with_steinhart_hart(sh);
+    assert_eq!(calibrated.model, ThermistorModel::SteinhartHart(sh));
+    assert_celsius(
+        calibrated.voltage_to_temperature(volts(5.0), volts(6.144), volts(2.5)),
+        25.0,
+        0.01,
+    );
+
+    // 12.49kΩ is 20°C on this probe; the generic B3950 curve is off
+    let r_20 = 12_493.15;
+    assert_celsius(calibrated.resistance_to_temperature(r_20), 20.0, 1e-3);
+    assert!((beta.resistance_to_temperature(r_20).celsius() - 20.0).abs() > 0.05);
+}
+
+#[test]
+fn test_steinhart_hart_rejects_bad_points() {
+    assert!(SteinhartHart::fit([(5.0, 25_000.0), (5.0, 12_000.0), (35.0, 6_500.0)]).is_none());
+    assert!(SteinhartHart::fit([(5.0, 25_000.0), (20.0, 25_000.0), (35.0, 6_500.0)]).is_none());
+    assert!(SteinhartHart::fit([(5.0, 25_000.0), (20.0, 0.0), (35.0, 6_500.0)]).is_none());
+}
+
+fn ntc_10k_table() -> RtTable {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tables/ntc_10k.csv");
+    RtTable::load(path).expect("Failed to load R-T table")
+}
+
+#[test]
+fn test_rt_table_interpolation() {
+    let table = ntc_10k_table();
+    assert_eq!(table.points().len(), 15);
+
+    // Table rows come back exactly
+    assert_celsius(table.temperature(10_000.0).unwrap(), 25.0, 1e-4);
+    assert_celsius(table.temperature(55_303.0).unwrap(), -10.0, 1e-4);
+    assert_celsius(table.temperature(2_487.0).unwrap(), 60.0, 1e-4);
+
+    // ┌──────────────────────────────────────────────────────────────┐
+    // │ The probe reads 11.168kΩ at 22.5°C. Log-linear interpolation │
+    // │ between 20°C and 25°C lands within 0.02°C; linear in R would │
+    // │ be 0.16°C off.                                               │
+    // └──────────────────────────────────────────────────────────────┘
+    assert_celsius(table.temperature(11_168.0).unwrap(), 22.5, 0.02);
+
+    // Selected as the thermistor model
+    let params = ThermistorParams::default().with_table(table);
+    assert!(matches!(params.model, ThermistorModel::Table(_)));
+    assert_celsius(
+        params.voltage_to_temperature(volts(5.0), volts(6.144), volts(2.5)),
+        25.0,
+        1e-3,
+    );
+}
+
+#[test]
+fn test_rt_table_out_of_range() {
+    let table = ntc_10k_table();
+
+    let err = table.temperature(60_000.0).unwrap_err();
+    assert_eq!((err.min, err.max), (2_487.0, 55_303.0));
+    assert!(table.temperature(2_000.0).is_err());
+    assert!(table.temperature(f32::NAN).is_err());
+
+    let params = ThermistorParams::default().with_table(table);
+    assert!(params.resistance_to_temperature(100.0).is_nan());
+}
+
+#[test]
+fn test_rt_table_rejects_bad_csv() {
+    // Header and comments are fine, order does not matter
+    let csv = "temp_c,ohms\n# comment\n30,8056\n20,12493\n25,10000\n";
+    let table = RtTable::from_csv(csv.as_bytes()).unwrap();
+    assert_eq!(table.points()[0], (30.0, 8_056.0));
+
+    assert!(RtTable::from_csv("20,12493\n25,oops\n".as_bytes()).is_err());
+
+    // Only one line of column names, and only before the points
+    let bad = |csv: &str| RtTable::from_csv(csv.as_bytes()).unwrap_err().kind();
+    assert_eq!(
+        bad("temp_c,ohms\nunits,units\n20,12493\n25,10000\n"),
+        io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+        bad("20,12493\ntemp_c,ohms\n25,10000\n"),
+        io::ErrorKind::InvalidData
+    );
+    assert!(RtTable::from_csv("20,12493\n".as_bytes()).is_err());
+    assert!(RtTable::from_csv("20,12493\n25,-5\n".as_bytes()).is_err());
+    assert!(RtTable::from_csv("20,12493\n25,12493\n".as_bytes()).is_err());
+}
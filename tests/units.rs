@@ -0,0 +1,46 @@
+mod common;
+
+use hydro_sense::units::{Conductivity, Ph, Temperature, Unit, Voltage};
+
+#[test]
+fn test_temperature_conversions() {
+    let room = Temperature::from_celsius(25.0);
+    common::assert_close(room.fahrenheit(), 77.0, 1e-4);
+    common::assert_close(room.kelvin(), 298.15, 1e-4);
+
+    common::assert_close(Temperature::from_fahrenheit(32.0).celsius(), 0.0, 1e-5);
+    common::assert_close(Temperature::from_fahrenheit(-40.0).celsius(), -40.0, 1e-5);
+    common::assert_close(Temperature::from_kelvin(273.15).celsius(), 0.0, 1e-5);
+
+    common::assert_close(room.value_in(Unit::Fahrenheit), 77.0, 1e-4);
+    common::assert_close(room.value_in(Unit::Celsius), 25.0, 1e-6);
+    assert!(Temperature::from_celsius(18.0) < room);
+}
+
+#[test]
+fn test_temperature_formatting() {
+    let water = Temperature::from_celsius(21.5);
+    assert_eq!(water.to_string(), "21.50 °C");
+    assert_eq!(format!("{:.1}", water), "21.5 °C");
+    assert_eq!(format!("{:.1}", water.display(Unit::Fahrenheit)), "70.7 °F");
+    assert_eq!(water.display(Unit::Kelvin).to_string(), "294.65 K");
+
+    // Units other than temperatures fall back to Celsius
+    assert_eq!(water.display(Unit::Volts).to_string(), "21.50 °C");
+}
+
+#[test]
+fn test_other_quantities() {
+    let voltage = Voltage::from_millivolts(250.0);
+    common::assert_close(voltage.volts(), 0.25, 1e-6);
+    assert_eq!(voltage.to_string(), "0.250 V");
+
+    assert_eq!(Ph::new(6.2).to_string(), "pH 6.20");
+    assert_eq!(format!("{:.1}", Ph::new(6.24)), "pH 6.2");
+
+    let ec = Conductivity::from_us_per_cm(1413.0);
+    common::assert_close(ec.ms_per_cm(), 1.413, 1e-6);
+    assert_eq!(ec.to_string(), "1.413 mS/cm");
+
+    assert_eq!(Unit::Ppm.to_string(), "ppm");
+}